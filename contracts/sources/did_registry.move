@@ -9,6 +9,7 @@ use sui::event;
 use sui::clock::{Self, Clock};
 use sui::url::{Self, Url};
 use std::string::{Self, String};
+use suiverify::enclave::{Self, Enclave};
 
 // Error codes
 const EInvalidCap: u64 = 1;
@@ -26,6 +27,19 @@ const STATUS_PENDING: u8 = 0;
 const STATUS_VERIFIED: u8 = 1;
 const STATUS_REJECTED: u8 = 2;
 
+// Intent scope of the Nautilus signature on a DID (IntentScope::DIDIssuance)
+const DID_ISSUANCE_INTENT: u8 = 2;
+
+/// Payload of the Nautilus signature on a DID, signed as
+/// `enclave::IntentMessage<DIDIssuance>` under `DID_ISSUANCE_INTENT` with
+/// `signature_timestamp_ms`. The document class and nullifier are leaves of
+/// `evidence_hash`, opened through the enclave's `/open_evidence`.
+public struct DIDIssuance has drop {
+    owner: address,
+    did_type: u8,
+    evidence_hash: vector<u8>,
+}
+
 /// User's DID record with verification status
 public struct UserDID has key, store {
     id: UID,
@@ -71,11 +85,11 @@ public struct DIDSoulBoundNFT has key {
     image_url: Url,
     /// Walrus blob ID reference (stored in metadata)
     blob_id: String,
-    /// Nautilus TEE signature for verification attestation
+    /// Nautilus TEE signature over `DIDIssuance { owner, did_type, evidence_hash }`
     nautilus_signature: vector<u8>,
     /// When signature was created in Nautilus (for SDK verification)
     signature_timestamp_ms: u64,
-    /// OCR data hash from Python (for SDK verification)
+    /// Evidence commitment (Merkle root) from the enclave
     evidence_hash: vector<u8>,
     /// When the DID expires
     expiry_epoch: u64,
@@ -349,6 +363,22 @@ public fun claim_did_nft(
     nft_id
 }
 
+/// Check a DID NFT's Nautilus signature against a registered enclave
+public fun verify_nft_signature<T>(nft: &DIDSoulBoundNFT, enclave: &Enclave<T>): bool {
+    let payload = DIDIssuance {
+        owner: nft.owner,
+        did_type: nft.did_type,
+        evidence_hash: nft.evidence_hash,
+    };
+    enclave::verify_signature(
+        enclave,
+        DID_ISSUANCE_INTENT,
+        nft.signature_timestamp_ms,
+        payload,
+        &nft.nautilus_signature,
+    )
+}
+
 /// Check if user has a verified DID of specific type
 public fun has_verified_did(
     registry: &DIDRegistry,
@@ -0,0 +1,104 @@
+#!/usr/bin/env python3
+"""Regenerate the synthetic Aadhaar fixtures signed with a throwaway test certificate.
+
+Requires openssl and zip on PATH. Never use these keys outside tests.
+"""
+import base64
+import gzip
+import hashlib
+import os
+import subprocess
+import xml.etree.ElementTree as ET
+
+HERE = os.path.dirname(os.path.abspath(__file__))
+KEY = os.path.join(HERE, "test_uidai_key.pem")
+CERT = os.path.join(HERE, "test_uidai_cert.pem")
+SHARE_CODE = "1234"
+MOBILE = "9876543210"
+EMAIL = "resident@example.com"
+REFERENCE_ID = "123420190101120000000"  # last Aadhaar digit 4 -> 4 hash rounds
+DSIG = "http://www.w3.org/2000/09/xmldsig#"
+
+
+def sign(data: bytes, digest: str) -> bytes:
+    return subprocess.run(
+        ["openssl", "dgst", f"-{digest}", "-sign", KEY],
+        input=data, capture_output=True, check=True,
+    ).stdout
+
+
+def hash_chain(value: str) -> str:
+    for _ in range(max(int(REFERENCE_ID[3]), 1)):
+        value = hashlib.sha256(value.encode()).hexdigest()
+    return value
+
+
+def c14n(xml: str) -> bytes:
+    return ET.canonicalize(xml_data=xml).encode()
+
+
+def offline_ekyc():
+    photo = base64.b64encode(open(os.path.join(HERE, "..", "media", "valid.jpg"), "rb").read()).decode()
+    body = (
+        f'<UidData>'
+        f'<Poi dob="01-01-1990" e="{hash_chain(EMAIL + SHARE_CODE)}" gender="M" '
+        f'm="{hash_chain(MOBILE + SHARE_CODE)}" name="Test Resident"/>'
+        f'<Poa careof="S/O Test" country="India" dist="Bengaluru" house="12" landmark="Park" '
+        f'loc="Indiranagar" pc="560038" po="Indiranagar" state="Karnataka" street="1st Main" '
+        f'subdist="Bengaluru East" vtc="Bengaluru"/>'
+        f'<Pht>{photo}</Pht>'
+        f'</UidData>'
+    )
+    unsigned = f'<OfflinePaperlessKyc referenceId="{REFERENCE_ID}">{body}</OfflinePaperlessKyc>'
+    digest = base64.b64encode(hashlib.sha256(c14n(unsigned)).digest()).decode()
+    signed_info = (
+        f'<SignedInfo xmlns="{DSIG}">'
+        f'<CanonicalizationMethod Algorithm="http://www.w3.org/TR/2001/REC-xml-c14n-20010315"></CanonicalizationMethod>'
+        f'<SignatureMethod Algorithm="http://www.w3.org/2001/04/xmldsig-more#rsa-sha256"></SignatureMethod>'
+        f'<Reference URI=""><Transforms>'
+        f'<Transform Algorithm="http://www.w3.org/2000/09/xmldsig#enveloped-signature"></Transform>'
+        f'</Transforms>'
+        f'<DigestMethod Algorithm="http://www.w3.org/2001/04/xmlenc#sha256"></DigestMethod>'
+        f'<DigestValue>{digest}</DigestValue></Reference></SignedInfo>'
+    )
+    signature = base64.b64encode(sign(c14n(signed_info), "sha256")).decode()
+    signed_info_inner = signed_info.replace(f' xmlns="{DSIG}"', "", 1)
+    xml = (
+        f'<?xml version="1.0" encoding="UTF-8" standalone="yes"?>\n'
+        f'<OfflinePaperlessKyc referenceId="{REFERENCE_ID}">{body}'
+        f'<Signature xmlns="{DSIG}">{signed_info_inner}'
+        f'<SignatureValue>{signature}</SignatureValue></Signature>'
+        f'</OfflinePaperlessKyc>'
+    )
+    xml_path = os.path.join(HERE, f"offline_paperless_kyc_{REFERENCE_ID}.xml")
+    write_zip("offline_ekyc.zip", xml_path, xml)
+    write_zip("offline_ekyc_tampered.zip", xml_path, xml.replace("Test Resident", "Other Resident"))
+
+
+def write_zip(name, xml_path, xml):
+    open(xml_path, "w").write(xml)
+    zip_path = os.path.join(HERE, name)
+    if os.path.exists(zip_path):
+        os.remove(zip_path)
+    subprocess.run(["zip", "-j", "-q", "-P", SHARE_CODE, zip_path, xml_path], check=True)
+    os.remove(xml_path)
+
+
+def secure_qr():
+    fields = [b"V2", b"3", REFERENCE_ID.encode(), b"Test Resident", b"01-01-1990", b"M", b"S/O Test",
+              b"Bengaluru", b"Park", b"12", b"Indiranagar", b"560038", b"Indiranagar", b"Karnataka",
+              b"1st Main", b"Bengaluru East", b"Bengaluru", MOBILE[-4:].encode()]
+    photo = b"\x00\x00\x00\x0cjP  \r\n\x87\n" + b"\x00" * 64  # JPEG 2000 signature box stand-in
+    data = (b"\xff".join(fields) + b"\xff" + photo
+            + bytes.fromhex(hash_chain(EMAIL)) + bytes.fromhex(hash_chain(MOBILE)))
+    payload = gzip.compress(data + sign(data, "sha256"), mtime=0)
+    open(os.path.join(HERE, "secure_qr.txt"), "w").write(str(int.from_bytes(payload, "big")))
+
+
+if __name__ == "__main__":
+    if not os.path.exists(KEY):
+        subprocess.run(["openssl", "req", "-x509", "-newkey", "rsa:2048", "-nodes", "-keyout", KEY,
+                        "-out", CERT, "-days", "36500", "-subj", "/CN=SuiVerify Test UIDAI"],
+                       check=True, capture_output=True)
+    offline_ekyc()
+    secure_qr()
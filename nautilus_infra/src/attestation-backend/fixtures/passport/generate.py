@@ -0,0 +1,66 @@
+#!/usr/bin/env python3
+"""Generate MRZ fixtures with valid ICAO 9303 check digits."""
+from pathlib import Path
+
+HERE = Path(__file__).parent
+
+
+def check(field):
+    def value(c):
+        if c.isdigit():
+            return int(c)
+        if c.isalpha():
+            return ord(c) - ord("A") + 10
+        return 0
+    return str(sum(value(c) * w for c, w in zip(field, [7, 3, 1] * 100)) % 10)
+
+
+def pad(s, n):
+    return (s + "<" * n)[:n]
+
+
+def td3(code, state, surname, given, number, nat, dob, sex, expiry, personal=""):
+    l1 = pad(f"{code}<{state}{surname}<<{given.replace(' ', '<')}", 44)
+    num = pad(number, 9)
+    opt = pad(personal, 14)
+    opt_check = check(opt) if personal else "<"
+    l2 = num + check(num) + nat + dob + check(dob) + sex + expiry + check(expiry) + opt + opt_check
+    return l1 + "\n" + l2 + check(l2[0:10] + l2[13:20] + l2[21:43])
+
+
+def td2(code, state, surname, given, number, nat, dob, sex, expiry):
+    l1 = pad(f"{code}<{state}{surname}<<{given.replace(' ', '<')}", 36)
+    num = pad(number, 9)
+    l2 = num + check(num) + nat + dob + check(dob) + sex + expiry + check(expiry) + "<" * 7
+    return l1 + "\n" + l2 + check(l2[0:10] + l2[13:20] + l2[21:35])
+
+
+def td1(code, state, number, dob, sex, expiry, nat, surname, given):
+    num = pad(number, 9)
+    l1 = f"{code}<{state}" + num + check(num) + "<" * 15
+    l2 = dob + check(dob) + sex + expiry + check(expiry) + nat + "<" * 11
+    l2 += check(l1[5:30] + l2[0:7] + l2[8:15] + l2[18:29])
+    l3 = pad(f"{surname}<<{given.replace(' ', '<')}", 30)
+    return "\n".join([l1, l2, l3])
+
+
+def main():
+    fixtures = {
+        "td3_indian.txt": td3("P", "IND", "SHARMA", "PRIYA", "Z1234567", "IND", "900115", "F", "320601"),
+        "td3_foreign.txt": td3("P", "UTO", "ERIKSSON", "ANNA MARIA", "L898902C3", "UTO", "740812", "F", "340415", "ZE184226B"),
+        "td2_passport.txt": td2("P", "UTO", "ERIKSSON", "ANNA MARIA", "D23145890", "UTO", "740812", "F", "340415"),
+        "td1_id_card.txt": td1("I", "UTO", "D23145890", "740812", "F", "340415", "UTO", "ERIKSSON", "ANNA MARIA"),
+        # ICAO 9303 part 4 specimen, expired in 2012
+        "td3_expired.txt": "P<UTOERIKSSON<<ANNA<MARIA<<<<<<<<<<<<<<<<<<<\nL898902C36UTO7408122F1204159ZE184226B<<<<<10",
+    }
+    # One character of the document number changed, check digits left alone
+    bad = fixtures["td3_indian.txt"].split("\n")
+    fixtures["td3_bad_check.txt"] = bad[0] + "\n" + "Z1234568" + bad[1][8:]
+
+    for name, text in fixtures.items():
+        (HERE / name).write_text(text + "\n")
+        print(name)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,210 @@
+#!/usr/bin/env python3
+"""Generate synthetic ePassport chip fixtures under a throwaway test CSCA.
+
+Requires the `cryptography` package. Never use these keys outside tests.
+"""
+import base64
+import datetime
+import hashlib
+import json
+from pathlib import Path
+
+from cryptography import x509
+from cryptography.hazmat.primitives import hashes, serialization
+from cryptography.hazmat.primitives.asymmetric import ec, padding, rsa
+from cryptography.x509.oid import NameOID
+
+from generate import td3
+
+HERE = Path(__file__).parent
+MEDIA = HERE.parent / "media"
+
+OID_SIGNED_DATA = "1.2.840.113549.1.7.2"
+OID_LDS_SECURITY_OBJECT = "2.23.136.1.1.1"
+OID_CSCA_MASTER_LIST = "2.23.136.1.1.2"
+OID_CONTENT_TYPE = "1.2.840.113549.1.9.3"
+OID_MESSAGE_DIGEST = "1.2.840.113549.1.9.4"
+OID_SHA256 = "2.16.840.1.101.3.4.2.1"
+OID_SHA256_WITH_RSA = "1.2.840.113549.1.1.11"
+
+
+# Minimal DER encoder -----------------------------------------------------
+
+def tlv(tag: bytes, value: bytes) -> bytes:
+    n = len(value)
+    if n < 0x80:
+        length = bytes([n])
+    else:
+        raw = n.to_bytes((n.bit_length() + 7) // 8, "big")
+        length = bytes([0x80 | len(raw)]) + raw
+    return tag + length + value
+
+
+def seq(*items: bytes) -> bytes:
+    return tlv(b"\x30", b"".join(items))
+
+
+def der_set(*items: bytes) -> bytes:
+    return tlv(b"\x31", b"".join(sorted(items)))
+
+
+def integer(n: int) -> bytes:
+    raw = n.to_bytes(max(1, (n.bit_length() + 8) // 8), "big")
+    return tlv(b"\x02", raw)
+
+
+def octets(data: bytes) -> bytes:
+    return tlv(b"\x04", data)
+
+
+def oid(dotted: str) -> bytes:
+    parts = [int(p) for p in dotted.split(".")]
+    body = bytes([parts[0] * 40 + parts[1]])
+    for part in parts[2:]:
+        chunk = [part & 0x7F]
+        part >>= 7
+        while part:
+            chunk.append(0x80 | (part & 0x7F))
+            part >>= 7
+        body += bytes(reversed(chunk))
+    return tlv(b"\x06", body)
+
+
+def explicit(n: int, value: bytes) -> bytes:
+    return tlv(bytes([0xA0 | n]), value)
+
+
+def algorithm(dotted: str) -> bytes:
+    return seq(oid(dotted), b"\x05\x00")
+
+
+# Certificates -------------------------------------------------------------
+
+def name(country: str, common_name: str) -> x509.Name:
+    return x509.Name([
+        x509.NameAttribute(NameOID.COUNTRY_NAME, country),
+        x509.NameAttribute(NameOID.COMMON_NAME, common_name),
+    ])
+
+
+def certificate(subject, issuer, public_key, signing_key, serial, ca):
+    return (
+        x509.CertificateBuilder()
+        .subject_name(subject)
+        .issuer_name(issuer)
+        .public_key(public_key)
+        .serial_number(serial)
+        .not_valid_before(datetime.datetime(2024, 1, 1))
+        .not_valid_after(datetime.datetime(2040, 1, 1))
+        .add_extension(x509.BasicConstraints(ca=ca, path_length=0 if ca else None), critical=True)
+        .sign(signing_key, hashes.SHA256())
+    )
+
+
+# Data groups --------------------------------------------------------------
+
+def dg1(mrz: str) -> bytes:
+    return tlv(b"\x61", tlv(b"\x5f\x1f", mrz.replace("\n", "").encode()))
+
+
+def dg2(image: bytes) -> bytes:
+    # ISO/IEC 19794-5: facial information without feature points, then image information
+    facial_information = b"\x00\x00\x00\x00" + b"\x00" * 16
+    image_information = b"\x01\x01" + (128).to_bytes(2, "big") * 2 + b"\x01\x02" + b"\x00" * 4
+    block = facial_information + image_information + image
+    block = len(block).to_bytes(4, "big") + block[4:]
+    record = b"FAC\x00" + b"010\x00" + (14 + len(block)).to_bytes(4, "big") + (1).to_bytes(2, "big") + block
+    header = tlv(b"\xa1", tlv(b"\x80", b"\x01\x01"))
+    template = tlv(b"\x7f\x60", header + tlv(b"\x5f\x2e", record))
+    return tlv(b"\x75", tlv(b"\x7f\x61", tlv(b"\x02", b"\x01") + template))
+
+
+# Security object ----------------------------------------------------------
+
+def sod(dsc, dsc_key, groups) -> bytes:
+    hashes_ = seq(*(seq(integer(n), octets(hashlib.sha256(data).digest())) for n, data in groups))
+    lds = seq(integer(0), algorithm(OID_SHA256), hashes_)
+
+    attributes = [
+        seq(oid(OID_CONTENT_TYPE), der_set(oid(OID_LDS_SECURITY_OBJECT))),
+        seq(oid(OID_MESSAGE_DIGEST), der_set(octets(hashlib.sha256(lds).digest()))),
+    ]
+    signed_attributes = der_set(*attributes)
+    signature = dsc_key.sign(signed_attributes, padding.PKCS1v15(), hashes.SHA256())
+
+    issuer = dsc.issuer.public_bytes()
+    signer_info = seq(
+        integer(1),
+        seq(issuer, integer(dsc.serial_number)),
+        algorithm(OID_SHA256),
+        b"\xa0" + signed_attributes[1:],  # [0] IMPLICIT
+        algorithm(OID_SHA256_WITH_RSA),
+        octets(signature),
+    )
+    signed_data = seq(
+        integer(3),
+        der_set(algorithm(OID_SHA256)),
+        seq(oid(OID_LDS_SECURITY_OBJECT), explicit(0, octets(lds))),
+        tlv(b"\xa0", dsc.public_bytes(serialization.Encoding.DER)),
+        der_set(signer_info),
+    )
+    content_info = seq(oid(OID_SIGNED_DATA), explicit(0, signed_data))
+    return tlv(b"\x77", content_info)
+
+
+def master_list(cscas) -> bytes:
+    certs = der_set(*(c.public_bytes(serialization.Encoding.DER) for c in cscas))
+    content = seq(integer(0), certs)
+    signed_data = seq(
+        integer(3),
+        der_set(algorithm(OID_SHA256)),
+        seq(oid(OID_CSCA_MASTER_LIST), explicit(0, octets(content))),
+        der_set(),
+    )
+    return seq(oid(OID_SIGNED_DATA), explicit(0, signed_data))
+
+
+def chip_json(sod_bytes, dg1_bytes, dg2_bytes) -> str:
+    b64 = lambda b: base64.b64encode(b).decode()
+    return json.dumps({"sod": b64(sod_bytes), "dg1": b64(dg1_bytes), "dg2": b64(dg2_bytes)}, indent=2)
+
+
+def main():
+    csca_key = ec.generate_private_key(ec.SECP256R1())
+    csca_name = name("IN", "Test CSCA India")
+    csca = certificate(csca_name, csca_name, csca_key.public_key(), csca_key, 1, ca=True)
+
+    dsc_key = rsa.generate_private_key(public_exponent=65537, key_size=2048)
+    dsc = certificate(name("IN", "Test Document Signer"), csca_name, dsc_key.public_key(), csca_key, 2, ca=False)
+
+    # A CSCA for another country, to check that Indian passports need India's
+    rogue_key = ec.generate_private_key(ec.SECP256R1())
+    rogue_name = name("UT", "Test CSCA Utopia")
+    rogue_csca = certificate(rogue_name, rogue_name, rogue_key.public_key(), rogue_key, 3, ca=True)
+    rogue_dsc = certificate(name("UT", "Test Utopia Signer"), rogue_name, dsc_key.public_key(), rogue_key, 4, ca=False)
+
+    mrz = td3("P", "IND", "SHARMA", "PRIYA", "Z1234567", "IND", "900115", "F", "320601")
+    dg1_bytes = dg1(mrz)
+    dg2_bytes = dg2((MEDIA / "valid.jpg").read_bytes())
+    sod_bytes = sod(dsc, dsc_key, [(1, dg1_bytes), (2, dg2_bytes)])
+
+    forged_mrz = td3("P", "IND", "SHARMA", "PRIYA", "Z1234567", "IND", "000115", "F", "320601")
+    rogue_sod = sod(rogue_dsc, dsc_key, [(1, dg1_bytes), (2, dg2_bytes)])
+
+    outputs = {
+        "chip_valid.json": chip_json(sod_bytes, dg1_bytes, dg2_bytes),
+        "chip_tampered_dg1.json": chip_json(sod_bytes, dg1(forged_mrz), dg2_bytes),
+        "chip_wrong_csca.json": chip_json(rogue_sod, dg1_bytes, dg2_bytes),
+    }
+    for file_name, text in outputs.items():
+        (HERE / file_name).write_text(text + "\n")
+        print(file_name)
+
+    (HERE / "test_csca.pem").write_bytes(csca.public_bytes(serialization.Encoding.PEM))
+    (HERE / "test_master_list.ml").write_bytes(master_list([csca, rogue_csca]))
+    print("test_csca.pem")
+    print("test_master_list.ml")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,84 @@
+#!/usr/bin/env python3
+"""Generate zkLogin JWT fixtures signed by throwaway RSA keys.
+
+Requires the `cryptography` package. Never use these keys outside tests.
+"""
+import base64
+import json
+from pathlib import Path
+
+from cryptography.hazmat.primitives import hashes
+from cryptography.hazmat.primitives.asymmetric import padding, rsa
+
+HERE = Path(__file__).parent
+
+ISSUER = "https://accounts.test.suiverify.local"
+AUDIENCE = "suiverify-test-client"
+# zkLogin nonce of ephemeral key 0x11 * 32 (Ed25519), max_epoch 10 and
+# randomness 100681567828351849884072155819400689117
+NONCE = "j0aMqmHDUhoJmNqXeOptH7dzR50"
+
+ISSUED_AT = 1_760_000_000  # 2025-10-09
+EXPIRES = 4_102_444_800  # 2100-01-01
+EXPIRED = 1_760_003_600
+
+
+def b64url(data: bytes) -> str:
+    return base64.urlsafe_b64encode(data).rstrip(b"=").decode()
+
+
+def uint(n: int) -> str:
+    return b64url(n.to_bytes((n.bit_length() + 7) // 8, "big"))
+
+
+def jwk(kid: str, key) -> dict:
+    numbers = key.public_key().public_numbers()
+    return {"kty": "RSA", "kid": kid, "alg": "RS256", "use": "sig", "n": uint(numbers.n), "e": uint(numbers.e)}
+
+
+def jwt(key, kid: str, alg: str = "RS256", **overrides) -> str:
+    claims = {
+        "iss": ISSUER,
+        "sub": "110169484474386276334",
+        "aud": AUDIENCE,
+        "iat": ISSUED_AT,
+        "exp": EXPIRES,
+        "nonce": NONCE,
+    }
+    claims.update(overrides)
+    header = b64url(json.dumps({"alg": alg, "kid": kid, "typ": "JWT"}).encode())
+    payload = b64url(json.dumps(claims).encode())
+    signing_input = f"{header}.{payload}".encode()
+    signature = key.sign(signing_input, padding.PKCS1v15(), hashes.SHA256())
+    return f"{header}.{payload}.{b64url(signature)}"
+
+
+def main():
+    current = rsa.generate_private_key(public_exponent=65537, key_size=2048)
+    rotated = rsa.generate_private_key(public_exponent=65537, key_size=2048)
+    unpublished = rsa.generate_private_key(public_exponent=65537, key_size=2048)
+
+    valid = jwt(current, "test-key-1")
+    header, payload, signature = valid.split(".")
+    forged_payload = json.loads(base64.urlsafe_b64decode(payload + "=="))
+    forged_payload["sub"] = "999999999999999999999"
+
+    outputs = {
+        "jwks.json": json.dumps({"keys": [jwk("test-key-1", current)]}, indent=2),
+        "jwks_rotated.json": json.dumps({"keys": [jwk("test-key-1", current), jwk("test-key-2", rotated)]}, indent=2),
+        "valid.jwt": valid,
+        "rotated_kid.jwt": jwt(rotated, "test-key-2"),
+        "unknown_kid.jwt": jwt(unpublished, "test-key-3"),
+        "expired.jwt": jwt(current, "test-key-1", exp=EXPIRED),
+        "wrong_audience.jwt": jwt(current, "test-key-1", aud="someone-else"),
+        "wrong_nonce.jwt": jwt(current, "test-key-1", nonce="AAAAAAAAAAAAAAAAAAAAAAAAAAA"),
+        "forged_payload.jwt": f"{header}.{b64url(json.dumps(forged_payload).encode())}.{signature}",
+        "hs256.jwt": jwt(current, "test-key-1", alg="HS256"),
+    }
+    for file_name, text in outputs.items():
+        (HERE / file_name).write_text(text + "\n")
+        print(file_name)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,214 @@
+// aadhaar/mod.rs
+use crate::age::DateOfBirth;
+use crate::EnclaveError;
+use rsa::pkcs1v15::{Signature, VerifyingKey};
+use rsa::pkcs8::DecodePublicKey;
+use rsa::signature::Verifier;
+use rsa::RsaPublicKey;
+use sha1::Sha1;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+use x509_cert::der::{Decode, DecodePem, Encode};
+use x509_cert::Certificate;
+
+pub mod offline_ekyc;
+pub mod qr;
+pub mod xmldsig;
+
+/// Where an Aadhaar record was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AadhaarSource {
+    SecureQr,
+    OfflineEkyc,
+}
+
+/// Demographic data from a UIDAI-signed Aadhaar document. `Debug` leaves out
+/// personal data.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AadhaarRecord {
+    pub source: AadhaarSource,
+    /// Last 4 Aadhaar digits followed by the generation timestamp
+    pub reference_id: String,
+    pub name: String,
+    /// Date of birth as printed by UIDAI (`DD-MM-YYYY`, sometimes only `YYYY`)
+    pub date_of_birth: DateOfBirth,
+    pub gender: String,
+    pub care_of: String,
+    pub house: String,
+    pub street: String,
+    pub landmark: String,
+    pub location: String,
+    pub vtc: String,
+    pub post_office: String,
+    pub sub_district: String,
+    pub district: String,
+    pub state: String,
+    pub pincode: String,
+    /// Last 4 digits of the registered mobile (V2 QR only)
+    pub mobile_last4: Option<String>,
+    /// Hex hash chain of the registered mobile, if one is registered
+    pub mobile_hash: Option<String>,
+    /// Hex hash chain of the registered email, if one is registered
+    pub email_hash: Option<String>,
+    /// Resident photo as embedded by UIDAI (JPEG 2000 in QR, JPEG in Offline e-KYC)
+    #[serde(with = "serde_bytes")]
+    pub photo: Vec<u8>,
+}
+
+impl std::fmt::Debug for AadhaarRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AadhaarRecord").field("source", &self.source).finish_non_exhaustive()
+    }
+}
+
+impl AadhaarRecord {
+    /// Hex SHA-256 over the BCS encoding of the record, used in evidence commitments.
+    pub fn digest(&self) -> Result<String, EnclaveError> {
+        let encoded = bcs::to_bytes(self)
+            .map_err(|e| EnclaveError::InternalError(format!("BCS encoding failed: {}", e)))?;
+        Ok(hex::encode(Sha256::digest(encoded)))
+    }
+
+    /// Check a user-supplied mobile number against the record's hash chain.
+    /// `share_code` is the Offline e-KYC share code, empty for Secure QR.
+    pub fn verify_mobile(&self, mobile: &str, share_code: &str) -> Result<(), EnclaveError> {
+        self.verify_contact("mobile", self.mobile_hash.as_deref(), mobile, share_code)
+    }
+
+    /// Check a user-supplied email address against the record's hash chain.
+    pub fn verify_email(&self, email: &str, share_code: &str) -> Result<(), EnclaveError> {
+        self.verify_contact("email", self.email_hash.as_deref(), email, share_code)
+    }
+
+    /// UIDAI hashes `contact || share_code` with SHA-256, re-hashing the hex
+    /// digest as many times as the last Aadhaar digit (at least once). The last
+    /// four Aadhaar digits open the reference ID.
+    fn verify_contact(
+        &self,
+        kind: &str,
+        expected: Option<&str>,
+        contact: &str,
+        share_code: &str,
+    ) -> Result<(), EnclaveError> {
+        let expected = expected.ok_or_else(|| {
+            EnclaveError::VerificationFailed(format!("Aadhaar record has no registered {}", kind))
+        })?;
+        let rounds = self
+            .reference_id
+            .chars()
+            .nth(3)
+            .and_then(|c| c.to_digit(10))
+            .ok_or_else(|| EnclaveError::InvalidInput("Malformed Aadhaar reference ID".to_string()))?
+            .max(1);
+
+        let mut value = format!("{}{}", contact.trim(), share_code);
+        for _ in 0..rounds {
+            value = hex::encode(Sha256::digest(value.as_bytes()));
+        }
+        if value.eq_ignore_ascii_case(expected) {
+            Ok(())
+        } else {
+            Err(EnclaveError::VerificationFailed(format!(
+                "Supplied {} does not match the Aadhaar record",
+                kind
+            )))
+        }
+    }
+}
+
+/// RSA signature schemes UIDAI documents use.
+#[derive(Debug, Clone, Copy)]
+pub enum SignatureAlgorithm {
+    /// Older Offline e-KYC XML files
+    RsaSha1,
+    RsaSha256,
+}
+
+/// UIDAI signing certificates trusted for Aadhaar documents.
+pub struct UidaiTrust {
+    keys: Vec<RsaPublicKey>,
+}
+
+impl UidaiTrust {
+    /// Load certificates (PEM or DER) from `UIDAI_CERT_FILES`, comma separated so a
+    /// rotated certificate can be added before the old one is retired.
+    pub fn from_env() -> Self {
+        let files = std::env::var("UIDAI_CERT_FILES").unwrap_or_default();
+        let paths: Vec<&str> = files.split(',').map(str::trim).filter(|p| !p.is_empty()).collect();
+        Self::from_files(&paths)
+    }
+
+    /// Load certificates from files; unreadable ones are skipped.
+    pub fn from_files(paths: &[&str]) -> Self {
+        let mut keys = Vec::new();
+        for path in paths {
+            match std::fs::read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| public_key_from_certificate(&bytes))
+            {
+                Ok(key) => {
+                    info!("Loaded UIDAI certificate from {}", path);
+                    keys.push(key);
+                }
+                Err(e) => warn!("Failed to load UIDAI certificate {}: {}", path, e),
+            }
+        }
+        if keys.is_empty() {
+            warn!("No UIDAI certificates configured: Aadhaar documents will be rejected");
+        }
+        Self { keys }
+    }
+
+    /// Verify an RSA-SHA256 (PKCS#1 v1.5) signature against any trusted certificate.
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<(), EnclaveError> {
+        self.verify_with(SignatureAlgorithm::RsaSha256, data, signature)
+    }
+
+    /// Verify a PKCS#1 v1.5 signature with the given digest.
+    pub fn verify_with(
+        &self,
+        algorithm: SignatureAlgorithm,
+        data: &[u8],
+        signature: &[u8],
+    ) -> Result<(), EnclaveError> {
+        if self.keys.is_empty() {
+            return Err(EnclaveError::InternalError(
+                "No UIDAI certificate configured".to_string(),
+            ));
+        }
+        let signature = Signature::try_from(signature).map_err(|e| {
+            EnclaveError::InvalidInput(format!("Malformed UIDAI signature: {}", e))
+        })?;
+        let trusted = self.keys.iter().any(|key| match algorithm {
+            SignatureAlgorithm::RsaSha1 => VerifyingKey::<Sha1>::new(key.clone())
+                .verify(data, &signature)
+                .is_ok(),
+            SignatureAlgorithm::RsaSha256 => VerifyingKey::<Sha256>::new(key.clone())
+                .verify(data, &signature)
+                .is_ok(),
+        });
+        if trusted {
+            Ok(())
+        } else {
+            Err(EnclaveError::VerificationFailed(
+                "Aadhaar document signature does not match a UIDAI certificate".to_string(),
+            ))
+        }
+    }
+}
+
+fn public_key_from_certificate(bytes: &[u8]) -> Result<RsaPublicKey, String> {
+    let certificate = if bytes.starts_with(b"-----BEGIN") {
+        Certificate::from_pem(bytes).map_err(|e| e.to_string())?
+    } else {
+        Certificate::from_der(bytes).map_err(|e| e.to_string())?
+    };
+    let spki = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|e| e.to_string())?;
+    RsaPublicKey::from_public_key_der(&spki).map_err(|e| e.to_string())
+}
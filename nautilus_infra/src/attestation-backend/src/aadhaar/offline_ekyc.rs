@@ -0,0 +1,172 @@
+// aadhaar/offline_ekyc.rs
+use super::xmldsig::verify_enveloped;
+use super::{AadhaarRecord, AadhaarSource, UidaiTrust};
+use crate::age::DateOfBirth;
+use crate::EnclaveError;
+use base64::{engine::general_purpose, Engine as _};
+use roxmltree::{Document, Node};
+use std::io::{Cursor, Read};
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// Cap on the extracted XML.
+const MAX_XML_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Open an Offline e-KYC ZIP with the resident's share code, verify the
+/// UIDAI XML signature and return the demographic record.
+pub fn decode_offline_ekyc(
+    zip_bytes: &[u8],
+    share_code: &str,
+    trust: &UidaiTrust,
+) -> Result<AadhaarRecord, EnclaveError> {
+    let xml = extract_xml(zip_bytes, share_code)?;
+    let xml = xml.strip_prefix('\u{feff}').unwrap_or(&xml);
+    let doc = Document::parse(xml)
+        .map_err(|e| EnclaveError::InvalidInput(format!("Offline e-KYC XML is malformed: {}", e)))?;
+
+    verify_enveloped(&doc, trust)?;
+    parse_record(&doc)
+}
+
+fn extract_xml(zip_bytes: &[u8], share_code: &str) -> Result<String, EnclaveError> {
+    let invalid = |reason: String| EnclaveError::InvalidInput(format!("Offline e-KYC ZIP: {}", reason));
+
+    let mut archive = ZipArchive::new(Cursor::new(zip_bytes)).map_err(|e| invalid(e.to_string()))?;
+    let index = (0..archive.len())
+        .find(|i| {
+            archive
+                .name_for_index(*i)
+                .is_some_and(|name| name.to_ascii_lowercase().ends_with(".xml"))
+        })
+        .ok_or_else(|| invalid("no XML file inside".to_string()))?;
+
+    let entry = archive
+        .by_index_decrypt(index, share_code.trim().as_bytes())
+        .map_err(|e| match e {
+            ZipError::InvalidPassword => invalid("share code does not open the file".to_string()),
+            other => invalid(other.to_string()),
+        })?;
+
+    let mut xml = Vec::new();
+    entry
+        .take(MAX_XML_BYTES + 1)
+        .read_to_end(&mut xml)
+        .map_err(|e| invalid(format!("cannot be decrypted: {}", e)))?;
+    if xml.len() as u64 > MAX_XML_BYTES {
+        return Err(invalid("XML is too large".to_string()));
+    }
+    String::from_utf8(xml).map_err(|_| invalid("XML is not UTF-8".to_string()))
+}
+
+/// Read `OfflinePaperlessKyc/UidData/{Poi,Poa,Pht}`.
+fn parse_record(doc: &Document) -> Result<AadhaarRecord, EnclaveError> {
+    let root = doc.root_element();
+    if root.tag_name().name() != "OfflinePaperlessKyc" {
+        return Err(EnclaveError::InvalidInput(format!(
+            "Unexpected Offline e-KYC root element {}",
+            root.tag_name().name()
+        )));
+    }
+    let uid_data = child(root, "UidData")?;
+    let poi = child(uid_data, "Poi")?;
+    let poa = child(uid_data, "Poa")?;
+    let photo_base64: String = child(uid_data, "Pht")?
+        .text()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let photo = general_purpose::STANDARD
+        .decode(photo_base64)
+        .map_err(|e| EnclaveError::InvalidInput(format!("Offline e-KYC photo is not base64: {}", e)))?;
+
+    let attr = |node: Node, name: &str| node.attribute(name).unwrap_or_default().to_string();
+    let hash = |name: &str| poi.attribute(name).filter(|h| !h.is_empty()).map(str::to_string);
+
+    Ok(AadhaarRecord {
+        source: AadhaarSource::OfflineEkyc,
+        reference_id: root
+            .attribute("referenceId")
+            .ok_or_else(|| EnclaveError::InvalidInput("Offline e-KYC has no referenceId".to_string()))?
+            .to_string(),
+        name: attr(poi, "name"),
+        date_of_birth: DateOfBirth::new(attr(poi, "dob")),
+        gender: attr(poi, "gender"),
+        care_of: attr(poa, "careof"),
+        house: attr(poa, "house"),
+        street: attr(poa, "street"),
+        landmark: attr(poa, "landmark"),
+        location: attr(poa, "loc"),
+        vtc: attr(poa, "vtc"),
+        post_office: attr(poa, "po"),
+        sub_district: attr(poa, "subdist"),
+        district: attr(poa, "dist"),
+        state: attr(poa, "state"),
+        pincode: attr(poa, "pc"),
+        mobile_last4: None,
+        mobile_hash: hash("m"),
+        email_hash: hash("e"),
+        photo,
+    })
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Result<Node<'a, 'input>, EnclaveError> {
+    node.children()
+        .find(|n| n.is_element() && n.tag_name().name() == name)
+        .ok_or_else(|| EnclaveError::InvalidInput(format!("Offline e-KYC XML has no {}", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::public_key_from_certificate;
+    use super::*;
+    use rsa::RsaPrivateKey;
+
+    const VALID_ZIP: &[u8] = include_bytes!("../../fixtures/aadhaar/offline_ekyc.zip");
+    const TAMPERED_ZIP: &[u8] = include_bytes!("../../fixtures/aadhaar/offline_ekyc_tampered.zip");
+    const TEST_CERT: &[u8] = include_bytes!("../../fixtures/aadhaar/test_uidai_cert.pem");
+    const SHARE_CODE: &str = "1234";
+
+    fn test_trust() -> UidaiTrust {
+        UidaiTrust { keys: vec![public_key_from_certificate(TEST_CERT).unwrap()] }
+    }
+
+    #[test]
+    fn accepts_signed_fixture() {
+        let record = decode_offline_ekyc(VALID_ZIP, SHARE_CODE, &test_trust()).unwrap();
+        assert_eq!(record.source, AadhaarSource::OfflineEkyc);
+        assert_eq!(record.reference_id, "123420190101120000000");
+        assert_eq!(record.name, "Test Resident");
+        assert_eq!(record.pincode, "560038");
+        record.verify_mobile("9876543210", SHARE_CODE).unwrap();
+        record.verify_email("resident@example.com", SHARE_CODE).unwrap();
+        assert!(record.verify_mobile("9876543211", SHARE_CODE).is_err());
+    }
+
+    #[test]
+    fn rejects_content_changed_after_signing() {
+        match decode_offline_ekyc(TAMPERED_ZIP, SHARE_CODE, &test_trust()) {
+            Err(EnclaveError::VerificationFailed(message)) => assert!(message.contains("signed digest")),
+            other => panic!("expected a digest mismatch, got {:?}", other.map(|r| r.reference_id)),
+        }
+    }
+
+    #[test]
+    fn rejects_signature_from_another_key() {
+        let key = RsaPrivateKey::new(&mut rand::thread_rng(), 1024).unwrap();
+        let trust = UidaiTrust { keys: vec![key.to_public_key()] };
+        match decode_offline_ekyc(VALID_ZIP, SHARE_CODE, &trust) {
+            Err(EnclaveError::VerificationFailed(message)) => assert!(message.contains("UIDAI certificate")),
+            other => panic!("expected a signature mismatch, got {:?}", other.map(|r| r.reference_id)),
+        }
+        assert!(decode_offline_ekyc(VALID_ZIP, SHARE_CODE, &UidaiTrust { keys: Vec::new() }).is_err());
+    }
+
+    #[test]
+    fn rejects_wrong_share_code() {
+        match decode_offline_ekyc(VALID_ZIP, "0000", &test_trust()) {
+            Err(EnclaveError::InvalidInput(message)) => assert!(message.contains("ZIP")),
+            other => panic!("expected a ZIP error, got {:?}", other.map(|r| r.reference_id)),
+        }
+    }
+}
@@ -0,0 +1,258 @@
+// aadhaar/qr.rs
+use super::{AadhaarRecord, AadhaarSource, UidaiTrust};
+use crate::age::DateOfBirth;
+use crate::EnclaveError;
+use flate2::read::GzDecoder;
+use num_bigint::BigUint;
+use std::io::Read;
+
+/// Field separator in the decompressed payload.
+const DELIMITER: u8 = 0xFF;
+/// RSA-2048 signature appended to the payload.
+const SIGNATURE_LENGTH: usize = 256;
+/// SHA-256 hash chains for mobile and email.
+const HASH_LENGTH: usize = 32;
+/// Longest decimal string accepted from a scanned QR.
+const MAX_QR_DIGITS: usize = 16 * 1024;
+/// Cap on the decompressed payload.
+const MAX_PAYLOAD_BYTES: u64 = 64 * 1024;
+
+/// Decode a scanned Aadhaar Secure QR (the decimal string in the code), verify
+/// the UIDAI signature and return the demographic record.
+pub fn decode_secure_qr(qr_text: &str, trust: &UidaiTrust) -> Result<AadhaarRecord, EnclaveError> {
+    let digits = qr_text.trim();
+    if digits.is_empty() || digits.len() > MAX_QR_DIGITS || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(EnclaveError::InvalidInput(
+            "Aadhaar QR must be a decimal string".to_string(),
+        ));
+    }
+
+    let compressed = BigUint::parse_bytes(digits.as_bytes(), 10)
+        .ok_or_else(|| EnclaveError::InvalidInput("Aadhaar QR is not a number".to_string()))?
+        .to_bytes_be();
+    let payload = decompress(&compressed)?;
+
+    if payload.len() <= SIGNATURE_LENGTH {
+        return Err(EnclaveError::InvalidInput(
+            "Aadhaar QR payload is too short".to_string(),
+        ));
+    }
+    let (signed, signature) = payload.split_at(payload.len() - SIGNATURE_LENGTH);
+    trust.verify(signed, signature)?;
+
+    parse_payload(signed)
+}
+
+fn decompress(compressed: &[u8]) -> Result<Vec<u8>, EnclaveError> {
+    let mut payload = Vec::new();
+    GzDecoder::new(compressed)
+        .take(MAX_PAYLOAD_BYTES + 1)
+        .read_to_end(&mut payload)
+        .map_err(|e| EnclaveError::InvalidInput(format!("Aadhaar QR does not decompress: {}", e)))?;
+    if payload.len() as u64 > MAX_PAYLOAD_BYTES {
+        return Err(EnclaveError::InvalidInput(
+            "Aadhaar QR payload is too large".to_string(),
+        ));
+    }
+    Ok(payload)
+}
+
+/// Parse the signed part of the payload: text fields separated by 0xFF, then
+/// the photo, then the optional email and mobile hashes.
+fn parse_payload(signed: &[u8]) -> Result<AadhaarRecord, EnclaveError> {
+    // V2 codes start with a version field and end the text with the last 4
+    // mobile digits; legacy codes have neither.
+    let version_2 = signed.starts_with(b"V2") && signed.get(2) == Some(&DELIMITER);
+    let field_count = if version_2 { 18 } else { 16 };
+
+    let mut fields = Vec::with_capacity(field_count);
+    let mut start = 0;
+    while fields.len() < field_count {
+        let end = signed[start..]
+            .iter()
+            .position(|b| *b == DELIMITER)
+            .map(|offset| start + offset)
+            .ok_or_else(|| {
+                EnclaveError::InvalidInput(format!(
+                    "Aadhaar QR has {} of {} text fields",
+                    fields.len(),
+                    field_count
+                ))
+            })?;
+        let text = std::str::from_utf8(&signed[start..end]).map_err(|_| {
+            EnclaveError::InvalidInput("Aadhaar QR text field is not UTF-8".to_string())
+        })?;
+        fields.push(text.to_string());
+        start = end + 1;
+    }
+    if version_2 {
+        fields.remove(0);
+    }
+
+    let indicator = fields[0].as_str();
+    let (has_email, has_mobile) = match indicator {
+        "0" => (false, false),
+        "1" => (true, false),
+        "2" => (false, true),
+        "3" => (true, true),
+        other => {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Unknown Aadhaar email/mobile indicator: {}",
+                other
+            )))
+        }
+    };
+
+    // Hashes sit at the end of the signed data: email first, then mobile
+    let mut tail = signed.len();
+    let mut take_hash = |present: bool| -> Result<Option<String>, EnclaveError> {
+        if !present {
+            return Ok(None);
+        }
+        if tail < start + HASH_LENGTH {
+            return Err(EnclaveError::InvalidInput(
+                "Aadhaar QR is missing contact hashes".to_string(),
+            ));
+        }
+        tail -= HASH_LENGTH;
+        Ok(Some(hex::encode(&signed[tail..tail + HASH_LENGTH])))
+    };
+    let mobile_hash = take_hash(has_mobile)?;
+    let email_hash = take_hash(has_email)?;
+    let photo = signed[start..tail].to_vec();
+    if photo.is_empty() {
+        return Err(EnclaveError::InvalidInput(
+            "Aadhaar QR has no photo".to_string(),
+        ));
+    }
+
+    let field = |index: usize| fields[index].clone();
+    Ok(AadhaarRecord {
+        source: AadhaarSource::SecureQr,
+        reference_id: field(1),
+        name: field(2),
+        date_of_birth: DateOfBirth::new(field(3)),
+        gender: field(4),
+        care_of: field(5),
+        district: field(6),
+        landmark: field(7),
+        house: field(8),
+        location: field(9),
+        pincode: field(10),
+        post_office: field(11),
+        state: field(12),
+        street: field(13),
+        sub_district: field(14),
+        vtc: field(15),
+        mobile_last4: version_2.then(|| field(16)).filter(|m| !m.is_empty()),
+        mobile_hash,
+        email_hash,
+        photo,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::public_key_from_certificate;
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const SECURE_QR: &str = include_str!("../../fixtures/aadhaar/secure_qr.txt");
+    const TEST_CERT: &[u8] = include_bytes!("../../fixtures/aadhaar/test_uidai_cert.pem");
+
+    fn test_trust() -> UidaiTrust {
+        UidaiTrust { keys: vec![public_key_from_certificate(TEST_CERT).unwrap()] }
+    }
+
+    /// Compress `payload` and write it as a QR decimal string.
+    fn encode(payload: &[u8]) -> String {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(payload).unwrap();
+        BigUint::from_bytes_be(&encoder.finish().unwrap()).to_str_radix(10)
+    }
+
+    fn fixture_payload() -> Vec<u8> {
+        let compressed = BigUint::parse_bytes(SECURE_QR.trim().as_bytes(), 10).unwrap().to_bytes_be();
+        decompress(&compressed).unwrap()
+    }
+
+    #[test]
+    fn decodes_signed_fixture() {
+        let record = decode_secure_qr(SECURE_QR, &test_trust()).unwrap();
+        assert_eq!(record.source, AadhaarSource::SecureQr);
+        assert_eq!(record.reference_id, "123420190101120000000");
+        assert_eq!(record.name, "Test Resident");
+        assert_eq!(record.gender, "M");
+        assert_eq!(record.pincode, "560038");
+        assert_eq!(record.state, "Karnataka");
+        assert_eq!(record.vtc, "Bengaluru");
+        assert_eq!(record.mobile_last4.as_deref(), Some("3210"));
+        assert_eq!(record.photo.len(), 76);
+        assert!(record.photo.starts_with(b"\x00\x00\x00\x0cjP  "));
+
+        // Secure QR hashes the contact alone, without a share code
+        record.verify_mobile("9876543210", "").unwrap();
+        record.verify_email("resident@example.com", "").unwrap();
+        assert!(record.verify_mobile("9876543211", "").is_err());
+        assert!(record.verify_email("someone@example.com", "").is_err());
+    }
+
+    #[test]
+    fn rejects_payload_changed_after_signing() {
+        let mut payload = fixture_payload();
+        // "Test Resident" -> "Test Resideot"
+        let at = payload.windows(13).position(|w| w == b"Test Resident").unwrap() + 11;
+        payload[at] = b'o';
+        match decode_secure_qr(&encode(&payload), &test_trust()) {
+            Err(EnclaveError::VerificationFailed(_)) => {}
+            other => panic!("expected a signature failure, got {:?}", other.map(|r| r.name)),
+        }
+
+        // A flipped digit of the QR string itself never yields a record
+        let mut digits = SECURE_QR.trim().as_bytes().to_vec();
+        let middle = digits.len() / 2;
+        digits[middle] = if digits[middle] == b'9' { b'0' } else { digits[middle] + 1 };
+        assert!(decode_secure_qr(std::str::from_utf8(&digits).unwrap(), &test_trust()).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        let trust = test_trust();
+        for text in ["", "12ab34", "0x1234", "-1234"] {
+            let error = decode_secure_qr(text, &trust).unwrap_err();
+            assert!(error.message().contains("decimal string"), "{}: {}", text, error.message());
+        }
+        assert!(decode_secure_qr("123456789", &trust).unwrap_err().message().contains("decompress"));
+        for length in [10, SIGNATURE_LENGTH] {
+            let error = decode_secure_qr(&encode(&vec![1u8; length]), &trust).unwrap_err();
+            assert!(error.message().contains("too short"), "{}", error.message());
+        }
+    }
+
+    #[test]
+    fn parses_legacy_layout_and_indicators() {
+        let fields = ["0", "123420190101120000000", "Test Resident", "01-01-1990", "M", "", "", "", "", "", "560038",
+            "", "Karnataka", "", "", ""];
+        let mut signed = Vec::new();
+        for field in fields {
+            signed.extend_from_slice(field.as_bytes());
+            signed.push(DELIMITER);
+        }
+        signed.extend_from_slice(b"photo");
+
+        let record = parse_payload(&signed).unwrap();
+        assert_eq!(record.name, "Test Resident");
+        assert_eq!((record.mobile_last4, record.mobile_hash, record.email_hash), (None, None, None));
+        assert_eq!(record.photo, b"photo");
+
+        // Indicator 3 needs two hashes after the photo
+        signed[0] = b'3';
+        assert!(parse_payload(&signed).unwrap_err().message().contains("contact hashes"));
+        signed[0] = b'7';
+        assert!(parse_payload(&signed).unwrap_err().message().contains("indicator"));
+        let truncated = &signed[..signed.len() - 20];
+        assert!(parse_payload(truncated).unwrap_err().message().contains("text fields"));
+    }
+}
@@ -0,0 +1,262 @@
+// aadhaar/xmldsig.rs
+use super::{SignatureAlgorithm, UidaiTrust};
+use crate::EnclaveError;
+use base64::{engine::general_purpose, Engine as _};
+use roxmltree::{Document, Node, NodeId, NodeType};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+const DSIG_NS: &str = "http://www.w3.org/2000/09/xmldsig#";
+const C14N_10: &str = "http://www.w3.org/TR/2001/REC-xml-c14n-20010315";
+const ENVELOPED_SIGNATURE: &str = "http://www.w3.org/2000/09/xmldsig#enveloped-signature";
+const RSA_SHA1: &str = "http://www.w3.org/2000/09/xmldsig#rsa-sha1";
+const RSA_SHA256: &str = "http://www.w3.org/2001/04/xmldsig-more#rsa-sha256";
+const DIGEST_SHA1: &str = "http://www.w3.org/2000/09/xmldsig#sha1";
+const DIGEST_SHA256: &str = "http://www.w3.org/2001/04/xmlenc#sha256";
+
+/// Verify the enveloped XML-DSig signature on the document root: the single
+/// `Reference URI=""` must digest the canonical document without the
+/// `Signature` element, and `SignedInfo` must carry a UIDAI signature.
+pub fn verify_enveloped(doc: &Document, trust: &UidaiTrust) -> Result<(), EnclaveError> {
+    let root = doc.root_element();
+    let mut signatures = root
+        .children()
+        .filter(|n| n.is_element() && n.tag_name().namespace() == Some(DSIG_NS) && n.tag_name().name() == "Signature");
+    let signature = signatures.next().ok_or_else(|| invalid("document is not signed"))?;
+    if signatures.next().is_some() {
+        return Err(invalid("document has more than one signature"));
+    }
+
+    let signed_info = dsig_child(signature, "SignedInfo")?;
+    let c14n_method = algorithm(dsig_child(signed_info, "CanonicalizationMethod")?)?;
+    if c14n_method != C14N_10 {
+        return Err(invalid(&format!("unsupported canonicalization {}", c14n_method)));
+    }
+    let signature_algorithm = match algorithm(dsig_child(signed_info, "SignatureMethod")?)? {
+        RSA_SHA1 => SignatureAlgorithm::RsaSha1,
+        RSA_SHA256 => SignatureAlgorithm::RsaSha256,
+        other => return Err(invalid(&format!("unsupported signature method {}", other))),
+    };
+
+    let mut references = dsig_children(signed_info, "Reference");
+    let reference = references.next().ok_or_else(|| invalid("no Reference"))?;
+    if references.next().is_some() {
+        return Err(invalid("more than one Reference"));
+    }
+    if reference.attribute("URI") != Some("") {
+        return Err(invalid("Reference must cover the whole document"));
+    }
+    if let Ok(transforms) = dsig_child(reference, "Transforms") {
+        for transform in dsig_children(transforms, "Transform") {
+            let method = algorithm(transform)?;
+            if method != ENVELOPED_SIGNATURE && method != C14N_10 {
+                return Err(invalid(&format!("unsupported transform {}", method)));
+            }
+        }
+    }
+
+    // Reference digest over the document with the signature removed
+    let canonical_document = canonicalize(doc.input_text(), root, Some(signature.id()));
+    let digest = match algorithm(dsig_child(reference, "DigestMethod")?)? {
+        DIGEST_SHA1 => Sha1::digest(canonical_document.as_bytes()).to_vec(),
+        DIGEST_SHA256 => Sha256::digest(canonical_document.as_bytes()).to_vec(),
+        other => return Err(invalid(&format!("unsupported digest method {}", other))),
+    };
+    let expected_digest = decode_base64(dsig_child(reference, "DigestValue")?)?;
+    if digest != expected_digest {
+        return Err(EnclaveError::VerificationFailed(
+            "Aadhaar XML content does not match its signed digest".to_string(),
+        ));
+    }
+
+    let canonical_signed_info = canonicalize(doc.input_text(), signed_info, None);
+    let signature_value = decode_base64(dsig_child(signature, "SignatureValue")?)?;
+    trust.verify_with(signature_algorithm, canonical_signed_info.as_bytes(), &signature_value)
+}
+
+fn invalid(reason: &str) -> EnclaveError {
+    EnclaveError::InvalidInput(format!("Aadhaar XML signature: {}", reason))
+}
+
+fn dsig_children<'a, 'input>(
+    node: Node<'a, 'input>,
+    name: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |n| n.is_element() && n.tag_name().namespace() == Some(DSIG_NS) && n.tag_name().name() == name)
+}
+
+fn dsig_child<'a, 'input>(node: Node<'a, 'input>, name: &'static str) -> Result<Node<'a, 'input>, EnclaveError> {
+    dsig_children(node, name)
+        .next()
+        .ok_or_else(|| invalid(&format!("missing {}", name)))
+}
+
+fn algorithm<'a>(node: Node<'a, '_>) -> Result<&'a str, EnclaveError> {
+    node.attribute("Algorithm")
+        .ok_or_else(|| invalid(&format!("{} has no Algorithm", node.tag_name().name())))
+}
+
+fn decode_base64(node: Node) -> Result<Vec<u8>, EnclaveError> {
+    let text: String = node
+        .text()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    general_purpose::STANDARD
+        .decode(text)
+        .map_err(|e| invalid(&format!("{} is not base64: {}", node.tag_name().name(), e)))
+}
+
+// ====================================================================
+// Canonical XML 1.0 (inclusive, without comments)
+// ====================================================================
+
+/// Canonicalize the subtree at `node`, leaving out the element `exclude`.
+/// Namespaces in scope at `node` are rendered on it, as inclusive C14N requires
+/// for document subsets such as `SignedInfo`.
+pub fn canonicalize(input: &str, node: Node, exclude: Option<NodeId>) -> String {
+    let mut out = String::new();
+    write_element(&mut out, input, node, &BTreeMap::new(), exclude);
+    out
+}
+
+fn write_element(
+    out: &mut String,
+    input: &str,
+    node: Node,
+    rendered: &BTreeMap<String, String>,
+    exclude: Option<NodeId>,
+) {
+    let name = element_qname(input, node);
+    out.push('<');
+    out.push_str(name);
+
+    // Namespace declarations not already rendered by an output ancestor,
+    // default namespace first, then by prefix
+    let mut in_scope: BTreeMap<String, String> = node
+        .namespaces()
+        .filter(|ns| ns.name() != Some("xml"))
+        .map(|ns| (ns.name().unwrap_or_default().to_string(), ns.uri().to_string()))
+        .collect();
+    if !in_scope.contains_key("") && rendered.get("").is_some_and(|uri| !uri.is_empty()) {
+        in_scope.insert(String::new(), String::new());
+    }
+    let mut scope = rendered.clone();
+    for (prefix, uri) in &in_scope {
+        if rendered.get(prefix) == Some(uri) || (prefix.is_empty() && uri.is_empty() && !rendered.contains_key("")) {
+            continue;
+        }
+        if prefix.is_empty() {
+            out.push_str(" xmlns=\"");
+        } else {
+            out.push_str(" xmlns:");
+            out.push_str(prefix);
+            out.push_str("=\"");
+        }
+        escape_attribute(out, uri);
+        out.push('"');
+        scope.insert(prefix.clone(), uri.clone());
+    }
+
+    // Attributes ordered by namespace URI, then local name
+    let mut attributes: Vec<_> = node.attributes().collect();
+    attributes.sort_by(|a, b| {
+        (a.namespace().unwrap_or_default(), a.name()).cmp(&(b.namespace().unwrap_or_default(), b.name()))
+    });
+    for attribute in attributes {
+        out.push(' ');
+        out.push_str(&input[attribute.range_qname()]);
+        out.push_str("=\"");
+        escape_attribute(out, attribute.value());
+        out.push('"');
+    }
+    out.push('>');
+
+    for child in node.children() {
+        match child.node_type() {
+            NodeType::Element if Some(child.id()) != exclude => {
+                write_element(out, input, child, &scope, exclude)
+            }
+            NodeType::Text => escape_text(out, child.text().unwrap_or_default()),
+            NodeType::PI => {
+                if let Some(pi) = child.pi() {
+                    out.push_str("<?");
+                    out.push_str(pi.target);
+                    if let Some(value) = pi.value {
+                        out.push(' ');
+                        out.push_str(value);
+                    }
+                    out.push_str("?>");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    out.push_str("</");
+    out.push_str(name);
+    out.push('>');
+}
+
+/// Qualified element name exactly as written in the source (keeps the prefix).
+fn element_qname<'a>(input: &'a str, node: Node) -> &'a str {
+    let start = node.range().start + 1;
+    let tail = &input[start..];
+    let end = tail
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(tail.len());
+    &tail[..end]
+}
+
+fn escape_text(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\r' => out.push_str("&#xD;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+fn escape_attribute(out: &mut String, value: &str) {
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' => out.push_str("&#x9;"),
+            '\n' => out.push_str("&#xA;"),
+            '\r' => out.push_str("&#xD;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonicalizes_subset_with_inherited_namespace() {
+        let xml = "<Root b='2' a=\"1\"><Signature xmlns=\"urn:sig\"><SignedInfo><Empty/></SignedInfo></Signature>\
+                   <Text>a &amp; b &lt; c\r\n</Text></Root>";
+        let doc = Document::parse(xml).unwrap();
+        let root = doc.root_element();
+        let signature = root.first_element_child().unwrap();
+        let signed_info = signature.first_element_child().unwrap();
+
+        assert_eq!(
+            canonicalize(xml, signed_info, None),
+            "<SignedInfo xmlns=\"urn:sig\"><Empty></Empty></SignedInfo>"
+        );
+        assert_eq!(
+            canonicalize(xml, root, Some(signature.id())),
+            "<Root a=\"1\" b=\"2\"><Text>a &amp; b &lt; c\n</Text></Root>"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+// age.rs
+use crate::evidence::DID_AGE_VERIFY;
+use crate::EnclaveError;
+use chrono::{DateTime, Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::{error, info, warn};
+
+/// Date of birth from a verified document. It never leaves the enclave: the
+/// `Debug` output is redacted and there is no `Display`.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct DateOfBirth(String);
+
+impl DateOfBirth {
+    pub fn new(raw: impl Into<String>) -> Self {
+        Self(raw.into())
+    }
+
+    /// `YYYY-MM-DD` form, so the same DOB matches across document formats.
+    pub(crate) fn canonical(&self) -> Result<String, EnclaveError> {
+        Ok(self.latest_date()?.format("%Y-%m-%d").to_string())
+    }
+
+    /// Latest date consistent with the printed DOB. UIDAI prints `DD-MM-YYYY`,
+    /// `DD/MM/YYYY` or just `YYYY`; a bare year is taken as 31 December so the
+    /// computed age is never overstated.
+    fn latest_date(&self) -> Result<NaiveDate, EnclaveError> {
+        let raw = self.0.trim();
+        ["%d-%m-%Y", "%d/%m/%Y", "%Y-%m-%d"]
+            .iter()
+            .find_map(|format| NaiveDate::parse_from_str(raw, format).ok())
+            .or_else(|| {
+                (raw.len() == 4)
+                    .then(|| raw.parse::<i32>().ok())
+                    .flatten()
+                    .and_then(|year| NaiveDate::from_ymd_opt(year, 12, 31))
+            })
+            // The value itself is never echoed back
+            .ok_or_else(|| EnclaveError::InvalidInput("Unrecognized date of birth format".to_string()))
+    }
+}
+
+impl std::fmt::Debug for DateOfBirth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DateOfBirth(<redacted>)")
+    }
+}
+
+/// The only age information that leaves the enclave.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgePredicate {
+    /// Whether the holder was at least `threshold_years` old on `reference_date`
+    pub satisfied: bool,
+    pub threshold_years: u32,
+    /// UTC date the predicate was evaluated for (`YYYY-MM-DD`)
+    pub reference_date: String,
+}
+
+/// DID types whose claim is an age check, so they cannot be issued without a threshold.
+const AGE_GATED_DID_TYPES: &[u8] = &[DID_AGE_VERIFY];
+
+/// Minimum ages per DID type.
+pub struct AgePolicy {
+    thresholds: HashMap<u8, u32>,
+}
+
+impl AgePolicy {
+    /// Load from `AGE_THRESHOLDS`, comma separated `did_type=years` pairs
+    /// (default `1=18`, the age DID).
+    pub fn from_env() -> Self {
+        let spec = std::env::var("AGE_THRESHOLDS").unwrap_or_else(|_| format!("{}=18", DID_AGE_VERIFY));
+        let mut thresholds = HashMap::new();
+        for pair in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match pair
+                .split_once('=')
+                .and_then(|(did, years)| Some((did.trim().parse::<u8>().ok()?, years.trim().parse::<u32>().ok()?)))
+            {
+                Some((did_type, years)) => {
+                    thresholds.insert(did_type, years);
+                }
+                None => warn!("Ignoring invalid AGE_THRESHOLDS entry: {}", pair),
+            }
+        }
+        for did_type in AGE_GATED_DID_TYPES.iter().filter(|t| !thresholds.contains_key(t)) {
+            error!("AGE_THRESHOLDS has no entry for DID type {}; it will be refused", did_type);
+        }
+        info!("Age thresholds per DID type: {:?}", thresholds);
+        Self { thresholds }
+    }
+
+    /// Minimum age required for `did_type`, if it is age-gated.
+    pub fn threshold(&self, did_type: u8) -> Option<u32> {
+        self.thresholds.get(&did_type).copied()
+    }
+
+    /// Evaluate the age predicate for `did_type` as of `timestamp_ms` (UTC).
+    /// Returns `None` for DID types without an age threshold, and fails for
+    /// age-gated DID types that have none configured.
+    pub fn evaluate(
+        &self,
+        did_type: u8,
+        date_of_birth: Option<&DateOfBirth>,
+        timestamp_ms: u64,
+    ) -> Result<Option<AgePredicate>, EnclaveError> {
+        let Some(threshold_years) = self.threshold(did_type) else {
+            if AGE_GATED_DID_TYPES.contains(&did_type) {
+                return Err(EnclaveError::InternalError(format!(
+                    "No age threshold configured for DID type {}",
+                    did_type
+                )));
+            }
+            return Ok(None);
+        };
+        let date_of_birth = date_of_birth.ok_or_else(|| {
+            EnclaveError::VerificationFailed(
+                "This DID type requires a date of birth from a verified document".to_string(),
+            )
+        })?;
+
+        let reference = DateTime::from_timestamp_millis(timestamp_ms as i64)
+            .ok_or_else(|| EnclaveError::InternalError("Invalid enclave timestamp".to_string()))?
+            .date_naive();
+        let born = date_of_birth.latest_date()?;
+
+        Ok(Some(AgePredicate {
+            satisfied: completed_years(born, reference) >= threshold_years as i32,
+            threshold_years,
+            reference_date: reference.format("%Y-%m-%d").to_string(),
+        }))
+    }
+}
+
+/// Whole years between `born` and `on`; a 29 February birthday completes a
+/// year on 1 March in non-leap years.
+fn completed_years(born: NaiveDate, on: NaiveDate) -> i32 {
+    let mut years = on.year() - born.year();
+    if (on.month(), on.day()) < (born.month(), born.day()) {
+        years -= 1;
+    }
+    years
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evidence::DID_CITIZENSHIP_VERIFY;
+
+    // 2024-06-15T00:00:00Z
+    const JUNE_15_2024_MS: u64 = 1_718_409_600_000;
+
+    fn policy(thresholds: &[(u8, u32)]) -> AgePolicy {
+        AgePolicy { thresholds: thresholds.iter().copied().collect() }
+    }
+
+    #[test]
+    fn evaluates_threshold_on_the_reference_date() {
+        let policy = policy(&[(DID_AGE_VERIFY, 18)]);
+        let adult = DateOfBirth::new("15-06-2006");
+        let minor = DateOfBirth::new("16/06/2006");
+        let predicate = policy.evaluate(DID_AGE_VERIFY, Some(&adult), JUNE_15_2024_MS).unwrap().unwrap();
+        assert!(predicate.satisfied);
+        assert_eq!(predicate.reference_date, "2024-06-15");
+        assert!(!policy.evaluate(DID_AGE_VERIFY, Some(&minor), JUNE_15_2024_MS).unwrap().unwrap().satisfied);
+        // A bare year is read as 31 December
+        let year_only = DateOfBirth::new("2006");
+        assert!(!policy.evaluate(DID_AGE_VERIFY, Some(&year_only), JUNE_15_2024_MS).unwrap().unwrap().satisfied);
+        assert!(policy.evaluate(DID_AGE_VERIFY, None, JUNE_15_2024_MS).is_err());
+    }
+
+    #[test]
+    fn age_did_without_threshold_fails_closed() {
+        let policy = policy(&[]);
+        let dob = DateOfBirth::new("01-01-1990");
+        assert!(matches!(
+            policy.evaluate(DID_AGE_VERIFY, Some(&dob), JUNE_15_2024_MS),
+            Err(EnclaveError::InternalError(_))
+        ));
+        assert!(policy.evaluate(DID_CITIZENSHIP_VERIFY, Some(&dob), JUNE_15_2024_MS).unwrap().is_none());
+    }
+
+    #[test]
+    fn leap_day_birthday_completes_on_first_of_march() {
+        let born = NaiveDate::from_ymd_opt(2004, 2, 29).unwrap();
+        assert_eq!(completed_years(born, NaiveDate::from_ymd_opt(2022, 2, 28).unwrap()), 17);
+        assert_eq!(completed_years(born, NaiveDate::from_ymd_opt(2022, 3, 1).unwrap()), 18);
+    }
+}
@@ -1,15 +1,24 @@
 // app.rs
 use crate::common::{to_signed_response, IntentScope, ProcessDataRequest, ProcessedDataResponse};
 use crate::{AppState, EnclaveError};
-use axum::extract::State;
-use axum::Json;
+use crate::auth::AuthenticatedClient;
+use crate::limits::LimitKind;
+use axum::extract::{ConnectInfo, State};
+use axum::{Extension, Json};
+use std::net::SocketAddr;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use base64::{Engine as _, engine::general_purpose};
-use fastcrypto::ed25519::Ed25519KeyPair;
-use fastcrypto::traits::KeyPair as FcKeyPair;
-use fastcrypto::traits::ToFromBytes;
 use crate::common::IntentMessage;
+use crate::metrics::metrics;
+use tracing::info;
+use crate::age::AgePredicate;
+use crate::documents::{DocumentInput, DocumentType, IdentityRecord};
+use crate::rules::{check_document_supports, DocumentClass};
+use crate::redact::SecretBytes;
+use crate::evidence::{check_did_type, EvidenceOpening, FieldOpening};
+use crate::verifier::VerificationInput;
+use crate::wallet::{evidence_opening_challenge, kyc_challenge, normalize_sui_address};
 
 
 // Add KYC structures and functions
@@ -18,76 +27,360 @@ pub struct KYCRequest {
     pub encrypted_doc: String,
     pub encrypted_faces: Vec<String>,
     pub encrypted_session_key: String,
+    /// Kind of issuer-signed document in `encrypted_document_data`
+    #[serde(default)]
+    pub document_type: Option<DocumentType>,
+    /// Issuer-signed document (e.g. Aadhaar QR text or e-KYC ZIP), encrypted like the document
+    #[serde(default)]
+    pub encrypted_document_data: Option<String>,
+    /// Secret that opens the document data (e.g. the Aadhaar share code)
+    #[serde(default)]
+    pub document_secret: Option<String>,
+    /// Mobile number to check against the document
+    #[serde(default)]
+    pub contact_mobile: Option<String>,
+    /// Email address to check against the document
+    #[serde(default)]
+    pub contact_email: Option<String>,
+    /// Face embedding of the document photo
+    #[serde(default)]
+    pub document_embedding: Vec<f32>,
+    /// One face embedding per entry in `encrypted_faces`
+    #[serde(default)]
+    pub face_embeddings: Vec<Vec<f32>>,
     pub wallet_address: String,
+    /// DID being requested (1 = age, 2 = citizenship, as in did_registry.move)
+    pub did_type: u8,
+    /// Client-generated nonce, unique per submission
+    pub nonce: String,
+    /// Client time of submission (ms since epoch), checked against the skew window
+    pub timestamp_ms: u64,
+    /// Base64 Sui personal-message signature by `wallet_address` over
+    /// `kyc_challenge` of this request's nonce, timestamp, DID type and blobs
+    pub wallet_signature: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct KYCResponse {
     pub verified: bool,
     pub wallet_address: String,
-    pub attestation_hash: String,
+    pub did_type: u8,
+    /// Hex commitment to the verified evidence, stored as `UserDID.evidence_hash`
+    pub evidence_hash: String,
+    /// Version of the identity check that produced `verified`
+    pub check_version: String,
+    /// Class of the verified document that backs the claim
+    pub document_class: DocumentClass,
+    /// Age predicate for age-gated DID types; the date of birth itself is never included
+    pub age: Option<AgePredicate>,
+    /// Hex nullifier of the document for this DID type, if it has a stable identity
+    pub nullifier: Option<String>,
+    /// Request nonce, binding the signed response to one session
+    pub nonce: String,
 }
 
 pub async fn process_kyc(
     State(state): State<Arc<AppState>>,
+    ConnectInfo(source): ConnectInfo<SocketAddr>,
+    client: Option<Extension<AuthenticatedClient>>,
     Json(request): Json<ProcessDataRequest<KYCRequest>>,
 ) -> Result<Json<ProcessedDataResponse<IntentMessage<KYCResponse>>>, EnclaveError>{
-    let kyc_data = &request.payload;
-    
+    let client_id = client.as_ref().map(|Extension(c)| c.id.as_str());
+    let result = match enforce_limits(&state, source, client_id, &request.payload).await {
+        Ok(()) => {
+            state.wallet_verifier.refresh_epoch().await;
+            handle_kyc(&state, &request.payload)
+        }
+        Err(e) => Err(e),
+    };
+
+    let outcome = match &result {
+        Ok(signed) if signed.response.data.verified => "verified",
+        Ok(_) => "rejected",
+        Err(EnclaveError::RateLimited { .. }) => "rate_limited",
+        Err(_) => "error",
+    };
+    metrics().record_kyc(outcome);
+
+    result.map(Json)
+}
+
+/// Frame limits first (cheap), then token buckets per source, client and wallet.
+async fn enforce_limits(
+    state: &AppState,
+    source: SocketAddr,
+    client_id: Option<&str>,
+    kyc_data: &KYCRequest,
+) -> Result<(), EnclaveError> {
+    state
+        .request_limits
+        .check_frames(&kyc_data.encrypted_doc, &kyc_data.encrypted_faces)?;
+
+    state
+        .rate_limiter
+        .check(LimitKind::Source, &source.ip().to_string())
+        .await?;
+    if let Some(client_id) = client_id {
+        state.rate_limiter.check(LimitKind::Client, client_id).await?;
+    }
+    let wallet = normalize_sui_address(&kyc_data.wallet_address)?;
+    state.rate_limiter.check(LimitKind::Wallet, &wallet).await
+}
+
+fn handle_kyc(
+    state: &AppState,
+    kyc_data: &KYCRequest,
+) -> Result<ProcessedDataResponse<IntentMessage<KYCResponse>>, EnclaveError> {
+    let timestamp_ms = current_timestamp()?;
+
+    // Reject stale submissions before doing any work
+    state.nonces.validate(&kyc_data.nonce, kyc_data.timestamp_ms, timestamp_ms)?;
+    check_did_type(kyc_data.did_type)?;
+
+    // The wallet must sign this exact submission before it can be attested
+    let wallet_address = normalize_sui_address(&kyc_data.wallet_address)?;
+    let challenge = kyc_challenge(
+        &kyc_data.nonce,
+        kyc_data.timestamp_ms,
+        kyc_data.did_type,
+        &kyc_data.encrypted_doc,
+        &kyc_data.encrypted_faces,
+        kyc_data.encrypted_document_data.as_deref(),
+    );
+    state.wallet_verifier.verify_personal_message(
+        challenge.as_bytes(),
+        &kyc_data.wallet_signature,
+        &wallet_address,
+    )?;
+
+    // Only a signed submission may use up its nonce
+    state.nonces.check(&kyc_data.nonce, kyc_data.timestamp_ms, timestamp_ms)?;
+
     // For demo, simple decryption (in production, use proper crypto)
     let doc_data = decrypt_demo(&kyc_data.encrypted_doc)?;
-    let face_frames: Vec<Vec<u8>> = kyc_data.encrypted_faces
+    let face_frames: Vec<SecretBytes> = kyc_data.encrypted_faces
         .iter()
         .map(|f| decrypt_demo(f))
         .collect::<Result<Vec<_>, _>>()?;
     
+    // Decode, strip metadata and normalize before anything looks at the pixels
+    let document_image = state.media.decode("Document", &doc_data)?;
+    let face_images = state.media.decode_frames(&face_frames)?;
+
+    // An issuer-signed document makes the identity data authoritative
+    let identity_record = read_document(state, kyc_data, timestamp_ms)?;
+
+    // Only issue DID types the document can actually back
+    let document_class = identity_record
+        .as_ref()
+        .map_or(DocumentClass::Unverified, |r| r.document_class);
+    let issuer_signed = identity_record.as_ref().is_some_and(|r| r.issuer_signed);
+    check_document_supports(document_class, issuer_signed, kyc_data.did_type)?;
+
+    // One document backs one wallet per DID type; fail before the expensive checks
+    let nullifier = match &identity_record {
+        Some(record) => record
+            .identity_key()?
+            .map(|key| state.nullifiers.derive(&key, kyc_data.did_type)),
+        None => None,
+    };
+    if let Some(nullifier) = &nullifier {
+        state.nullifiers.check(nullifier, &wallet_address)?;
+    }
+
     // Verify faces match and liveness
-    let verification_result = verify_identity(doc_data, face_frames)?;
-    
-    // Generate attestation
-    let attestation_hash = generate_attestation_hash(&state.eph_kp, &verification_result)?;
- 
-    
+    let verification = state.verifier.verify(&VerificationInput {
+        document: &document_image,
+        faces: &face_images,
+        document_embedding: &kyc_data.document_embedding,
+        face_embeddings: &kyc_data.face_embeddings,
+        document_record: identity_record.as_ref(),
+    })?;
+    info!(
+        "Identity check {}: verified={} match={:.3} liveness={:.3}",
+        verification.model_version,
+        verification.verified,
+        verification.match_score,
+        verification.liveness_score
+    );
+    for reason in verification.reasons.iter().filter(|r| !r.passed) {
+        info!("Identity check failed {}: {}", reason.check, reason.detail);
+    }
+
+    // Age is derived here from the verified DOB, which never leaves the enclave
+    let age = state.age_policy.evaluate(
+        kyc_data.did_type,
+        identity_record.as_ref().and_then(|r| r.date_of_birth.as_ref()),
+        timestamp_ms,
+    )?;
+
+    // Commit to what was verified by digest and predicate only; the salt stays
+    // in memory so the wallet can open single fields later
+    let opening = EvidenceOpening::new(
+        &wallet_address,
+        &doc_data,
+        identity_record.as_ref(),
+        document_class,
+        nullifier.clone(),
+        age.as_ref(),
+        &face_frames,
+        kyc_data.did_type,
+        timestamp_ms,
+        &kyc_data.nonce,
+    );
+    let evidence_hash = opening.evidence_hash()?;
+    let verified = verification.verified && age.as_ref().is_none_or(|a| a.satisfied);
+    if let Some(nullifier) = nullifier.as_ref().filter(|_| verified) {
+        state.nullifiers.bind(nullifier, &wallet_address, timestamp_ms)?;
+    }
+    state.evidence.record(&evidence_hash, opening, verified);
+
     let response = KYCResponse {
-        verified: verification_result,
-        wallet_address: kyc_data.wallet_address.clone(),
-        attestation_hash,
+        verified,
+        wallet_address,
+        did_type: kyc_data.did_type,
+        evidence_hash,
+        check_version: verification.model_version,
+        document_class,
+        age,
+        nullifier,
+        nonce: kyc_data.nonce.clone(),
     };
 
-    Ok(Json(to_signed_response(
+    Ok(to_signed_response(
         &state.eph_kp,
         response,
-        current_timestamp()?,
+        timestamp_ms,
         IntentScope::KYCVerification,
-    )))
+    ))
 }
 
-fn decrypt_demo(encrypted: &str) -> Result<Vec<u8>, EnclaveError> {
-    general_purpose::STANDARD
-        .decode(encrypted)
-        .map_err(|e| EnclaveError::GenericError(format!("Decryption failed: {}", e)))
+/// Parse and verify the issuer-signed document, if one was submitted.
+fn read_document(
+    state: &AppState,
+    kyc_data: &KYCRequest,
+    timestamp_ms: u64,
+) -> Result<Option<IdentityRecord>, EnclaveError> {
+    let (document_type, encrypted) = match (kyc_data.document_type, &kyc_data.encrypted_document_data) {
+        (Some(document_type), Some(encrypted)) => (document_type, encrypted),
+        (None, None) => return Ok(None),
+        _ => {
+            return Err(EnclaveError::InvalidInput(
+                "document_type and encrypted_document_data must be submitted together".to_string(),
+            ))
+        }
+    };
+    let data = decrypt_demo(encrypted)?;
+    let record = state.documents.parse(
+        document_type,
+        kyc_data.did_type,
+        &DocumentInput {
+            data: &data,
+            secret: kyc_data.document_secret.as_deref(),
+            mobile: kyc_data.contact_mobile.as_deref(),
+            email: kyc_data.contact_email.as_deref(),
+            timestamp_ms,
+        },
+    )?;
+    info!(
+        "Verified {} document ({})",
+        document_type.as_str(),
+        record.document_class.as_str()
+    );
+    Ok(Some(record))
 }
 
-fn verify_identity(doc: Vec<u8>, faces: Vec<Vec<u8>>) -> Result<bool, EnclaveError> {
-    Ok(!doc.is_empty() && faces.len() >= 5)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevokeBindingRequest {
+    pub nullifier: String,
+    pub wallet_address: String,
 }
 
-fn generate_attestation_hash(
-    keypair: &Ed25519KeyPair, 
-    verified: &bool
-) -> Result<String, EnclaveError> {
-    use sha2::{Sha256, Digest};
-    
-    let mut hasher = Sha256::new();
-    hasher.update(verified.to_string());
-    hasher.update(keypair.public().as_bytes());
-    
-    Ok(hex::encode(hasher.finalize()))
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RevokeBindingResponse {
+    pub nullifier: String,
+    pub wallet_address: String,
+    pub revoked: bool,
+}
+
+/// Revoke a nullifier's binding to a wallet, so that under the
+/// `rebind_after_revocation` policy the document can verify another wallet.
+pub async fn revoke_binding(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ProcessDataRequest<RevokeBindingRequest>>,
+) -> Result<Json<RevokeBindingResponse>, EnclaveError> {
+    let wallet_address = normalize_sui_address(&request.payload.wallet_address)?;
+    state
+        .nullifiers
+        .revoke(&request.payload.nullifier, &wallet_address, current_timestamp()?)?;
+    info!("Revoked nullifier binding for {}", wallet_address);
+    Ok(Json(RevokeBindingResponse {
+        nullifier: request.payload.nullifier,
+        wallet_address,
+        revoked: true,
+    }))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenEvidenceRequest {
+    pub evidence_hash: String,
+    /// Names of the committed fields to open, e.g. `age_satisfied`
+    pub fields: Vec<String>,
+    /// Wallet the evidence was issued to
+    pub wallet_address: String,
+    /// Client-generated nonce, unique per request
+    pub nonce: String,
+    /// Client time of the request (ms since epoch), checked against the skew window
+    pub timestamp_ms: u64,
+    /// Base64 Sui personal-message signature by `wallet_address` over
+    /// `evidence_opening_challenge` of this request
+    pub wallet_signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OpenEvidenceResponse {
+    pub evidence_hash: String,
+    pub openings: Vec<FieldOpening>,
+}
+
+/// Export selective openings of an evidence commitment at the request of the
+/// wallet it was issued to; fields not asked for stay hidden behind their
+/// salted leaves.
+pub async fn open_evidence(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ProcessDataRequest<OpenEvidenceRequest>>,
+) -> Result<Json<OpenEvidenceResponse>, EnclaveError> {
+    let request = request.payload;
+    let timestamp_ms = current_timestamp()?;
+    state.nonces.validate(&request.nonce, request.timestamp_ms, timestamp_ms)?;
+
+    let evidence_hash = request.evidence_hash.to_ascii_lowercase();
+    let wallet_address = normalize_sui_address(&request.wallet_address)?;
+    let challenge =
+        evidence_opening_challenge(&request.nonce, request.timestamp_ms, &evidence_hash, &request.fields);
+    state.wallet_verifier.refresh_epoch().await;
+    state.wallet_verifier.verify_personal_message(
+        challenge.as_bytes(),
+        &request.wallet_signature,
+        &wallet_address,
+    )?;
+    state.nonces.check(&request.nonce, request.timestamp_ms, timestamp_ms)?;
+
+    let openings = state.evidence.open_fields(&evidence_hash, &wallet_address, &request.fields)?;
+    info!("Opened {} field(s) of evidence {} for its wallet", openings.len(), evidence_hash);
+    Ok(Json(OpenEvidenceResponse { evidence_hash, openings }))
+}
+
+fn decrypt_demo(encrypted: &str) -> Result<SecretBytes, EnclaveError> {
+    general_purpose::STANDARD
+        .decode(encrypted)
+        .map(SecretBytes::new)
+        .map_err(|e| EnclaveError::DecryptionFailed(format!("Decryption failed: {}", e)))
 }
 
 fn current_timestamp() -> Result<u64, EnclaveError> {
     std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .map(|d| d.as_millis() as u64)
-        .map_err(|e| EnclaveError::GenericError(format!("Time error: {}", e)))
+        .map_err(|e| EnclaveError::InternalError(format!("Time error: {}", e)))
 }
\ No newline at end of file
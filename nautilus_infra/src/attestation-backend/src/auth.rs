@@ -0,0 +1,468 @@
+// auth.rs
+use crate::replay::{NonceStore, ReplayConfig};
+use crate::EnclaveError;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, HeaderName, Method};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::MethodRouter;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use subtle::ConstantTimeEq;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+use tracing::{info, warn};
+
+pub const API_KEY_HEADER: &str = "x-api-key";
+pub const CLIENT_ID_HEADER: &str = "x-client-id";
+pub const TIMESTAMP_HEADER: &str = "x-timestamp";
+pub const SIGNATURE_HEADER: &str = "x-signature";
+pub const NONCE_HEADER: &str = "x-nonce";
+
+/// What a route requires from the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthRequirement {
+    /// Anyone may call the route (ping, health, attestation).
+    Public,
+    /// Caller must present a valid API key or a signed request.
+    Client,
+}
+
+/// A configured server-to-server client.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClientConfig {
+    pub id: String,
+    /// Hex SHA-256 of the client's API key; the key itself is never stored.
+    #[serde(default)]
+    pub api_key_sha256: Option<String>,
+    /// Hex HMAC-SHA256 secret for signed requests.
+    #[serde(default)]
+    pub hmac_secret: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ClientsFile {
+    #[serde(default)]
+    clients: Vec<ClientConfig>,
+}
+
+/// Authentication and CORS settings.
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub allowed_origins: Vec<String>,
+    pub clients: Vec<ClientConfig>,
+    /// Maximum clock skew accepted for signed requests.
+    pub max_skew: Duration,
+    /// Maximum number of signed-request nonces remembered at once.
+    pub nonce_capacity: usize,
+    /// Skip client authentication entirely (local development only).
+    pub disabled: bool,
+}
+
+impl AuthConfig {
+    /// Load from `CORS_ALLOWED_ORIGINS` (comma separated, `*` for any),
+    /// `AUTH_CLIENTS_FILE` (default `auth_clients.yaml`), `AUTH_MAX_SKEW_SECS`,
+    /// `AUTH_NONCE_CAPACITY` and `AUTH_DISABLED`.
+    pub fn from_env() -> Self {
+        let allowed_origins = std::env::var("CORS_ALLOWED_ORIGINS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|o| o.trim().to_string())
+            .filter(|o| !o.is_empty())
+            .collect();
+
+        let path = std::env::var("AUTH_CLIENTS_FILE")
+            .unwrap_or_else(|_| "auth_clients.yaml".to_string());
+        let clients = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_yaml::from_str::<ClientsFile>(&content)
+                .map(|f| f.clients)
+                .unwrap_or_else(|e| {
+                    warn!("Failed to parse {}: {}", path, e);
+                    Vec::new()
+                }),
+            Err(e) => {
+                info!("No auth clients file at {}: {}", path, e);
+                Vec::new()
+            }
+        };
+
+        Self {
+            allowed_origins,
+            clients,
+            max_skew: Duration::from_secs(
+                std::env::var("AUTH_MAX_SKEW_SECS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(300),
+            ),
+            nonce_capacity: std::env::var("AUTH_NONCE_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100_000),
+            disabled: std::env::var("AUTH_DISABLED")
+                .map(|v| v == "true" || v == "1")
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Identity of an authenticated caller, added to request extensions.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedClient {
+    pub id: String,
+}
+
+struct ClientSecrets {
+    api_key_sha256: Option<[u8; 32]>,
+    hmac_secret: Option<Vec<u8>>,
+}
+
+/// Checks API keys and request signatures against the configured clients.
+pub struct Authenticator {
+    clients: HashMap<String, ClientSecrets>,
+    max_skew: Duration,
+    /// Nonces of signed requests accepted within the skew window
+    nonces: NonceStore,
+    /// Upper bound on bodies buffered to check a request signature; the same
+    /// limit the routes enforce, so signing cannot be used to bypass it.
+    max_body_bytes: usize,
+    disabled: bool,
+}
+
+impl Authenticator {
+    pub fn new(config: &AuthConfig, max_body_bytes: usize) -> Self {
+        let mut clients = HashMap::new();
+        for client in &config.clients {
+            let api_key_sha256 = client.api_key_sha256.as_deref().and_then(|h| {
+                hex::decode(h)
+                    .ok()
+                    .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+                    .or_else(|| {
+                        warn!("Client {}: api_key_sha256 is not 32 hex bytes, ignoring", client.id);
+                        None
+                    })
+            });
+            let hmac_secret = client.hmac_secret.as_deref().and_then(|s| {
+                hex::decode(s).ok().or_else(|| {
+                    warn!("Client {}: hmac_secret is not hex, ignoring", client.id);
+                    None
+                })
+            });
+            clients.insert(
+                client.id.clone(),
+                ClientSecrets {
+                    api_key_sha256,
+                    hmac_secret,
+                },
+            );
+        }
+
+        if config.disabled {
+            warn!("AUTH_DISABLED is set: protected routes accept unauthenticated requests");
+        } else if clients.is_empty() {
+            warn!("No API clients configured: protected routes will reject every request");
+        }
+
+        Self {
+            clients,
+            max_skew: config.max_skew,
+            nonces: NonceStore::new(ReplayConfig {
+                max_skew: config.max_skew,
+                capacity: config.nonce_capacity,
+            }),
+            max_body_bytes,
+            disabled: config.disabled,
+        }
+    }
+
+    /// Authenticate with `x-api-key` (any client whose key hash matches).
+    fn check_api_key(&self, key: &str) -> Result<AuthenticatedClient, String> {
+        let digest: [u8; 32] = Sha256::digest(key.as_bytes()).into();
+        self.clients
+            .iter()
+            .find(|(_, secrets)| {
+                secrets
+                    .api_key_sha256
+                    .is_some_and(|expected| bool::from(expected.ct_eq(&digest)))
+            })
+            .map(|(id, _)| AuthenticatedClient { id: id.clone() })
+            .ok_or_else(|| "unknown API key".to_string())
+    }
+
+    /// Authenticate a signed request: `x-signature` is hex
+    /// HMAC-SHA256(secret, "{METHOD}\n{path}\n{x-timestamp}\n{x-nonce}\n{hex sha256(body)}").
+    /// The nonce is recorded only once the MAC checks out, so a signed request
+    /// is accepted at most once and forged ones cannot burn nonces.
+    fn check_signature(
+        &self,
+        method: &Method,
+        path: &str,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<AuthenticatedClient, String> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| format!("missing {} header", name))
+        };
+        let client_id = header(CLIENT_ID_HEADER)?;
+        let timestamp = header(TIMESTAMP_HEADER)?;
+        let nonce = header(NONCE_HEADER)?;
+        let signature = hex::decode(header(SIGNATURE_HEADER)?)
+            .map_err(|_| "signature is not hex".to_string())?;
+
+        let timestamp_ms: u64 = timestamp
+            .parse()
+            .map_err(|_| "invalid timestamp".to_string())?;
+        let now_ms = now_ms();
+        let skew = now_ms.abs_diff(timestamp_ms);
+        if skew > self.max_skew.as_millis() as u64 {
+            return Err(format!("timestamp outside {:?} window", self.max_skew));
+        }
+
+        let secret = self
+            .clients
+            .get(client_id)
+            .and_then(|c| c.hmac_secret.as_ref())
+            .ok_or_else(|| format!("unknown signing client '{}'", client_id))?;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret)
+            .map_err(|_| "invalid client secret".to_string())?;
+        mac.update(
+            format!(
+                "{}\n{}\n{}\n{}\n{}",
+                method.as_str(),
+                path,
+                timestamp,
+                nonce,
+                hex::encode(Sha256::digest(body))
+            )
+            .as_bytes(),
+        );
+        mac.verify_slice(&signature)
+            .map_err(|_| "signature mismatch".to_string())?;
+        self.nonces
+            .check(nonce, timestamp_ms, now_ms)
+            .map_err(|e| e.message().to_string())?;
+
+        Ok(AuthenticatedClient {
+            id: client_id.to_string(),
+        })
+    }
+}
+
+/// Apply the auth requirement a route declares.
+pub fn with_auth<S>(
+    route: MethodRouter<S>,
+    requirement: AuthRequirement,
+    auth: &Arc<Authenticator>,
+) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    match requirement {
+        AuthRequirement::Public => route,
+        AuthRequirement::Client => {
+            route.route_layer(middleware::from_fn_with_state(auth.clone(), require_client))
+        }
+    }
+}
+
+async fn require_client(
+    State(auth): State<Arc<Authenticator>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if auth.disabled {
+        return next.run(request).await;
+    }
+
+    let source = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let path = request.uri().path().to_string();
+    let claimed_client = request
+        .headers()
+        .get(CLIENT_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-")
+        .to_string();
+
+    let (result, request) = if let Some(key) = request.headers().get(API_KEY_HEADER) {
+        let result = key
+            .to_str()
+            .map_err(|_| "malformed API key".to_string())
+            .and_then(|key| auth.check_api_key(key));
+        (result, request)
+    } else if request.headers().contains_key(SIGNATURE_HEADER) {
+        let (parts, body) = request.into_parts();
+        match axum::body::to_bytes(body, auth.max_body_bytes).await {
+            Ok(bytes) => {
+                let result = auth.check_signature(&parts.method, &path, &parts.headers, &bytes);
+                (result, Request::from_parts(parts, Body::from(bytes)))
+            }
+            Err(_) => (
+                Err("body too large to verify".to_string()),
+                Request::from_parts(parts, Body::empty()),
+            ),
+        }
+    } else {
+        (Err("no credentials".to_string()), request)
+    };
+
+    match result {
+        Ok(client) => {
+            info!(target: "audit", "auth ok: client={} route={} source={}", client.id, path, source);
+            let mut request = request;
+            request.extensions_mut().insert(client);
+            next.run(request).await
+        }
+        Err(reason) => {
+            warn!(
+                target: "audit",
+                "auth failed: client={} route={} source={} reason={}",
+                claimed_client, path, source, reason
+            );
+            EnclaveError::Unauthorized(format!("Authentication failed: {}", reason)).into_response()
+        }
+    }
+}
+
+/// CORS restricted to the configured origins; `*` allows any origin.
+pub fn cors_layer(config: &AuthConfig) -> CorsLayer {
+    let cors = CorsLayer::new()
+        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        .allow_headers([
+            axum::http::header::CONTENT_TYPE,
+            axum::http::header::AUTHORIZATION,
+            HeaderName::from_static(API_KEY_HEADER),
+            HeaderName::from_static(CLIENT_ID_HEADER),
+            HeaderName::from_static(TIMESTAMP_HEADER),
+            HeaderName::from_static(SIGNATURE_HEADER),
+            HeaderName::from_static(NONCE_HEADER),
+            HeaderName::from_static(crate::request_id::REQUEST_ID_HEADER),
+        ]);
+
+    if config.allowed_origins.iter().any(|o| o == "*") {
+        warn!("CORS allows any origin");
+        return cors.allow_origin(Any);
+    }
+
+    let origins: Vec<_> = config
+        .allowed_origins
+        .iter()
+        .filter_map(|o| match o.parse() {
+            Ok(origin) => Some(origin),
+            Err(_) => {
+                warn!("Ignoring invalid CORS origin: {}", o);
+                None
+            }
+        })
+        .collect();
+    info!("CORS allowed origins: {:?}", config.allowed_origins);
+    cors.allow_origin(AllowOrigin::list(origins))
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    const SECRET: &str = "00112233445566778899aabbccddeeff";
+
+    fn authenticator() -> Authenticator {
+        let config = AuthConfig {
+            allowed_origins: Vec::new(),
+            clients: vec![ClientConfig {
+                id: "svc".to_string(),
+                api_key_sha256: None,
+                hmac_secret: Some(SECRET.to_string()),
+            }],
+            max_skew: Duration::from_secs(300),
+            nonce_capacity: 16,
+            disabled: false,
+        };
+        Authenticator::new(&config, 1024)
+    }
+
+    fn signed_headers(nonce: &str, body: &[u8], secret: &str) -> HeaderMap {
+        let timestamp = now_ms().to_string();
+        let mut mac = Hmac::<Sha256>::new_from_slice(&hex::decode(secret).unwrap()).unwrap();
+        mac.update(
+            format!("POST\n/process_kyc\n{}\n{}\n{}", timestamp, nonce, hex::encode(Sha256::digest(body))).as_bytes(),
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(CLIENT_ID_HEADER, HeaderValue::from_static("svc"));
+        headers.insert(TIMESTAMP_HEADER, HeaderValue::from_str(&timestamp).unwrap());
+        headers.insert(NONCE_HEADER, HeaderValue::from_str(nonce).unwrap());
+        headers.insert(
+            SIGNATURE_HEADER,
+            HeaderValue::from_str(&hex::encode(mac.finalize().into_bytes())).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn signed_request_is_accepted_once() {
+        let auth = authenticator();
+        let headers = signed_headers("nonce-0000000001", b"{}", SECRET);
+        let check = || auth.check_signature(&Method::POST, "/process_kyc", &headers, b"{}");
+        assert_eq!(check().unwrap().id, "svc");
+        assert!(check().unwrap_err().contains("already been used"));
+    }
+
+    #[test]
+    fn forged_request_does_not_burn_the_nonce() {
+        let auth = authenticator();
+        let forged = signed_headers("nonce-0000000002", b"{}", "ffeeddccbbaa99887766554433221100");
+        assert_eq!(
+            auth.check_signature(&Method::POST, "/process_kyc", &forged, b"{}").unwrap_err(),
+            "signature mismatch"
+        );
+        let genuine = signed_headers("nonce-0000000002", b"{}", SECRET);
+        assert!(auth.check_signature(&Method::POST, "/process_kyc", &genuine, b"{}").is_ok());
+    }
+
+    #[test]
+    fn nonce_is_required_and_covered_by_the_mac() {
+        let auth = authenticator();
+        let mut headers = signed_headers("nonce-0000000003", b"{}", SECRET);
+        headers.insert(NONCE_HEADER, HeaderValue::from_static("nonce-0000000004"));
+        assert!(auth.check_signature(&Method::POST, "/process_kyc", &headers, b"{}").is_err());
+        headers.remove(NONCE_HEADER);
+        assert!(auth
+            .check_signature(&Method::POST, "/process_kyc", &headers, b"{}")
+            .unwrap_err()
+            .contains(NONCE_HEADER));
+    }
+
+    #[tokio::test]
+    async fn signed_bodies_are_bounded_by_the_route_limit() {
+        let auth = Arc::new(authenticator());
+        let body = vec![b'a'; 2048];
+        let headers = signed_headers("nonce-0000000005", &body, SECRET);
+        let mut request = Request::post("/process_kyc").body(Body::from(body)).unwrap();
+        *request.headers_mut() = headers;
+        let app = axum::Router::new().route(
+            "/process_kyc",
+            with_auth(axum::routing::post(|| async { "ok" }), AuthRequirement::Client, &auth),
+        );
+        let response = tower::ServiceExt::oneshot(app, request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+}
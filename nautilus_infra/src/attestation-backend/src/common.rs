@@ -1,5 +1,8 @@
 use crate::AppState;
 use crate::EnclaveError;
+use crate::health::CheckResult;
+use crate::metrics::metrics;
+use crate::supervisor::SubsystemStatus;
 use axum::{extract::State, Json};
 use fastcrypto::traits::Signer;
 use fastcrypto::{encoding::Encoding, traits::ToFromBytes};
@@ -8,20 +11,18 @@ use fastcrypto::{encoding::Hex, traits::KeyPair as FcKeyPair};
 use aws_nitro_enclaves_nsm_api::api::{Request as NsmRequest, Response as NsmResponse};
 #[cfg(feature = "aws")]
 use aws_nitro_enclaves_nsm_api::driver;
-use reqwest::Client;
 use serde::{Deserialize, Serialize};
 #[cfg(feature = "aws")]
 use serde_bytes::ByteBuf;
 use serde_repr::Deserialize_repr;
 use serde_repr::Serialize_repr;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 use std::sync::Arc;
-use std::time::Duration;
 use tracing::info;
 
 use fastcrypto::ed25519::Ed25519KeyPair;
-/// ==== COMMON TYPES ====
+// ==== COMMON TYPES ====
 
 /// Intent message wrapper struct containing the intent scope and timestamp.
 /// This standardizes the serialized payload for signing.
@@ -39,6 +40,20 @@ pub struct IntentMessage<T: Serialize> {
 pub enum IntentScope {
     Generic = 0,
     KYCVerification = 1, 
+    /// Owner, DID type and evidence commitment of a `DIDSoulBoundNFT`
+    /// (`did_nft::DidIssuance`, Move `did_registry::DIDIssuance`)
+    DIDIssuance = 2,
+}
+
+impl IntentScope {
+    /// Stable label used for metrics and logs.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IntentScope::Generic => "generic",
+            IntentScope::KYCVerification => "kyc_verification",
+            IntentScope::DIDIssuance => "did_issuance",
+        }
+    }
 }
 
 impl<T: Serialize + Debug> IntentMessage<T> {
@@ -71,6 +86,7 @@ pub fn to_signed_response<T: Serialize + Clone>(
     timestamp_ms: u64,
     intent: IntentScope,
 ) -> ProcessedDataResponse<IntentMessage<T>> {
+    metrics().record_signing(intent.as_str());
     let intent_msg = IntentMessage {
         intent,
         timestamp_ms,
@@ -85,7 +101,7 @@ pub fn to_signed_response<T: Serialize + Clone>(
     }
 }
 
-/// ==== HEALTHCHECK, GET ATTESTASTION ENDPOINT IMPL ====
+// ==== HEALTHCHECK, GET ATTESTASTION ENDPOINT IMPL ====
 
 /// Response for get attestation.
 #[derive(Debug, Serialize, Deserialize)]
@@ -122,8 +138,8 @@ pub async fn get_attestation(
         }
         _ => {
             driver::nsm_exit(fd);
-            Err(EnclaveError::GenericError(
-                "unexpected response".to_string(),
+            Err(EnclaveError::AttestationUnavailable(
+                "unexpected response from NSM".to_string(),
             ))
         }
     }
@@ -149,90 +165,31 @@ pub struct HealthCheckResponse {
     pub pk: String,
     /// Status of endpoint connectivity checks
     pub endpoints_status: HashMap<String, bool>,
+    /// Cached dependency probes (Redis, Sui proxy, gas)
+    pub dependencies: BTreeMap<String, CheckResult>,
+    /// State of each supervised subsystem (API server, Redis processor)
+    pub subsystems: BTreeMap<String, SubsystemStatus>,
+    /// When the background prober last completed a round
+    pub last_probe_ms: Option<u64>,
 }
 
-/// Endpoint that health checks the enclave connectivity to all
-/// domains and returns the enclave's public key.
+/// Endpoint that reports the enclave's public key together with the last
+/// connectivity results from the background health prober.
 pub async fn health_check(
     State(state): State<Arc<AppState>>,
 ) -> Result<Json<HealthCheckResponse>, EnclaveError> {
     let pk = state.eph_kp.public();
-
-    // Create HTTP client with timeout
-    let client = Client::builder()
-        .timeout(Duration::from_secs(5))
-        .build()
-        .map_err(|e| EnclaveError::GenericError(format!("Failed to create HTTP client: {}", e)))?;
-
-    // Load allowed endpoints from YAML file
-    let endpoints_status = match std::fs::read_to_string("allowed_endpoints.yaml") {
-        Ok(yaml_content) => {
-            match serde_yaml::from_str::<serde_yaml::Value>(&yaml_content) {
-                Ok(yaml_value) => {
-                    let mut status_map = HashMap::new();
-
-                    if let Some(endpoints) =
-                        yaml_value.get("endpoints").and_then(|e| e.as_sequence())
-                    {
-                        for endpoint in endpoints {
-                            if let Some(endpoint_str) = endpoint.as_str() {
-                                // Check connectivity to each endpoint
-                                let url = if endpoint_str.contains(".amazonaws.com") {
-                                    format!("https://{}/ping", endpoint_str)
-                                } else {
-                                    format!("https://{}", endpoint_str)
-                                };
-
-                                let is_reachable = match client.get(&url).send().await {
-                                    Ok(response) => {
-                                        if endpoint_str.contains(".amazonaws.com") {
-                                            // For AWS endpoints, check if response body contains "healthy"
-                                            match response.text().await {
-                                                Ok(body) => body.to_lowercase().contains("healthy"),
-                                                Err(e) => {
-                                                    info!(
-                                                        "Failed to read response body from {}: {}",
-                                                        endpoint_str, e
-                                                    );
-                                                    false
-                                                }
-                                            }
-                                        } else {
-                                            // For non-AWS endpoints, check for 200 status
-                                            response.status().is_success()
-                                        }
-                                    }
-                                    Err(e) => {
-                                        info!("Failed to connect to {}: {}", endpoint_str, e);
-                                        false
-                                    }
-                                };
-
-                                status_map.insert(endpoint_str.to_string(), is_reachable);
-                                info!(
-                                    "Checked endpoint {}: reachable = {}",
-                                    endpoint_str, is_reachable
-                                );
-                            }
-                        }
-                    }
-
-                    status_map
-                }
-                Err(e) => {
-                    info!("Failed to parse YAML: {}", e);
-                    HashMap::new()
-                }
-            }
-        }
-        Err(e) => {
-            info!("Failed to read allowed_endpoints.yaml: {}", e);
-            HashMap::new()
-        }
-    };
+    let snapshot = state.health.snapshot();
 
     Ok(Json(HealthCheckResponse {
         pk: Hex::encode(pk.as_bytes()),
-        endpoints_status,
+        endpoints_status: snapshot
+            .endpoints
+            .iter()
+            .map(|(host, result)| (host.clone(), result.ok))
+            .collect(),
+        dependencies: snapshot.dependencies,
+        subsystems: state.subsystems.snapshot(),
+        last_probe_ms: snapshot.last_probe_ms,
     }))
 }
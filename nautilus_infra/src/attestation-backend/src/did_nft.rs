@@ -0,0 +1,420 @@
+// did_nft.rs
+use crate::common::{IntentMessage, IntentScope, ProcessDataRequest};
+use crate::evidence::{DID_AGE_VERIFY, DID_CITIZENSHIP_VERIFY};
+use crate::sui_rpc::{parse_bytes, parse_u64, sui_rpc_url, JsonRpcClient, SuiObject, SuiRpc};
+use crate::wallet::normalize_sui_address;
+use crate::AppState;
+use crate::EnclaveError;
+use axum::{extract::State, Json};
+use fastcrypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
+use fastcrypto::traits::{ToFromBytes, VerifyingKey};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{info, warn};
+
+/// What the enclave signs when a DID is issued, mirrored by the Move struct
+/// `did_registry::DIDIssuance`. Owner and DID type are signed alongside the
+/// evidence commitment so that neither can be changed on-chain; the document
+/// class and nullifier are leaves of the commitment, disclosed through
+/// `/open_evidence`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DidIssuance {
+    /// Sui address of the DID owner, serialized like a Move `address`
+    pub owner: [u8; 32],
+    pub did_type: u8,
+    pub evidence_hash: Vec<u8>,
+}
+
+impl DidIssuance {
+    pub fn new(owner: &str, did_type: u8, evidence_hash: &[u8]) -> Result<Self, EnclaveError> {
+        let owner = normalize_sui_address(owner)?;
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(&owner[2..], &mut bytes)
+            .map_err(|e| EnclaveError::InvalidInput(format!("Invalid Sui address {}: {}", owner, e)))?;
+        Ok(Self { owner: bytes, did_type, evidence_hash: evidence_hash.to_vec() })
+    }
+}
+
+/// BCS bytes of `IntentMessage<DidIssuance>` under `IntentScope::DIDIssuance`
+/// (2), which `did_registry::verify_nft_signature` checks on-chain.
+pub fn signing_payload(signature_timestamp_ms: u64, issuance: &DidIssuance) -> Vec<u8> {
+    let message = IntentMessage::new(issuance.clone(), signature_timestamp_ms, IntentScope::DIDIssuance);
+    bcs::to_bytes(&message).expect("should not fail")
+}
+
+/// Optional expectations on top of the signature and expiry checks.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct VerifyOptions {
+    /// Wallet the NFT must belong to
+    #[serde(default)]
+    pub owner: Option<String>,
+    /// DID type the NFT must carry (1 = age, 2 = citizenship)
+    #[serde(default)]
+    pub did_type: Option<u8>,
+}
+
+/// Outcome for one NFT, mirroring the SDK's `VerificationResult`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DidNftVerification {
+    pub nft_id: String,
+    pub is_valid: bool,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<DidNftData>,
+}
+
+/// Fields of a `DIDSoulBoundNFT` that the verification covered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DidNftData {
+    pub owner: String,
+    pub did_type: u8,
+    pub expiry_epoch: u64,
+    pub signature_timestamp_ms: u64,
+    /// Hex evidence commitment, openable through `/open_evidence`
+    pub evidence_hash: String,
+}
+
+impl DidNftVerification {
+    fn invalid(nft_id: &str, message: String) -> Self {
+        Self { nft_id: nft_id.to_string(), is_valid: false, message, data: None }
+    }
+}
+
+/// Chain state shared by every NFT in a batch.
+struct ChainContext {
+    epoch: u64,
+    enclave_pk: Ed25519PublicKey,
+}
+
+/// Checks `DIDSoulBoundNFT`s against the chain and the registered enclave
+/// key, like the SDK's `verifyDIDNFT` but with the signature actually checked.
+pub struct DidNftVerifier {
+    rpc: Box<dyn SuiRpc>,
+    package_id: String,
+    enclave_id: Option<String>,
+    pub max_batch: usize,
+}
+
+impl DidNftVerifier {
+    pub fn new(rpc: Box<dyn SuiRpc>, package_id: String, enclave_id: Option<String>, max_batch: usize) -> Self {
+        Self { rpc, package_id, enclave_id, max_batch }
+    }
+
+    /// Load from `SUI_RPC_URL` (default testnet full node), `SUI_PACKAGE_ID`,
+    /// `SUI_ENCLAVE_ID` (the registered `Enclave` object) and
+    /// `DID_VERIFY_MAX_BATCH` (default 50).
+    pub fn from_env() -> Self {
+        let package_id = std::env::var("SUI_PACKAGE_ID")
+            .unwrap_or_else(|_| "0x6ec40d30e636afb906e621748ee60a9b72bc59a39325adda43deadd28dc89e09".to_string());
+        let enclave_id = std::env::var("SUI_ENCLAVE_ID").ok().filter(|id| !id.is_empty());
+        if enclave_id.is_none() {
+            warn!("SUI_ENCLAVE_ID not set; DID NFT verification is unavailable");
+        }
+        let max_batch = std::env::var("DID_VERIFY_MAX_BATCH")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(50);
+        Self::new(Box::new(JsonRpcClient::new(sui_rpc_url())), package_id, enclave_id, max_batch)
+    }
+
+    fn nft_type(&self) -> String {
+        format!("{}::did_registry::DIDSoulBoundNFT", self.package_id)
+    }
+
+    /// IDs of the DID NFTs held by `owner`.
+    pub async fn nfts_by_owner(&self, owner: &str) -> Result<Vec<String>, EnclaveError> {
+        let owner = normalize_sui_address(owner)?;
+        self.rpc.get_owned_objects(&owner, &self.nft_type()).await
+    }
+
+    /// Verify one NFT.
+    pub async fn verify(&self, nft_id: &str, options: &VerifyOptions) -> Result<DidNftVerification, EnclaveError> {
+        let context = self.context().await?;
+        self.verify_with(&context, nft_id, options).await
+    }
+
+    /// Verify several NFTs against one epoch and enclave key. RPC failures for
+    /// a single NFT are reported in its entry rather than failing the batch.
+    pub async fn batch_verify(
+        &self,
+        nft_ids: &[String],
+        options: &VerifyOptions,
+    ) -> Result<Vec<DidNftVerification>, EnclaveError> {
+        if nft_ids.len() > self.max_batch {
+            return Err(EnclaveError::InvalidInput(format!(
+                "At most {} NFTs can be verified per request",
+                self.max_batch
+            )));
+        }
+        let context = self.context().await?;
+        let results = join_all(nft_ids.iter().map(|id| self.verify_with(&context, id, options))).await;
+        Ok(nft_ids
+            .iter()
+            .zip(results)
+            .map(|(id, result)| result.unwrap_or_else(|e| DidNftVerification::invalid(id, e.to_string())))
+            .collect())
+    }
+
+    async fn context(&self) -> Result<ChainContext, EnclaveError> {
+        let (epoch, enclave_pk) = futures::try_join!(self.rpc.current_epoch(), self.enclave_pk())?;
+        Ok(ChainContext { epoch, enclave_pk })
+    }
+
+    /// Public key of the registered `Enclave` object.
+    async fn enclave_pk(&self) -> Result<Ed25519PublicKey, EnclaveError> {
+        let enclave_id = self
+            .enclave_id
+            .as_deref()
+            .ok_or_else(|| EnclaveError::InternalError("SUI_ENCLAVE_ID not set".to_string()))?;
+        let enclave = self
+            .rpc
+            .get_object(enclave_id)
+            .await?
+            .ok_or_else(|| EnclaveError::UpstreamUnavailable(format!("Enclave {} not found", enclave_id)))?;
+        if !enclave.type_.contains("::enclave::Enclave<") {
+            return Err(EnclaveError::InternalError(format!(
+                "{} is a {}, not an Enclave",
+                enclave_id, enclave.type_
+            )));
+        }
+        parse_bytes(&enclave.fields["pk"])
+            .and_then(|pk| Ed25519PublicKey::from_bytes(&pk).ok())
+            .ok_or_else(|| EnclaveError::InternalError(format!("Enclave {} has no valid Ed25519 key", enclave_id)))
+    }
+
+    async fn verify_with(
+        &self,
+        context: &ChainContext,
+        nft_id: &str,
+        options: &VerifyOptions,
+    ) -> Result<DidNftVerification, EnclaveError> {
+        let nft_id = normalize_sui_address(nft_id)?;
+        let Some(object) = self.rpc.get_object(&nft_id).await? else {
+            return Ok(DidNftVerification::invalid(&nft_id, "NFT not found".to_string()));
+        };
+        let outcome = self.check(context, &object, options);
+        match &outcome {
+            Ok(data) => info!("DID NFT {} of {} verified", nft_id, data.owner),
+            Err(reason) => info!("DID NFT {} rejected: {}", nft_id, reason),
+        }
+        Ok(match outcome {
+            Ok(data) => DidNftVerification {
+                nft_id,
+                is_valid: true,
+                message: "NFT verification successful".to_string(),
+                data: Some(data),
+            },
+            Err(reason) => DidNftVerification::invalid(&nft_id, reason),
+        })
+    }
+
+    /// All checks on a fetched object; the error is the reason it is invalid.
+    fn check(&self, context: &ChainContext, object: &SuiObject, options: &VerifyOptions) -> Result<DidNftData, String> {
+        if object.type_ != self.nft_type() {
+            return Err(format!("Object is a {}, not a DID NFT", object.type_));
+        }
+        let fields = &object.fields;
+        let missing = |name: &str| format!("NFT field {} is missing or malformed", name);
+
+        // Soulbound: the recorded owner must still hold the object
+        let owner = fields["owner"]
+            .as_str()
+            .and_then(|o| normalize_sui_address(o).ok())
+            .ok_or_else(|| missing("owner"))?;
+        let holder = object.owner.as_deref().and_then(|o| normalize_sui_address(o).ok());
+        if holder.as_deref() != Some(owner.as_str()) {
+            return Err(format!("NFT is held by {}, not its owner {}", holder.unwrap_or_default(), owner));
+        }
+        if let Some(expected) = &options.owner {
+            let expected = normalize_sui_address(expected).map_err(|e| e.message().to_string())?;
+            if expected != owner {
+                return Err(format!("NFT belongs to {}, not {}", owner, expected));
+            }
+        }
+
+        let did_type = parse_u64(&fields["did_type"])
+            .and_then(|t| u8::try_from(t).ok())
+            .ok_or_else(|| missing("did_type"))?;
+        if did_type != DID_AGE_VERIFY && did_type != DID_CITIZENSHIP_VERIFY {
+            return Err(format!("Unknown DID type {}", did_type));
+        }
+        if options.did_type.is_some_and(|expected| expected != did_type) {
+            return Err(format!("NFT has DID type {}, not {}", did_type, options.did_type.unwrap_or_default()));
+        }
+
+        // Same rule as `did_registry::is_expired`
+        let expiry_epoch = parse_u64(&fields["expiry_epoch"]).ok_or_else(|| missing("expiry_epoch"))?;
+        if context.epoch >= expiry_epoch {
+            return Err(format!("NFT expired at epoch {} (current epoch {})", expiry_epoch, context.epoch));
+        }
+
+        let signature_timestamp_ms =
+            parse_u64(&fields["signature_timestamp_ms"]).ok_or_else(|| missing("signature_timestamp_ms"))?;
+        let evidence_hash = parse_bytes(&fields["evidence_hash"]).ok_or_else(|| missing("evidence_hash"))?;
+        let signature = parse_bytes(&fields["nautilus_signature"])
+            .and_then(|s| Ed25519Signature::from_bytes(&s).ok())
+            .ok_or_else(|| missing("nautilus_signature"))?;
+        let issuance = DidIssuance::new(&owner, did_type, &evidence_hash).map_err(|e| e.message().to_string())?;
+        context
+            .enclave_pk
+            .verify(&signing_payload(signature_timestamp_ms, &issuance), &signature)
+            .map_err(|_| "Nautilus signature does not match the registered enclave key".to_string())?;
+
+        Ok(DidNftData {
+            owner,
+            did_type,
+            expiry_epoch,
+            signature_timestamp_ms,
+            evidence_hash: hex::encode(evidence_hash),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyDidNftsRequest {
+    /// NFTs to verify; when empty, the DID NFTs held by `options.owner`, at
+    /// most `max_batch` per request starting at `offset`
+    #[serde(default)]
+    pub nft_ids: Vec<String>,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(flatten)]
+    pub options: VerifyOptions,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyDidNftsResponse {
+    pub results: Vec<DidNftVerification>,
+    /// Offset of the owner's next page of NFTs, if there are more
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+}
+
+/// Batch form of the SDK's `verifyDIDNFT` for services that cannot run the
+/// TypeScript SDK.
+pub async fn verify_did_nfts(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<ProcessDataRequest<VerifyDidNftsRequest>>,
+) -> Result<Json<VerifyDidNftsResponse>, EnclaveError> {
+    let VerifyDidNftsRequest { mut nft_ids, offset, options } = request.payload;
+    let mut next_offset = None;
+    if nft_ids.is_empty() {
+        let owner = options
+            .owner
+            .as_deref()
+            .ok_or_else(|| EnclaveError::InvalidInput("Either nft_ids or owner is required".to_string()))?;
+        let owned = state.did_verifier.nfts_by_owner(owner).await?;
+        (nft_ids, next_offset) = page(owned, offset, state.did_verifier.max_batch);
+    }
+    let results = state.did_verifier.batch_verify(&nft_ids, &options).await?;
+    info!(
+        "Verified {} DID NFT(s), {} valid",
+        results.len(),
+        results.iter().filter(|r| r.is_valid).count()
+    );
+    Ok(Json(VerifyDidNftsResponse { results, next_offset }))
+}
+
+/// The `limit` IDs starting at `offset`, and the offset of the next page.
+fn page(ids: Vec<String>, offset: usize, limit: usize) -> (Vec<String>, Option<usize>) {
+    let total = ids.len();
+    let page: Vec<String> = ids.into_iter().skip(offset).take(limit).collect();
+    let end = offset.saturating_add(page.len());
+    (page, (end < total).then_some(end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sui_rpc::SuiRpc;
+    use fastcrypto::ed25519::Ed25519KeyPair;
+    use fastcrypto::traits::{KeyPair, Signer};
+    use futures::future::BoxFuture;
+    use serde_json::json;
+
+    const PACKAGE: &str = "0x6ec40d30e636afb906e621748ee60a9b72bc59a39325adda43deadd28dc89e09";
+    const OWNER: &str = "0x00000000000000000000000000000000000000000000000000000000000000a1";
+
+    struct NoRpc;
+
+    impl SuiRpc for NoRpc {
+        fn get_object<'a>(&'a self, _id: &'a str) -> BoxFuture<'a, Result<Option<SuiObject>, EnclaveError>> {
+            Box::pin(async { Ok(None) })
+        }
+
+        fn get_owned_objects<'a>(
+            &'a self,
+            _owner: &'a str,
+            _struct_type: &'a str,
+        ) -> BoxFuture<'a, Result<Vec<String>, EnclaveError>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn current_epoch(&self) -> BoxFuture<'_, Result<u64, EnclaveError>> {
+            Box::pin(async { Ok(1) })
+        }
+    }
+
+    fn nft(owner: &str, did_type: u8, signature: &[u8]) -> SuiObject {
+        SuiObject {
+            object_id: "0x1".to_string(),
+            type_: format!("{}::did_registry::DIDSoulBoundNFT", PACKAGE),
+            owner: Some(owner.to_string()),
+            fields: json!({
+                "owner": owner,
+                "did_type": did_type,
+                "expiry_epoch": "10",
+                "signature_timestamp_ms": "1700000000000",
+                "evidence_hash": vec![7u8; 32],
+                "nautilus_signature": signature,
+            }),
+        }
+    }
+
+    #[test]
+    fn payload_matches_the_move_layout() {
+        let issuance = DidIssuance::new("0xa1", DID_AGE_VERIFY, &[7u8; 32]).unwrap();
+        let payload = signing_payload(1_700_000_000_000, &issuance);
+
+        let mut expected = vec![IntentScope::DIDIssuance as u8];
+        expected.extend(1_700_000_000_000u64.to_le_bytes());
+        expected.extend(hex::decode(&OWNER[2..]).unwrap());
+        expected.push(DID_AGE_VERIFY);
+        expected.push(32);
+        expected.extend([7u8; 32]);
+        assert_eq!(payload, expected);
+    }
+
+    #[test]
+    fn signature_binds_owner_and_did_type() {
+        let kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+        let issuance = DidIssuance::new(OWNER, DID_AGE_VERIFY, &[7u8; 32]).unwrap();
+        let signature = kp.sign(&signing_payload(1_700_000_000_000, &issuance));
+        let verifier = DidNftVerifier::new(Box::new(NoRpc), PACKAGE.to_string(), None, 50);
+        let context = ChainContext { epoch: 1, enclave_pk: kp.public().clone() };
+        let options = VerifyOptions::default();
+
+        let data = verifier.check(&context, &nft(OWNER, DID_AGE_VERIFY, signature.as_ref()), &options).unwrap();
+        assert_eq!(data.owner, OWNER);
+
+        let other_owner = "0x00000000000000000000000000000000000000000000000000000000000000b2";
+        for object in [
+            nft(other_owner, DID_AGE_VERIFY, signature.as_ref()),
+            nft(OWNER, DID_CITIZENSHIP_VERIFY, signature.as_ref()),
+        ] {
+            let reason = verifier.check(&context, &object, &options).unwrap_err();
+            assert!(reason.contains("does not match the registered enclave key"), "{}", reason);
+        }
+    }
+
+    #[test]
+    fn owner_lookups_are_paged() {
+        let ids: Vec<String> = (0..120).map(|i| i.to_string()).collect();
+        let (first, next) = page(ids.clone(), 0, 50);
+        assert_eq!((first.len(), first[0].as_str(), next), (50, "0", Some(50)));
+        let (last, next) = page(ids.clone(), 100, 50);
+        assert_eq!((last.len(), last[0].as_str(), next), (20, "100", None));
+        assert_eq!(page(ids, 500, 50), (Vec::new(), None));
+    }
+}
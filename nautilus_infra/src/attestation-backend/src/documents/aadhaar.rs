@@ -0,0 +1,93 @@
+// documents/aadhaar.rs
+use super::{DocumentInput, DocumentParser, DocumentRegistry, DocumentType, IdentityRecord};
+use crate::aadhaar::offline_ekyc::decode_offline_ekyc;
+use crate::aadhaar::qr::decode_secure_qr;
+use crate::aadhaar::{AadhaarRecord, UidaiTrust};
+use crate::evidence::DID_AGE_VERIFY;
+use crate::rules::DocumentClass;
+use crate::EnclaveError;
+use std::sync::Arc;
+
+/// Aadhaar proves date of birth and residency, not citizenship.
+const AADHAAR_DID_TYPES: &[u8] = &[DID_AGE_VERIFY];
+
+/// Register both Aadhaar parsers, sharing the UIDAI certificates.
+pub fn register(registry: &mut DocumentRegistry) {
+    let trust = Arc::new(UidaiTrust::from_env());
+    registry.register(Box::new(AadhaarQrParser { trust: trust.clone() }));
+    registry.register(Box::new(AadhaarXmlParser { trust }));
+}
+
+/// Aadhaar Secure QR; contact hashes are over the contact alone.
+pub struct AadhaarQrParser {
+    trust: Arc<UidaiTrust>,
+}
+
+impl DocumentParser for AadhaarQrParser {
+    fn document_type(&self) -> DocumentType {
+        DocumentType::AadhaarQr
+    }
+
+    fn supported_did_types(&self) -> &'static [u8] {
+        AADHAAR_DID_TYPES
+    }
+
+    fn parse(&self, input: &DocumentInput) -> Result<IdentityRecord, EnclaveError> {
+        let qr_text = std::str::from_utf8(input.data)
+            .map_err(|_| EnclaveError::InvalidInput("Aadhaar QR is not UTF-8".to_string()))?;
+        let record = decode_secure_qr(qr_text, &self.trust)?;
+        identity_record(DocumentType::AadhaarQr, record, input, "")
+    }
+}
+
+/// Aadhaar Offline e-KYC ZIP; the share code opens it and salts contact hashes.
+pub struct AadhaarXmlParser {
+    trust: Arc<UidaiTrust>,
+}
+
+impl DocumentParser for AadhaarXmlParser {
+    fn document_type(&self) -> DocumentType {
+        DocumentType::AadhaarXml
+    }
+
+    fn supported_did_types(&self) -> &'static [u8] {
+        AADHAAR_DID_TYPES
+    }
+
+    fn parse(&self, input: &DocumentInput) -> Result<IdentityRecord, EnclaveError> {
+        let share_code = input.secret.filter(|s| !s.is_empty()).ok_or_else(|| {
+            EnclaveError::InvalidInput("Offline e-KYC ZIP requires the share code".to_string())
+        })?;
+        let record = decode_offline_ekyc(input.data, share_code, &self.trust)?;
+        identity_record(DocumentType::AadhaarXml, record, input, share_code)
+    }
+}
+
+/// Check any contact details the user supplied, then map to the common record.
+fn identity_record(
+    document_type: DocumentType,
+    record: AadhaarRecord,
+    input: &DocumentInput,
+    share_code: &str,
+) -> Result<IdentityRecord, EnclaveError> {
+    if let Some(mobile) = input.mobile {
+        record.verify_mobile(mobile, share_code)?;
+    }
+    if let Some(email) = input.email {
+        record.verify_email(email, share_code)?;
+    }
+    Ok(IdentityRecord {
+        document_type,
+        document_class: DocumentClass::Aadhaar,
+        source_digest: record.digest()?,
+        reference_id: record.reference_id,
+        name: record.name,
+        date_of_birth: Some(record.date_of_birth),
+        gender: record.gender,
+        nationality: None,
+        document_number: None,
+        expiry_date: None,
+        issuer_signed: true,
+        photo: record.photo,
+    })
+}
@@ -0,0 +1,213 @@
+// documents/mod.rs
+use crate::age::DateOfBirth;
+use crate::rules::DocumentClass;
+use crate::EnclaveError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing::info;
+
+pub mod aadhaar;
+pub mod countries;
+pub mod mrz;
+pub mod passport_chip;
+
+/// Kind of identity document a KYC submission carries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentType {
+    /// Aadhaar Secure QR (the decimal string in the code)
+    AadhaarQr,
+    /// Aadhaar Offline e-KYC ZIP, opened with the share code
+    AadhaarXml,
+    /// Machine readable zone of a passport
+    PassportMrz,
+    /// EF.SOD, DG1 and DG2 read from an ePassport chip
+    PassportChip,
+}
+
+impl DocumentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentType::AadhaarQr => "aadhaar_qr",
+            DocumentType::AadhaarXml => "aadhaar_xml",
+            DocumentType::PassportMrz => "passport_mrz",
+            DocumentType::PassportChip => "passport_chip",
+        }
+    }
+}
+
+/// Decrypted document payload and what the user supplied alongside it.
+pub struct DocumentInput<'a> {
+    pub data: &'a [u8],
+    /// Secret that opens the payload (e.g. the Aadhaar share code)
+    pub secret: Option<&'a str>,
+    /// Mobile number to check against the document, if it records one
+    pub mobile: Option<&'a str>,
+    /// Email address to check against the document, if it records one
+    pub email: Option<&'a str>,
+    /// Enclave time of the submission, for expiry checks
+    pub timestamp_ms: u64,
+}
+
+/// Identity data common to all document types, read from a document whose
+/// authenticity the parser has checked. `Debug` leaves out personal data.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct IdentityRecord {
+    pub document_type: DocumentType,
+    pub document_class: DocumentClass,
+    /// Short issuer reference safe to log; never a full document number
+    pub reference_id: String,
+    pub name: String,
+    pub date_of_birth: Option<DateOfBirth>,
+    pub gender: String,
+    /// ICAO nationality code (ISO 3166-1 alpha-3, `D` for Germany), if stated
+    pub nationality: Option<String>,
+    /// Document number, for documents that have one printed
+    pub document_number: Option<String>,
+    /// Expiry date (`YYYY-MM-DD`) for documents that expire
+    pub expiry_date: Option<String>,
+    /// Whether an issuer signature over the record was verified
+    pub issuer_signed: bool,
+    /// Holder photo embedded by the issuer
+    #[serde(with = "serde_bytes")]
+    pub photo: Vec<u8>,
+    /// Hex digest of the parsed source record, e.g. the full Aadhaar record
+    pub source_digest: String,
+}
+
+impl std::fmt::Debug for IdentityRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdentityRecord")
+            .field("document_type", &self.document_type)
+            .field("document_class", &self.document_class)
+            .field("issuer_signed", &self.issuer_signed)
+            .field("source_digest", &self.source_digest)
+            .finish_non_exhaustive()
+    }
+}
+
+impl IdentityRecord {
+    /// Normalized identity of the underlying document, the same whichever
+    /// format it was submitted in. Input to nullifier derivation only.
+    pub fn identity_key(&self) -> Result<Option<String>, EnclaveError> {
+        match self.document_class {
+            // Aadhaar files carry only the last 4 digits of the number, so
+            // combine them with demographics the holder cannot freely change
+            DocumentClass::Aadhaar => {
+                let last4 = self.reference_id.get(..4).ok_or_else(|| {
+                    EnclaveError::InvalidInput("Malformed Aadhaar reference ID".to_string())
+                })?;
+                let date_of_birth = match &self.date_of_birth {
+                    Some(date_of_birth) => date_of_birth.canonical()?,
+                    None => String::new(),
+                };
+                Ok(Some(format!(
+                    "aadhaar:{}:{}:{}:{}",
+                    last4,
+                    date_of_birth,
+                    self.gender.trim().to_ascii_uppercase(),
+                    normalize_name(&self.name)
+                )))
+            }
+            DocumentClass::IndianPassport | DocumentClass::ForeignPassport => {
+                Ok(self.nationality.as_ref().zip(self.document_number.as_ref()).map(
+                    |(nationality, number)| format!("passport:{}:{}", nationality, number.to_ascii_uppercase()),
+                ))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+fn normalize_name(name: &str) -> String {
+    name.split_whitespace()
+        .map(str::to_uppercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Turns one document type into an `IdentityRecord`.
+pub trait DocumentParser: Send + Sync {
+    fn document_type(&self) -> DocumentType;
+    /// DID types (as in did_registry.move) this parser's records can back
+    fn supported_did_types(&self) -> &'static [u8];
+    /// Check the document's authenticity and read the identity data.
+    fn parse(&self, input: &DocumentInput) -> Result<IdentityRecord, EnclaveError>;
+}
+
+/// Parsers by document type.
+#[derive(Default)]
+pub struct DocumentRegistry {
+    parsers: HashMap<DocumentType, Box<dyn DocumentParser>>,
+}
+
+impl DocumentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register every parser whose configuration loads from the environment.
+    pub fn from_env() -> Self {
+        let mut registry = Self::new();
+        aadhaar::register(&mut registry);
+        registry.register(Box::new(mrz::PassportMrzParser));
+        passport_chip::register(&mut registry);
+        info!("Document parsers: {:?}", registry.document_types());
+        registry
+    }
+
+    /// Add a parser, replacing any earlier one for the same document type.
+    pub fn register(&mut self, parser: Box<dyn DocumentParser>) {
+        self.parsers.insert(parser.document_type(), parser);
+    }
+
+    pub fn document_types(&self) -> Vec<&'static str> {
+        let mut types: Vec<_> = self.parsers.keys().map(DocumentType::as_str).collect();
+        types.sort_unstable();
+        types
+    }
+
+    /// Parse a document for a DID type its parser declares support for.
+    pub fn parse(
+        &self,
+        document_type: DocumentType,
+        did_type: u8,
+        input: &DocumentInput,
+    ) -> Result<IdentityRecord, EnclaveError> {
+        let parser = self.parsers.get(&document_type).ok_or_else(|| {
+            EnclaveError::InvalidInput(format!(
+                "Document type {} is not supported",
+                document_type.as_str()
+            ))
+        })?;
+        if !parser.supported_did_types().contains(&did_type) {
+            return Err(EnclaveError::VerificationFailed(format!(
+                "A {} document cannot back DID type {}",
+                document_type.as_str(),
+                did_type
+            )));
+        }
+        parser.parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_document_types_with_parsers_deserialize() {
+        for document_type in [
+            DocumentType::AadhaarQr,
+            DocumentType::AadhaarXml,
+            DocumentType::PassportMrz,
+            DocumentType::PassportChip,
+        ] {
+            let json = serde_json::to_string(&document_type).unwrap();
+            assert_eq!(json, format!("\"{}\"", document_type.as_str()));
+            assert_eq!(serde_json::from_str::<DocumentType>(&json).unwrap(), document_type);
+        }
+        assert!(serde_json::from_str::<DocumentType>("\"pan\"").is_err());
+        assert!(serde_json::from_str::<DocumentType>("\"driving_licence\"").is_err());
+    }
+}
@@ -0,0 +1,354 @@
+// documents/mrz.rs
+use super::{DocumentInput, DocumentParser, DocumentType, IdentityRecord};
+use crate::age::DateOfBirth;
+use crate::rules::DocumentClass;
+use crate::EnclaveError;
+use chrono::{DateTime, Datelike, NaiveDate};
+use sha2::{Digest, Sha256};
+
+/// Nationality code of Indian passports.
+const INDIA: &str = "IND";
+
+/// ICAO 9303 machine readable zone layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MrzFormat {
+    /// 3 lines of 30 characters (ID cards)
+    Td1,
+    /// 2 lines of 36 characters
+    Td2,
+    /// 2 lines of 44 characters (passport books)
+    Td3,
+}
+
+/// Fields of a machine readable zone whose check digits all validated.
+#[derive(Debug, Clone)]
+pub struct Mrz {
+    pub format: MrzFormat,
+    /// `P` followed by an optional subtype for passports
+    pub document_code: String,
+    pub issuing_state: String,
+    pub surname: String,
+    pub given_names: String,
+    pub document_number: String,
+    pub nationality: String,
+    /// `YYMMDD`; unknown parts are filled with `<`
+    pub birth_date: String,
+    /// `M`, `F` or `X`
+    pub sex: String,
+    /// `YYMMDD`
+    pub expiry_date: String,
+    /// The normalized zone, one line per row
+    pub text: String,
+}
+
+impl Mrz {
+    pub fn is_passport(&self) -> bool {
+        self.document_code.starts_with('P')
+    }
+
+    /// Full date of birth, the century chosen so the holder is not born in the
+    /// future. Unknown day or month leaves a bare year.
+    pub fn date_of_birth(&self, today: NaiveDate) -> Result<String, EnclaveError> {
+        let year = two_digit_year(&self.birth_date[0..2], "date of birth")?;
+        let year = if 2000 + year <= today.year() { 2000 + year } else { 1900 + year };
+        let (month, day) = (&self.birth_date[2..4], &self.birth_date[4..6]);
+        if month.contains('<') || day.contains('<') {
+            return Ok(year.to_string());
+        }
+        let date = date(year, month, day, "date of birth")?;
+        Ok(date.format("%Y-%m-%d").to_string())
+    }
+
+    /// Expiry date; no machine readable passport expired before 2000.
+    pub fn expiry(&self) -> Result<NaiveDate, EnclaveError> {
+        let year = 2000 + two_digit_year(&self.expiry_date[0..2], "expiry date")?;
+        date(year, &self.expiry_date[2..4], &self.expiry_date[4..6], "expiry date")
+    }
+}
+
+/// Parse a TD1, TD2 or TD3 zone from decoded text (OCR output or typed by the
+/// client) and validate every check digit, including the composite one.
+pub fn parse_mrz(text: &str) -> Result<Mrz, EnclaveError> {
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_ascii_uppercase())
+        .filter(|line| !line.is_empty())
+        .collect();
+    if let Some(bad) = lines.iter().flat_map(|l| l.chars()).find(|c| !(c.is_ascii_alphanumeric() || *c == '<')) {
+        return Err(invalid(&format!("unexpected character {:?}", bad)));
+    }
+
+    let lengths: Vec<usize> = lines.iter().map(String::len).collect();
+    let mrz = match lengths.as_slice() {
+        [30, 30, 30] => parse_td1(&lines)?,
+        [36, 36] => parse_td2_td3(&lines, MrzFormat::Td2)?,
+        [44, 44] => parse_td2_td3(&lines, MrzFormat::Td3)?,
+        _ => return Err(invalid(&format!("unrecognized layout {:?}", lengths))),
+    };
+    Ok(mrz)
+}
+
+/// TD1: number and optional data on line 1, dates and nationality on line 2,
+/// name on line 3.
+fn parse_td1(lines: &[String]) -> Result<Mrz, EnclaveError> {
+    let (l1, l2, l3) = (lines[0].as_str(), lines[1].as_str(), lines[2].as_str());
+    check_field(&l1[5..14], &l1[14..15], "document number")?;
+    check_field(&l2[0..6], &l2[6..7], "date of birth")?;
+    check_field(&l2[8..14], &l2[14..15], "expiry date")?;
+    let composite = [&l1[5..30], &l2[0..7], &l2[8..15], &l2[18..29]].concat();
+    check_field(&composite, &l2[29..30], "composite")?;
+
+    let (surname, given_names) = split_name(l3);
+    Ok(Mrz {
+        format: MrzFormat::Td1,
+        document_code: filler(&l1[0..2]),
+        issuing_state: filler(&l1[2..5]),
+        surname,
+        given_names,
+        document_number: filler(&l1[5..14]),
+        nationality: filler(&l2[15..18]),
+        birth_date: l2[0..6].to_string(),
+        sex: sex(&l2[7..8]),
+        expiry_date: l2[8..14].to_string(),
+        text: lines.join("\n"),
+    })
+}
+
+/// TD2 and TD3 share a layout and differ only in the name and optional data widths.
+fn parse_td2_td3(lines: &[String], format: MrzFormat) -> Result<Mrz, EnclaveError> {
+    let (l1, l2) = (lines[0].as_str(), lines[1].as_str());
+    let last = l2.len() - 1;
+    check_field(&l2[0..9], &l2[9..10], "document number")?;
+    check_field(&l2[13..19], &l2[19..20], "date of birth")?;
+    check_field(&l2[21..27], &l2[27..28], "expiry date")?;
+    if format == MrzFormat::Td3 {
+        // Personal number; its check digit may be filler when it is empty
+        check_field(&l2[28..42], &l2[42..43], "personal number")?;
+    }
+    let composite = [&l2[0..10], &l2[13..20], &l2[21..last]].concat();
+    check_field(&composite, &l2[last..], "composite")?;
+
+    let (surname, given_names) = split_name(&l1[5..]);
+    Ok(Mrz {
+        format,
+        document_code: filler(&l1[0..2]),
+        issuing_state: filler(&l1[2..5]),
+        surname,
+        given_names,
+        document_number: filler(&l2[0..9]),
+        nationality: filler(&l2[10..13]),
+        birth_date: l2[13..19].to_string(),
+        sex: sex(&l2[20..21]),
+        expiry_date: l2[21..27].to_string(),
+        text: lines.join("\n"),
+    })
+}
+
+/// ICAO 9303 check digit: weights 7, 3, 1 over digits, letters (A = 10) and
+/// filler (`<` = 0), modulo 10.
+pub fn check_digit(field: &str) -> u32 {
+    field
+        .chars()
+        .zip([7, 3, 1].iter().cycle())
+        .map(|(c, weight)| {
+            let value = match c {
+                '0'..='9' => c as u32 - '0' as u32,
+                'A'..='Z' => c as u32 - 'A' as u32 + 10,
+                _ => 0,
+            };
+            value * weight
+        })
+        .sum::<u32>()
+        % 10
+}
+
+fn check_field(field: &str, check: &str, name: &str) -> Result<(), EnclaveError> {
+    let expected = match check {
+        // Filler is only a valid check digit over an empty field
+        "<" if field.chars().all(|c| c == '<') => return Ok(()),
+        "<" if name == "document number" => {
+            return Err(invalid("document numbers longer than 9 characters are not supported"))
+        }
+        _ => check
+            .parse::<u32>()
+            .map_err(|_| invalid(&format!("{} check digit is not a digit", name)))?,
+    };
+    if check_digit(field) == expected {
+        Ok(())
+    } else {
+        Err(EnclaveError::VerificationFailed(format!(
+            "MRZ {} check digit does not match",
+            name
+        )))
+    }
+}
+
+fn split_name(field: &str) -> (String, String) {
+    let field = field.trim_end_matches('<');
+    let (surname, given) = field.split_once("<<").unwrap_or((field, ""));
+    (filler(surname), filler(given))
+}
+
+/// Replace filler with spaces and trim.
+fn filler(field: &str) -> String {
+    field
+        .split('<')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn sex(field: &str) -> String {
+    match field {
+        "M" | "F" => field.to_string(),
+        _ => "X".to_string(),
+    }
+}
+
+fn two_digit_year(field: &str, name: &str) -> Result<i32, EnclaveError> {
+    field
+        .parse::<i32>()
+        .map_err(|_| invalid(&format!("{} has no year", name)))
+}
+
+fn date(year: i32, month: &str, day: &str, name: &str) -> Result<NaiveDate, EnclaveError> {
+    month
+        .parse::<u32>()
+        .ok()
+        .zip(day.parse::<u32>().ok())
+        .and_then(|(month, day)| NaiveDate::from_ymd_opt(year, month, day))
+        .ok_or_else(|| invalid(&format!("{} is not a date", name)))
+}
+
+fn invalid(reason: &str) -> EnclaveError {
+    EnclaveError::InvalidInput(format!("MRZ: {}", reason))
+}
+
+/// Passport MRZ supplied as text. Check digits catch OCR and typing errors but
+/// do not prove the passport is genuine: anyone can type a valid zone, so the
+/// record is `Unverified` and backs no DID type. Passports prove age or
+/// citizenship only through the chip, whose DG1 carries the same zone.
+pub struct PassportMrzParser;
+
+impl DocumentParser for PassportMrzParser {
+    fn document_type(&self) -> DocumentType {
+        DocumentType::PassportMrz
+    }
+
+    fn supported_did_types(&self) -> &'static [u8] {
+        &[]
+    }
+
+    fn parse(&self, input: &DocumentInput) -> Result<IdentityRecord, EnclaveError> {
+        let text = std::str::from_utf8(input.data)
+            .map_err(|_| invalid("text is not UTF-8"))?;
+        let mrz = parse_mrz(text)?;
+        if !mrz.is_passport() {
+            return Err(invalid(&format!("document code {} is not a passport", mrz.document_code)));
+        }
+        let record = passport_record(&mrz, input.timestamp_ms)?;
+        Ok(IdentityRecord { document_class: DocumentClass::Unverified, ..record })
+    }
+}
+
+/// Map a validated passport MRZ to the common record, rejecting expired passports.
+/// The class follows the MRZ and is only meaningful once the caller has
+/// authenticated the zone, as the chip parser does.
+pub fn passport_record(mrz: &Mrz, timestamp_ms: u64) -> Result<IdentityRecord, EnclaveError> {
+    let today = DateTime::from_timestamp_millis(timestamp_ms as i64)
+        .ok_or_else(|| EnclaveError::InternalError("Invalid enclave timestamp".to_string()))?
+        .date_naive();
+    if mrz.expiry()? < today {
+        return Err(EnclaveError::VerificationFailed("Passport has expired".to_string()));
+    }
+
+    let document_class = if mrz.nationality == INDIA && mrz.issuing_state == INDIA {
+        DocumentClass::IndianPassport
+    } else {
+        DocumentClass::ForeignPassport
+    };
+    let number = &mrz.document_number;
+    Ok(IdentityRecord {
+        document_type: DocumentType::PassportMrz,
+        document_class,
+        reference_id: format!("{}-{}", mrz.issuing_state, &number[number.len().saturating_sub(4)..]),
+        name: format!("{} {}", mrz.given_names, mrz.surname).trim().to_string(),
+        date_of_birth: Some(DateOfBirth::new(mrz.date_of_birth(today)?)),
+        gender: mrz.sex.clone(),
+        nationality: Some(mrz.nationality.clone()),
+        document_number: Some(mrz.document_number.clone()),
+        expiry_date: Some(mrz.expiry()?.format("%Y-%m-%d").to_string()),
+        issuer_signed: false,
+        photo: Vec::new(),
+        source_digest: hex::encode(Sha256::digest(mrz.text.as_bytes())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2026-01-01T00:00:00Z
+    const JAN_1_2026_MS: u64 = 1_767_225_600_000;
+
+    fn fixture(name: &str) -> String {
+        let path = format!("{}/fixtures/passport/{}", env!("CARGO_MANIFEST_DIR"), name);
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    fn input(data: &[u8]) -> DocumentInput<'_> {
+        DocumentInput { data, secret: None, mobile: None, email: None, timestamp_ms: JAN_1_2026_MS }
+    }
+
+    #[test]
+    fn check_digit_matches_icao_examples() {
+        assert_eq!(check_digit("L898902C3"), 6);
+        assert_eq!(check_digit("740812"), 2);
+        assert_eq!(check_digit("120415"), 9);
+        assert_eq!(check_digit("<<<<<<"), 0);
+    }
+
+    #[test]
+    fn parses_every_layout() {
+        let td3 = parse_mrz(&fixture("td3_indian.txt")).unwrap();
+        assert_eq!(td3.format, MrzFormat::Td3);
+        assert_eq!((td3.issuing_state.as_str(), td3.nationality.as_str()), (INDIA, INDIA));
+        assert_eq!(td3.document_number, "Z1234567");
+        assert_eq!((td3.surname.as_str(), td3.given_names.as_str()), ("SHARMA", "PRIYA"));
+
+        let td2 = parse_mrz(&fixture("td2_passport.txt")).unwrap();
+        assert_eq!(td2.format, MrzFormat::Td2);
+        assert!(td2.is_passport());
+
+        let td1 = parse_mrz(&fixture("td1_id_card.txt")).unwrap();
+        assert_eq!(td1.format, MrzFormat::Td1);
+        assert!(!td1.is_passport());
+    }
+
+    #[test]
+    fn rejects_altered_field() {
+        match parse_mrz(&fixture("td3_bad_check.txt")) {
+            Err(EnclaveError::VerificationFailed(message)) => assert!(message.contains("document number")),
+            other => panic!("expected a check digit mismatch, got {:?}", other),
+        }
+        let altered = fixture("td3_indian.txt").replace("9001158F", "9001168F");
+        assert!(matches!(parse_mrz(&altered), Err(EnclaveError::VerificationFailed(_))));
+    }
+
+    #[test]
+    fn rejects_expired_passport() {
+        let mrz = parse_mrz(&fixture("td3_expired.txt")).unwrap();
+        assert!(matches!(passport_record(&mrz, JAN_1_2026_MS), Err(EnclaveError::VerificationFailed(_))));
+    }
+
+    #[test]
+    fn typed_mrz_proves_nothing() {
+        let text = fixture("td3_indian.txt");
+        let record = PassportMrzParser.parse(&input(text.as_bytes())).unwrap();
+        assert_eq!(record.document_class, DocumentClass::Unverified);
+        assert!(!record.issuer_signed);
+        assert_eq!(record.expiry_date.as_deref(), Some("2032-06-01"));
+        assert!(PassportMrzParser.supported_did_types().is_empty());
+        assert!(PassportMrzParser.parse(&input(fixture("td1_id_card.txt").as_bytes())).is_err());
+    }
+}
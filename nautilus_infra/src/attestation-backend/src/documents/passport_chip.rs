@@ -0,0 +1,122 @@
+// documents/passport_chip.rs
+use super::countries::issuing_state_alpha2;
+use super::mrz::{parse_mrz, passport_record};
+use super::{DocumentInput, DocumentParser, DocumentRegistry, DocumentType, IdentityRecord};
+use crate::emrtd::{passive_authentication, ChipFiles, CscaTrust};
+use crate::evidence::{DID_AGE_VERIFY, DID_CITIZENSHIP_VERIFY};
+use crate::EnclaveError;
+
+pub fn register(registry: &mut DocumentRegistry) {
+    registry.register(Box::new(PassportChipParser {
+        trust: CscaTrust::from_env(),
+    }));
+}
+
+/// ePassport chip files (JSON `ChipFiles`) checked by passive authentication.
+pub struct PassportChipParser {
+    trust: CscaTrust,
+}
+
+impl DocumentParser for PassportChipParser {
+    fn document_type(&self) -> DocumentType {
+        DocumentType::PassportChip
+    }
+
+    fn supported_did_types(&self) -> &'static [u8] {
+        &[DID_AGE_VERIFY, DID_CITIZENSHIP_VERIFY]
+    }
+
+    fn parse(&self, input: &DocumentInput) -> Result<IdentityRecord, EnclaveError> {
+        let files: ChipFiles = serde_json::from_slice(input.data)
+            .map_err(|e| EnclaveError::InvalidInput(format!("Passport chip files: {}", e)))?;
+        let chip = passive_authentication(&files, &self.trust, input.timestamp_ms)?;
+
+        let mrz = parse_mrz(&chip.mrz)?;
+        if !mrz.is_passport() {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Chip document code {} is not a passport",
+                mrz.document_code
+            )));
+        }
+        let record = passport_record(&mrz, input.timestamp_ms)?;
+
+        // Every country's CSCA is trusted for its own passports only, so a
+        // master list entry cannot vouch for another state's passports
+        let issuer = issuing_state_alpha2(&mrz.issuing_state);
+        if issuer.is_none() || chip.csca_country.as_deref() != issuer {
+            return Err(EnclaveError::VerificationFailed(format!(
+                "Passport issued by {} is signed under a CSCA of {}",
+                mrz.issuing_state,
+                chip.csca_country.as_deref().unwrap_or("unknown country")
+            )));
+        }
+
+        Ok(IdentityRecord {
+            document_type: DocumentType::PassportChip,
+            issuer_signed: true,
+            photo: chip.face_image,
+            source_digest: chip.sod_digest,
+            ..record
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::DocumentClass;
+
+    // 2026-01-01T00:00:00Z
+    const JAN_1_2026_MS: u64 = 1_767_225_600_000;
+
+    fn fixture(name: &str) -> String {
+        format!("{}/fixtures/passport/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn parser() -> PassportChipParser {
+        PassportChipParser { trust: CscaTrust::from_files(&[fixture("test_master_list.ml")], &[]) }
+    }
+
+    fn parse(parser: &PassportChipParser, name: &str) -> Result<IdentityRecord, EnclaveError> {
+        let data = std::fs::read(fixture(name)).unwrap();
+        parser.parse(&DocumentInput {
+            data: &data,
+            secret: None,
+            mobile: None,
+            email: None,
+            timestamp_ms: JAN_1_2026_MS,
+        })
+    }
+
+    #[test]
+    fn accepts_chip_signed_under_the_issuing_state_csca() {
+        let record = parse(&parser(), "chip_valid.json").unwrap();
+        assert_eq!(record.document_type, DocumentType::PassportChip);
+        assert_eq!(record.document_class, DocumentClass::IndianPassport);
+        assert!(record.issuer_signed);
+        assert!(!record.photo.is_empty());
+        assert_eq!(record.document_number.as_deref(), Some("Z1234567"));
+    }
+
+    #[test]
+    fn rejects_data_group_that_does_not_match_the_sod() {
+        match parse(&parser(), "chip_tampered_dg1.json") {
+            Err(EnclaveError::VerificationFailed(message)) => assert!(message.contains("DG1"), "{}", message),
+            other => panic!("expected a DG1 hash mismatch, got {:?}", other.map(|r| r.reference_id)),
+        }
+    }
+
+    #[test]
+    fn rejects_csca_of_another_country() {
+        match parse(&parser(), "chip_wrong_csca.json") {
+            Err(EnclaveError::VerificationFailed(message)) => assert!(message.contains("CSCA of UT"), "{}", message),
+            other => panic!("expected a CSCA country mismatch, got {:?}", other.map(|r| r.reference_id)),
+        }
+    }
+
+    #[test]
+    fn rejects_chip_without_trusted_csca() {
+        let parser = PassportChipParser { trust: CscaTrust::from_files(&[], &[]) };
+        assert!(matches!(parse(&parser, "chip_valid.json"), Err(EnclaveError::InternalError(_))));
+    }
+}
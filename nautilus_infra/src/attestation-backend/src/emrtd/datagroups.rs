@@ -0,0 +1,78 @@
+// emrtd/datagroups.rs
+use super::tlv;
+use crate::EnclaveError;
+
+const DG1_TAG: u32 = 0x61;
+const MRZ_TAG: u32 = 0x5F1F;
+const DG2_TAG: u32 = 0x75;
+const BIOMETRIC_GROUP_TAG: u32 = 0x7F61;
+const BIOMETRIC_TEMPLATE_TAG: u32 = 0x7F60;
+const BIOMETRIC_DATA_TAG: u32 = 0x5F2E;
+const BIOMETRIC_DATA_EXTENDED_TAG: u32 = 0x7F2E;
+
+/// ISO/IEC 19794-5 facial record header: "FAC\0", version, length, image count.
+const FACE_HEADER_LENGTH: usize = 14;
+/// Facial information block before the feature points.
+const FACE_INFORMATION_LENGTH: usize = 20;
+const FEATURE_POINT_LENGTH: usize = 8;
+/// Image information block before the image data.
+const IMAGE_INFORMATION_LENGTH: usize = 12;
+
+/// MRZ text from DG1, split into lines so `mrz::parse_mrz` can read it.
+pub fn mrz_from_dg1(dg1: &[u8]) -> Result<String, EnclaveError> {
+    let mrz = tlv::find(tlv::unwrap(dg1, DG1_TAG)?, MRZ_TAG)?;
+    let mrz = std::str::from_utf8(mrz)
+        .map_err(|_| EnclaveError::InvalidInput("DG1 MRZ is not ASCII".to_string()))?;
+    let line_length = match mrz.len() {
+        90 => 30,
+        72 => 36,
+        88 => 44,
+        other => {
+            return Err(EnclaveError::InvalidInput(format!(
+                "DG1 MRZ has unexpected length {}",
+                other
+            )))
+        }
+    };
+    Ok(mrz
+        .as_bytes()
+        .chunks(line_length)
+        .map(|line| String::from_utf8_lossy(line).into_owned())
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+/// First facial image (JPEG or JPEG 2000) from DG2.
+pub fn face_from_dg2(dg2: &[u8]) -> Result<Vec<u8>, EnclaveError> {
+    let group = tlv::find(tlv::unwrap(dg2, DG2_TAG)?, BIOMETRIC_GROUP_TAG)?;
+    let template = tlv::find(group, BIOMETRIC_TEMPLATE_TAG)?;
+    let record = tlv::find(template, BIOMETRIC_DATA_TAG)
+        .or_else(|_| tlv::find(template, BIOMETRIC_DATA_EXTENDED_TAG))?;
+    face_image(record)
+}
+
+fn face_image(record: &[u8]) -> Result<Vec<u8>, EnclaveError> {
+    let invalid = |reason: &str| EnclaveError::InvalidInput(format!("DG2 facial record: {}", reason));
+
+    if !record.starts_with(b"FAC\0") {
+        return Err(invalid("not an ISO/IEC 19794-5 record"));
+    }
+    let face = record.get(FACE_HEADER_LENGTH..).ok_or_else(|| invalid("truncated header"))?;
+    let block_length = face
+        .get(0..4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+        .ok_or_else(|| invalid("truncated facial information"))?;
+    let feature_points = face
+        .get(4..6)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+        .ok_or_else(|| invalid("truncated facial information"))?;
+
+    let image_start = FACE_INFORMATION_LENGTH + feature_points * FEATURE_POINT_LENGTH + IMAGE_INFORMATION_LENGTH;
+    let image = face
+        .get(image_start..block_length)
+        .ok_or_else(|| invalid("image data is out of bounds"))?;
+    if image.is_empty() {
+        return Err(invalid("no image data"));
+    }
+    Ok(image.to_vec())
+}
@@ -0,0 +1,235 @@
+// emrtd/mod.rs
+use crate::EnclaveError;
+use base64::{engine::general_purpose, Engine as _};
+use cms::content_info::ContentInfo;
+use cms::signed_data::SignedData;
+use der::asn1::{ObjectIdentifier, OctetString, SetOfVec};
+use der::{Decode, DecodePem, Encode, Sequence};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tracing::{info, warn};
+use x509_cert::name::Name;
+use x509_cert::Certificate;
+
+pub mod datagroups;
+pub mod signature;
+pub mod sod;
+pub mod tlv;
+
+use sod::SecurityObject;
+
+const CSCA_MASTER_LIST: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.23.136.1.1.2");
+const COUNTRY_NAME: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.6");
+
+/// Files read from an ePassport chip over NFC, base64 encoded.
+#[derive(Debug, Deserialize)]
+pub struct ChipFiles {
+    /// EF.SOD, the signed hashes of the data groups
+    pub sod: String,
+    /// EF.DG1, the MRZ
+    pub dg1: String,
+    /// EF.DG2, the facial image
+    pub dg2: String,
+}
+
+/// Data from a chip that passed passive authentication.
+pub struct ChipData {
+    /// MRZ from DG1, one line per row
+    pub mrz: String,
+    /// Facial image from DG2 (JPEG or JPEG 2000)
+    pub face_image: Vec<u8>,
+    /// Country (ISO 3166-1 alpha-2) of the CSCA the Document Signer chains to
+    pub csca_country: Option<String>,
+    /// Hex SHA-256 of EF.SOD
+    pub sod_digest: String,
+}
+
+/// ICAO 9303-12 `CscaMasterList`.
+#[derive(Sequence)]
+struct CscaMasterList {
+    version: u8,
+    cert_list: SetOfVec<Certificate>,
+}
+
+/// Country Signing CA certificates trusted for passive authentication.
+pub struct CscaTrust {
+    certificates: Vec<Certificate>,
+}
+
+impl CscaTrust {
+    /// Load CSCAs from ICAO master lists in `CSCA_MASTER_LIST_FILES` and single
+    /// certificates (PEM or DER) in `CSCA_CERT_FILES`, both comma separated.
+    /// Master list signatures are not checked: the files are vetted when they
+    /// are configured.
+    pub fn from_env() -> Self {
+        Self::from_files(&env_paths("CSCA_MASTER_LIST_FILES"), &env_paths("CSCA_CERT_FILES"))
+    }
+
+    /// Load CSCAs from master list and certificate files; unreadable files are skipped.
+    pub fn from_files(master_lists: &[String], certificate_files: &[String]) -> Self {
+        let mut certificates = Vec::new();
+        for path in master_lists {
+            match std::fs::read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| certificates_from_master_list(&bytes))
+            {
+                Ok(list) => {
+                    info!("Loaded {} CSCA certificates from master list {}", list.len(), path);
+                    certificates.extend(list);
+                }
+                Err(e) => warn!("Failed to load CSCA master list {}: {}", path, e),
+            }
+        }
+        for path in certificate_files {
+            match std::fs::read(path)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| certificate_from_bytes(&bytes))
+            {
+                Ok(certificate) => {
+                    info!("Loaded CSCA certificate from {}", path);
+                    certificates.push(certificate);
+                }
+                Err(e) => warn!("Failed to load CSCA certificate {}: {}", path, e),
+            }
+        }
+        if certificates.is_empty() {
+            warn!("No CSCA certificates configured: passport chips will be rejected");
+        }
+        Self { certificates }
+    }
+
+    /// Chain a Document Signer certificate to a trusted CSCA, with both valid
+    /// at `timestamp_ms`. Returns the CSCA that signed it.
+    pub fn verify_document_signer(
+        &self,
+        document_signer: &Certificate,
+        timestamp_ms: u64,
+    ) -> Result<&Certificate, EnclaveError> {
+        if self.certificates.is_empty() {
+            return Err(EnclaveError::InternalError(
+                "No CSCA certificate configured".to_string(),
+            ));
+        }
+        check_validity(document_signer, "Document Signer", timestamp_ms)?;
+
+        let tbs = document_signer
+            .tbs_certificate
+            .to_der()
+            .map_err(|e| EnclaveError::InvalidInput(format!("Document Signer certificate: {}", e)))?;
+        // A CSCA with the issuer's name whose key cannot be used (e.g. an
+        // unsupported curve) is reported as such rather than as untrusted
+        let mut unusable = None;
+        for csca in self
+            .certificates
+            .iter()
+            .filter(|csca| csca.tbs_certificate.subject == document_signer.tbs_certificate.issuer)
+        {
+            match signature::verify(
+                &csca.tbs_certificate.subject_public_key_info,
+                &document_signer.signature_algorithm,
+                None,
+                &tbs,
+                document_signer.signature.raw_bytes(),
+            ) {
+                Ok(()) => {
+                    check_validity(csca, "CSCA", timestamp_ms)?;
+                    return Ok(csca);
+                }
+                Err(e @ EnclaveError::InvalidInput(_)) => unusable = Some(e),
+                Err(_) => {}
+            }
+        }
+        Err(unusable.unwrap_or_else(|| {
+            EnclaveError::VerificationFailed(
+                "Document Signer certificate does not chain to a trusted CSCA".to_string(),
+            )
+        }))
+    }
+}
+
+/// Passive authentication (ICAO 9303-11): the SOD is signed by a Document
+/// Signer that chains to a trusted CSCA, and DG1 and DG2 match the signed
+/// hashes. This proves the data is genuine, not that the chip is not a clone.
+pub fn passive_authentication(
+    files: &ChipFiles,
+    trust: &CscaTrust,
+    timestamp_ms: u64,
+) -> Result<ChipData, EnclaveError> {
+    let decode = |name: &str, value: &str| {
+        general_purpose::STANDARD
+            .decode(value)
+            .map_err(|e| EnclaveError::InvalidInput(format!("Passport {} is not base64: {}", name, e)))
+    };
+    let sod_bytes = decode("SOD", &files.sod)?;
+    let dg1 = decode("DG1", &files.dg1)?;
+    let dg2 = decode("DG2", &files.dg2)?;
+
+    let sod = SecurityObject::parse_and_verify(&sod_bytes)?;
+    let csca = trust.verify_document_signer(&sod.document_signer, timestamp_ms)?;
+    sod.check_data_group(1, &dg1)?;
+    sod.check_data_group(2, &dg2)?;
+
+    Ok(ChipData {
+        mrz: datagroups::mrz_from_dg1(&dg1)?,
+        face_image: datagroups::face_from_dg2(&dg2)?,
+        csca_country: country(&csca.tbs_certificate.subject),
+        sod_digest: hex::encode(Sha256::digest(&sod_bytes)),
+    })
+}
+
+fn check_validity(certificate: &Certificate, label: &str, timestamp_ms: u64) -> Result<(), EnclaveError> {
+    let validity = &certificate.tbs_certificate.validity;
+    let now = Duration::from_millis(timestamp_ms);
+    if now < validity.not_before.to_unix_duration() || now > validity.not_after.to_unix_duration() {
+        return Err(EnclaveError::VerificationFailed(format!(
+            "{} certificate is not valid at this time",
+            label
+        )));
+    }
+    Ok(())
+}
+
+/// The `C` attribute of a name.
+fn country(name: &Name) -> Option<String> {
+    name.0
+        .iter()
+        .flat_map(|rdn| rdn.0.iter())
+        .find(|attribute| attribute.oid == COUNTRY_NAME)
+        .and_then(|attribute| std::str::from_utf8(attribute.value.value()).ok())
+        .map(|country| country.to_ascii_uppercase())
+}
+
+fn env_paths(name: &str) -> Vec<String> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn certificate_from_bytes(bytes: &[u8]) -> Result<Certificate, String> {
+    if bytes.starts_with(b"-----BEGIN") {
+        Certificate::from_pem(bytes).map_err(|e| e.to_string())
+    } else {
+        Certificate::from_der(bytes).map_err(|e| e.to_string())
+    }
+}
+
+fn certificates_from_master_list(bytes: &[u8]) -> Result<Vec<Certificate>, String> {
+    let content_info = ContentInfo::from_der(bytes).map_err(|e| e.to_string())?;
+    let signed_data: SignedData = content_info.content.decode_as().map_err(|e| e.to_string())?;
+    let encap = signed_data.encap_content_info;
+    if encap.econtent_type != CSCA_MASTER_LIST {
+        return Err("not a CSCA master list".to_string());
+    }
+    let content = encap
+        .econtent
+        .ok_or("master list is empty")?
+        .decode_as::<OctetString>()
+        .map_err(|e| e.to_string())?;
+    let list = CscaMasterList::from_der(content.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(list.cert_list.into_vec())
+}
@@ -0,0 +1,202 @@
+// emrtd/signature.rs
+use crate::EnclaveError;
+use der::asn1::ObjectIdentifier;
+use der::{Decode, Encode};
+use p256::ecdsa::signature::hazmat::PrehashVerifier;
+use rsa::pkcs1::RsaPssParams;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Sign, Pss, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+use x509_cert::spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+
+const SHA1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.14.3.2.26");
+const SHA224: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.4");
+const SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.1");
+const SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.2");
+const SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.3");
+
+const RSA_ENCRYPTION: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const RSASSA_PSS: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.10");
+const SHA1_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.5");
+const SHA256_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.11");
+const SHA384_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.12");
+const SHA512_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.13");
+const SHA224_WITH_RSA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.14");
+
+const EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const ECDSA_WITH_SHA1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.1");
+const ECDSA_WITH_SHA224: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.1");
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const ECDSA_WITH_SHA384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.3");
+const ECDSA_WITH_SHA512: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.4");
+
+const P256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const P384: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.132.0.34");
+/// `ecStdCurvesAndGeneration` arc (RFC 5639) holding the Brainpool curves
+const BRAINPOOL_ARC: &str = "1.3.36.3.3.2.8.1.1.";
+
+/// Hash functions used by ePassport security objects and certificates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl HashAlgorithm {
+    pub fn from_oid(oid: &ObjectIdentifier) -> Result<Self, EnclaveError> {
+        match *oid {
+            SHA1 => Ok(Self::Sha1),
+            SHA224 => Ok(Self::Sha224),
+            SHA256 => Ok(Self::Sha256),
+            SHA384 => Ok(Self::Sha384),
+            SHA512 => Ok(Self::Sha512),
+            _ => Err(unsupported("digest", oid)),
+        }
+    }
+
+    pub fn digest(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Sha1 => Sha1::digest(data).to_vec(),
+            Self::Sha224 => Sha224::digest(data).to_vec(),
+            Self::Sha256 => Sha256::digest(data).to_vec(),
+            Self::Sha384 => Sha384::digest(data).to_vec(),
+            Self::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    fn pkcs1v15(&self) -> Pkcs1v15Sign {
+        match self {
+            Self::Sha1 => Pkcs1v15Sign::new::<Sha1>(),
+            Self::Sha224 => Pkcs1v15Sign::new::<Sha224>(),
+            Self::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+            Self::Sha384 => Pkcs1v15Sign::new::<Sha384>(),
+            Self::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+        }
+    }
+
+    fn pss(&self, salt_length: usize) -> Pss {
+        match self {
+            Self::Sha1 => Pss::new_with_salt::<Sha1>(salt_length),
+            Self::Sha224 => Pss::new_with_salt::<Sha224>(salt_length),
+            Self::Sha256 => Pss::new_with_salt::<Sha256>(salt_length),
+            Self::Sha384 => Pss::new_with_salt::<Sha384>(salt_length),
+            Self::Sha512 => Pss::new_with_salt::<Sha512>(salt_length),
+        }
+    }
+}
+
+enum Scheme {
+    Pkcs1v15(HashAlgorithm),
+    Pss(HashAlgorithm, usize),
+    Ecdsa(HashAlgorithm),
+}
+
+/// Verify `signature` over `message` by the holder of `key`. CMS signers may
+/// name only the key type (`rsaEncryption`, `ecPublicKey`) as the signature
+/// algorithm, in which case `digest_algorithm` supplies the hash.
+pub fn verify(
+    key: &SubjectPublicKeyInfoOwned,
+    algorithm: &AlgorithmIdentifierOwned,
+    digest_algorithm: Option<&ObjectIdentifier>,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), EnclaveError> {
+    let signer_digest = || {
+        digest_algorithm
+            .ok_or_else(|| unsupported("signature", &algorithm.oid))
+            .and_then(HashAlgorithm::from_oid)
+    };
+    let scheme = match algorithm.oid {
+        RSA_ENCRYPTION => Scheme::Pkcs1v15(signer_digest()?),
+        SHA1_WITH_RSA => Scheme::Pkcs1v15(HashAlgorithm::Sha1),
+        SHA224_WITH_RSA => Scheme::Pkcs1v15(HashAlgorithm::Sha224),
+        SHA256_WITH_RSA => Scheme::Pkcs1v15(HashAlgorithm::Sha256),
+        SHA384_WITH_RSA => Scheme::Pkcs1v15(HashAlgorithm::Sha384),
+        SHA512_WITH_RSA => Scheme::Pkcs1v15(HashAlgorithm::Sha512),
+        RSASSA_PSS => {
+            let params = algorithm
+                .parameters
+                .as_ref()
+                .ok_or_else(|| invalid("RSASSA-PSS without parameters"))?
+                .to_der()
+                .map_err(|e| invalid(&e.to_string()))?;
+            let params = RsaPssParams::from_der(&params).map_err(|e| invalid(&e.to_string()))?;
+            Scheme::Pss(HashAlgorithm::from_oid(&params.hash.oid)?, params.salt_len as usize)
+        }
+        EC_PUBLIC_KEY => Scheme::Ecdsa(signer_digest()?),
+        ECDSA_WITH_SHA1 => Scheme::Ecdsa(HashAlgorithm::Sha1),
+        ECDSA_WITH_SHA224 => Scheme::Ecdsa(HashAlgorithm::Sha224),
+        ECDSA_WITH_SHA256 => Scheme::Ecdsa(HashAlgorithm::Sha256),
+        ECDSA_WITH_SHA384 => Scheme::Ecdsa(HashAlgorithm::Sha384),
+        ECDSA_WITH_SHA512 => Scheme::Ecdsa(HashAlgorithm::Sha512),
+        other => return Err(unsupported("signature", &other)),
+    };
+
+    let valid = match scheme {
+        Scheme::Pkcs1v15(hash) => rsa_key(key)?
+            .verify(hash.pkcs1v15(), &hash.digest(message), signature)
+            .is_ok(),
+        Scheme::Pss(hash, salt_length) => rsa_key(key)?
+            .verify(hash.pss(salt_length), &hash.digest(message), signature)
+            .is_ok(),
+        Scheme::Ecdsa(hash) => verify_ecdsa(key, &hash.digest(message), signature)?,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(EnclaveError::VerificationFailed(
+            "Passport chip signature is invalid".to_string(),
+        ))
+    }
+}
+
+fn rsa_key(key: &SubjectPublicKeyInfoOwned) -> Result<RsaPublicKey, EnclaveError> {
+    let der = key.to_der().map_err(|e| invalid(&e.to_string()))?;
+    RsaPublicKey::from_public_key_der(&der).map_err(|e| invalid(&format!("RSA key: {}", e)))
+}
+
+fn verify_ecdsa(key: &SubjectPublicKeyInfoOwned, prehash: &[u8], signature: &[u8]) -> Result<bool, EnclaveError> {
+    let curve = key
+        .algorithm
+        .parameters
+        .as_ref()
+        .and_then(|p| p.decode_as::<ObjectIdentifier>().ok())
+        .ok_or_else(|| invalid("EC key without a named curve"))?;
+    let point = key.subject_public_key.raw_bytes();
+    match curve {
+        P256 => {
+            let key = p256::ecdsa::VerifyingKey::from_sec1_bytes(point)
+                .map_err(|e| invalid(&format!("P-256 key: {}", e)))?;
+            let Ok(signature) = p256::ecdsa::Signature::from_der(signature) else {
+                return Ok(false);
+            };
+            Ok(key.verify_prehash(prehash, &signature).is_ok())
+        }
+        P384 => {
+            let key = p384::ecdsa::VerifyingKey::from_sec1_bytes(point)
+                .map_err(|e| invalid(&format!("P-384 key: {}", e)))?;
+            let Ok(signature) = p384::ecdsa::Signature::from_der(signature) else {
+                return Ok(false);
+            };
+            Ok(key.verify_prehash(prehash, &signature).is_ok())
+        }
+        // Brainpool curves are common on European passports but not supported yet
+        other if other.to_string().starts_with(BRAINPOOL_ARC) => Err(EnclaveError::InvalidInput(format!(
+            "Passport chip is signed with Brainpool curve {}, which is not supported yet",
+            other
+        ))),
+        other => Err(unsupported("curve", &other)),
+    }
+}
+
+fn invalid(reason: &str) -> EnclaveError {
+    EnclaveError::InvalidInput(format!("Passport chip signature: {}", reason))
+}
+
+fn unsupported(kind: &str, oid: &ObjectIdentifier) -> EnclaveError {
+    EnclaveError::InvalidInput(format!("Unsupported passport chip {} algorithm {}", kind, oid))
+}
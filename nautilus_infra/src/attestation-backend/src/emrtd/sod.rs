@@ -0,0 +1,184 @@
+// emrtd/sod.rs
+use super::signature::{self, HashAlgorithm};
+use super::tlv;
+use crate::EnclaveError;
+use cms::cert::CertificateChoices;
+use cms::content_info::ContentInfo;
+use cms::signed_data::{SignedData, SignerIdentifier, SignerInfo};
+use der::asn1::{ObjectIdentifier, OctetString};
+use der::{Decode, Encode, Sequence};
+use x509_cert::spki::AlgorithmIdentifierOwned;
+use x509_cert::Certificate;
+
+/// EF.SOD wraps the CMS ContentInfo in application tag 23.
+const SOD_TAG: u32 = 0x77;
+const SIGNED_DATA: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.7.2");
+const LDS_SECURITY_OBJECT: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.23.136.1.1.1");
+const CONTENT_TYPE_ATTRIBUTE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.3");
+const MESSAGE_DIGEST_ATTRIBUTE: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.9.4");
+const SUBJECT_KEY_IDENTIFIER: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.14");
+
+/// ICAO 9303-10 `LDSSecurityObject`: hashes of the data groups on the chip.
+#[derive(Sequence)]
+struct LdsSecurityObject {
+    version: u8,
+    hash_algorithm: AlgorithmIdentifierOwned,
+    data_group_hash_values: Vec<DataGroupHash>,
+    #[asn1(optional = "true")]
+    lds_version_info: Option<der::Any>,
+}
+
+#[derive(Sequence)]
+struct DataGroupHash {
+    data_group_number: u8,
+    data_group_hash_value: OctetString,
+}
+
+/// A parsed EF.SOD whose signer signature has been checked.
+pub struct SecurityObject {
+    hash_algorithm: HashAlgorithm,
+    data_group_hashes: Vec<(u8, Vec<u8>)>,
+    /// Document Signer certificate embedded in the SOD
+    pub document_signer: Certificate,
+}
+
+impl SecurityObject {
+    /// Parse EF.SOD and verify its CMS signature with the embedded Document
+    /// Signer certificate. The certificate itself still has to be chained to
+    /// a CSCA by the caller.
+    pub fn parse_and_verify(sod: &[u8]) -> Result<Self, EnclaveError> {
+        let content_info = ContentInfo::from_der(tlv::unwrap(sod, SOD_TAG)?)
+            .map_err(|e| invalid(&format!("ContentInfo: {}", e)))?;
+        if content_info.content_type != SIGNED_DATA {
+            return Err(invalid("not CMS SignedData"));
+        }
+        let signed_data: SignedData = content_info
+            .content
+            .decode_as()
+            .map_err(|e| invalid(&format!("SignedData: {}", e)))?;
+
+        let encap = &signed_data.encap_content_info;
+        if encap.econtent_type != LDS_SECURITY_OBJECT {
+            return Err(invalid("content is not an LDS security object"));
+        }
+        let content = encap
+            .econtent
+            .as_ref()
+            .ok_or_else(|| invalid("no LDS security object"))?
+            .decode_as::<OctetString>()
+            .map_err(|e| invalid(&format!("LDS security object: {}", e)))?;
+
+        let mut signers = signed_data.signer_infos.0.iter();
+        let signer = signers.next().ok_or_else(|| invalid("no signer"))?;
+        if signers.next().is_some() {
+            return Err(invalid("more than one signer"));
+        }
+        let document_signer = find_signer_certificate(&signed_data, signer)?;
+        verify_signer(signer, &document_signer, content.as_bytes())?;
+
+        let lds = LdsSecurityObject::from_der(content.as_bytes())
+            .map_err(|e| invalid(&format!("LDS security object: {}", e)))?;
+        Ok(Self {
+            hash_algorithm: HashAlgorithm::from_oid(&lds.hash_algorithm.oid)?,
+            data_group_hashes: lds
+                .data_group_hash_values
+                .into_iter()
+                .map(|h| (h.data_group_number, h.data_group_hash_value.into_bytes()))
+                .collect(),
+            document_signer,
+        })
+    }
+
+    /// Check a data group file against the hash signed in the SOD.
+    pub fn check_data_group(&self, number: u8, contents: &[u8]) -> Result<(), EnclaveError> {
+        let expected = self
+            .data_group_hashes
+            .iter()
+            .find(|(n, _)| *n == number)
+            .map(|(_, hash)| hash)
+            .ok_or_else(|| invalid(&format!("DG{} is not covered by the SOD", number)))?;
+        if self.hash_algorithm.digest(contents) == *expected {
+            Ok(())
+        } else {
+            Err(EnclaveError::VerificationFailed(format!(
+                "Passport DG{} does not match its signed hash",
+                number
+            )))
+        }
+    }
+}
+
+/// The certificate named by the signer identifier.
+fn find_signer_certificate(signed_data: &SignedData, signer: &SignerInfo) -> Result<Certificate, EnclaveError> {
+    signed_data
+        .certificates
+        .iter()
+        .flat_map(|set| set.0.iter())
+        .filter_map(|choice| match choice {
+            CertificateChoices::Certificate(certificate) => Some(certificate),
+            _ => None,
+        })
+        .find(|certificate| {
+            let tbs = &certificate.tbs_certificate;
+            match &signer.sid {
+                SignerIdentifier::IssuerAndSerialNumber(id) => {
+                    tbs.issuer == id.issuer && tbs.serial_number == id.serial_number
+                }
+                SignerIdentifier::SubjectKeyIdentifier(ski) => tbs
+                    .extensions
+                    .iter()
+                    .flatten()
+                    .filter(|ext| ext.extn_id == SUBJECT_KEY_IDENTIFIER)
+                    .any(|ext| ext.extn_value.as_bytes().ends_with(ski.0.as_bytes())),
+            }
+        })
+        .cloned()
+        .ok_or_else(|| invalid("Document Signer certificate is not embedded"))
+}
+
+/// ICAO requires signed attributes: the content type, and a message digest
+/// over the LDS security object, signed by the Document Signer.
+fn verify_signer(signer: &SignerInfo, document_signer: &Certificate, content: &[u8]) -> Result<(), EnclaveError> {
+    let attributes = signer
+        .signed_attrs
+        .as_ref()
+        .ok_or_else(|| invalid("signer has no signed attributes"))?;
+    let attribute = |oid: ObjectIdentifier| {
+        attributes
+            .iter()
+            .find(|a| a.oid == oid)
+            .and_then(|a| a.values.get(0))
+            .ok_or_else(|| invalid(&format!("signed attribute {} is missing", oid)))
+    };
+
+    let content_type = attribute(CONTENT_TYPE_ATTRIBUTE)?
+        .decode_as::<ObjectIdentifier>()
+        .map_err(|e| invalid(&e.to_string()))?;
+    if content_type != LDS_SECURITY_OBJECT {
+        return Err(invalid("signed content type is not an LDS security object"));
+    }
+
+    let message_digest = attribute(MESSAGE_DIGEST_ATTRIBUTE)?
+        .decode_as::<OctetString>()
+        .map_err(|e| invalid(&e.to_string()))?;
+    let digest_algorithm = HashAlgorithm::from_oid(&signer.digest_alg.oid)?;
+    if digest_algorithm.digest(content) != message_digest.as_bytes() {
+        return Err(EnclaveError::VerificationFailed(
+            "Passport SOD content does not match its signed digest".to_string(),
+        ));
+    }
+
+    // The signature covers the attributes encoded as a DER SET
+    let signed = attributes.to_der().map_err(|e| invalid(&e.to_string()))?;
+    signature::verify(
+        &document_signer.tbs_certificate.subject_public_key_info,
+        &signer.signature_algorithm,
+        Some(&signer.digest_alg.oid),
+        &signed,
+        signer.signature.as_bytes(),
+    )
+}
+
+fn invalid(reason: &str) -> EnclaveError {
+    EnclaveError::InvalidInput(format!("Passport SOD: {}", reason))
+}
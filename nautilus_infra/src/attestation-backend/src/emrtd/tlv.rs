@@ -0,0 +1,80 @@
+// emrtd/tlv.rs
+use crate::EnclaveError;
+
+/// One BER-TLV object as used in the LDS data groups.
+pub struct Tlv<'a> {
+    /// Tag bytes as a big-endian number (e.g. `0x5F1F`)
+    pub tag: u32,
+    pub value: &'a [u8],
+}
+
+/// Read the TLV object at the start of `input`, returning it and the rest.
+pub fn read(input: &[u8]) -> Result<(Tlv<'_>, &[u8]), EnclaveError> {
+    let truncated = || EnclaveError::InvalidInput("Passport chip data is truncated".to_string());
+
+    let mut pos = 0;
+    let first = *input.first().ok_or_else(truncated)?;
+    let mut tag = first as u32;
+    pos += 1;
+    if first & 0x1F == 0x1F {
+        loop {
+            let byte = *input.get(pos).ok_or_else(truncated)?;
+            pos += 1;
+            tag = (tag << 8) | byte as u32;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            if pos > 3 {
+                return Err(EnclaveError::InvalidInput("Passport chip tag is too long".to_string()));
+            }
+        }
+    }
+
+    let first_length = *input.get(pos).ok_or_else(truncated)?;
+    pos += 1;
+    let length = match first_length {
+        0..=0x7F => first_length as usize,
+        0x81..=0x84 => {
+            let count = (first_length & 0x7F) as usize;
+            let bytes = input.get(pos..pos + count).ok_or_else(truncated)?;
+            pos += count;
+            bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize)
+        }
+        _ => {
+            return Err(EnclaveError::InvalidInput(
+                "Passport chip data has an unsupported length encoding".to_string(),
+            ))
+        }
+    };
+
+    let end = pos.checked_add(length).ok_or_else(truncated)?;
+    let value = input.get(pos..end).ok_or_else(truncated)?;
+    Ok((Tlv { tag, value }, &input[end..]))
+}
+
+/// Value of the single object with `tag` that `input` must consist of.
+pub fn unwrap(input: &[u8], tag: u32) -> Result<&[u8], EnclaveError> {
+    let (tlv, _) = read(input)?;
+    if tlv.tag != tag {
+        return Err(EnclaveError::InvalidInput(format!(
+            "Expected passport chip tag {:X}, found {:X}",
+            tag, tlv.tag
+        )));
+    }
+    Ok(tlv.value)
+}
+
+/// First child of a constructed value with `tag`.
+pub fn find(mut input: &[u8], tag: u32) -> Result<&[u8], EnclaveError> {
+    while !input.is_empty() {
+        let (tlv, rest) = read(input)?;
+        if tlv.tag == tag {
+            return Ok(tlv.value);
+        }
+        input = rest;
+    }
+    Err(EnclaveError::InvalidInput(format!(
+        "Passport chip data has no tag {:X}",
+        tag
+    )))
+}
@@ -0,0 +1,470 @@
+// evidence.rs
+use crate::age::AgePredicate;
+use crate::documents::IdentityRecord;
+use crate::merkle;
+use crate::rules::DocumentClass;
+use crate::EnclaveError;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Domain separator for KYC evidence commitments.
+const EVIDENCE_DOMAIN: &[u8] = b"SuiVerify/evidence/v3";
+
+/// DID types, matching `DID_AGE_VERIFY` / `DID_CITIZENSHIP_VERIFY` in did_registry.move.
+pub const DID_AGE_VERIFY: u8 = 1;
+pub const DID_CITIZENSHIP_VERIFY: u8 = 2;
+
+/// Reject DID types the registry contract does not know.
+pub fn check_did_type(did_type: u8) -> Result<(), EnclaveError> {
+    match did_type {
+        DID_AGE_VERIFY | DID_CITIZENSHIP_VERIFY => Ok(()),
+        other => Err(EnclaveError::InvalidInput(format!(
+            "Unknown DID type: {}",
+            other
+        ))),
+    }
+}
+
+/// Everything needed to recompute an evidence hash during an audit. It holds
+/// digests and the age predicate only, never identity data such as the name or
+/// date of birth, and the salt never leaves memory except in a field opening.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceOpening {
+    /// Normalized Sui address
+    pub wallet_address: String,
+    /// Hex SHA-256 of the decrypted document
+    pub document_digest: String,
+    /// Class of the document backing the claim
+    pub document_class: DocumentClass,
+    /// Whether the issuer signature over the document was verified
+    #[serde(default)]
+    pub issuer_signed: bool,
+    /// Document nullifier for this DID type, if the document has a stable identity
+    #[serde(default)]
+    pub nullifier: Option<String>,
+    /// Hex SHA-256 of each decrypted face frame, in submission order
+    pub face_digests: Vec<String>,
+    pub did_type: u8,
+    pub timestamp_ms: u64,
+    pub nonce: String,
+    /// Hex digest of the issuer-signed source record (e.g. EF.SOD), if one was read
+    #[serde(default)]
+    pub record_digest: Option<String>,
+    /// Age predicate for age-gated DID types
+    #[serde(default)]
+    pub age: Option<AgePredicate>,
+    /// Hex random salt; each leaf is salted with a value derived from it
+    pub salt: String,
+}
+
+/// One named, normalized value committed to as a Merkle leaf.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceField {
+    pub name: String,
+    pub value: String,
+}
+
+/// A single field of an evidence commitment, opened without revealing the
+/// others: the value, its leaf salt and the Merkle path to the root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldOpening {
+    pub evidence_hash: String,
+    pub name: String,
+    pub value: String,
+    /// Hex leaf salt; the opening salt it was derived from stays private
+    pub salt: String,
+    pub index: usize,
+    pub leaf_count: usize,
+    /// Hex sibling hashes, leaf to root
+    pub proof: Vec<String>,
+}
+
+impl FieldOpening {
+    /// Check the opened value against `evidence_hash`.
+    pub fn verify(&self) -> bool {
+        let decode32 = |h: &str| -> Option<[u8; 32]> { hex::decode(h).ok()?.try_into().ok() };
+        let (Some(root), Ok(salt)) = (decode32(&self.evidence_hash), hex::decode(&self.salt)) else {
+            return false;
+        };
+        let Some(proof) = self.proof.iter().map(|h| decode32(h)).collect::<Option<Vec<_>>>() else {
+            return false;
+        };
+        let leaf = merkle::leaf_hash(EVIDENCE_DOMAIN, &salt, &self.name, &self.value);
+        merkle::verify(&root, leaf, self.index, self.leaf_count, &proof)
+    }
+}
+
+impl EvidenceOpening {
+    /// Build an opening with a fresh salt over the decrypted evidence, the
+    /// digest of the identity record read from it and the age predicate.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        wallet_address: &str,
+        document: &[u8],
+        record: Option<&IdentityRecord>,
+        document_class: DocumentClass,
+        nullifier: Option<String>,
+        age: Option<&AgePredicate>,
+        faces: &[impl AsRef<[u8]>],
+        did_type: u8,
+        timestamp_ms: u64,
+        nonce: &str,
+    ) -> Self {
+        let mut salt = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut salt);
+        Self {
+            wallet_address: wallet_address.to_string(),
+            document_digest: hex::encode(Sha256::digest(document)),
+            document_class,
+            issuer_signed: record.is_some_and(|r| r.issuer_signed),
+            nullifier,
+            face_digests: faces.iter().map(|f| hex::encode(Sha256::digest(f.as_ref()))).collect(),
+            did_type,
+            timestamp_ms,
+            nonce: nonce.to_string(),
+            record_digest: record.map(|r| r.source_digest.clone()),
+            age: age.cloned(),
+            salt: hex::encode(salt),
+        }
+    }
+
+    /// Leaves of the commitment in tree order; absent values are empty strings.
+    pub fn fields(&self) -> Vec<EvidenceField> {
+        let field = |name: &str, value: String| EvidenceField { name: name.to_string(), value };
+        let age = self.age.as_ref();
+        vec![
+            field("wallet_address", self.wallet_address.clone()),
+            field("did_type", self.did_type.to_string()),
+            field("timestamp_ms", self.timestamp_ms.to_string()),
+            field("nonce", self.nonce.clone()),
+            field("document_class", self.document_class.as_str().to_string()),
+            field("issuer_signed", self.issuer_signed.to_string()),
+            field("nullifier", self.nullifier.clone().unwrap_or_default()),
+            field("document_digest", self.document_digest.clone()),
+            field("face_digests", self.face_digests.join(",")),
+            field("record_digest", self.record_digest.clone().unwrap_or_default()),
+            field("age_satisfied", age.map(|a| a.satisfied.to_string()).unwrap_or_default()),
+            field("age_threshold_years", age.map(|a| a.threshold_years.to_string()).unwrap_or_default()),
+            field("age_reference_date", age.map(|a| a.reference_date.clone()).unwrap_or_default()),
+        ]
+    }
+
+    /// Per-leaf salt, SHA-256(domain || opening salt || name), so opening one
+    /// field does not expose the salts of the others.
+    fn leaf_salt(&self, name: &str) -> Result<[u8; 32], EnclaveError> {
+        let salt = hex::decode(&self.salt)
+            .map_err(|e| EnclaveError::InvalidInput(format!("Invalid evidence salt: {}", e)))?;
+        let mut hasher = Sha256::new();
+        hasher.update(EVIDENCE_DOMAIN);
+        hasher.update(salt);
+        hasher.update(name.as_bytes());
+        Ok(hasher.finalize().into())
+    }
+
+    fn leaves(&self, fields: &[EvidenceField]) -> Result<Vec<[u8; 32]>, EnclaveError> {
+        fields
+            .iter()
+            .map(|f| {
+                let salt = self.leaf_salt(&f.name)?;
+                Ok(merkle::leaf_hash(EVIDENCE_DOMAIN, &salt, &f.name, &f.value))
+            })
+            .collect()
+    }
+
+    /// Salted Merkle root over [`Self::fields`]; the value stored as
+    /// `evidence_hash` on chain.
+    pub fn commitment(&self) -> Result<[u8; 32], EnclaveError> {
+        let leaves = self.leaves(&self.fields())?;
+        merkle::root(&leaves)
+            .ok_or_else(|| EnclaveError::InternalError("Empty evidence commitment".to_string()))
+    }
+
+    /// Hex form of [`Self::commitment`].
+    pub fn evidence_hash(&self) -> Result<String, EnclaveError> {
+        self.commitment().map(hex::encode)
+    }
+
+    /// Open one named field for an audit.
+    pub fn open_field(&self, name: &str) -> Result<FieldOpening, EnclaveError> {
+        let fields = self.fields();
+        let index = fields
+            .iter()
+            .position(|f| f.name == name)
+            .ok_or_else(|| EnclaveError::InvalidInput(format!("Unknown evidence field: {}", name)))?;
+        let leaves = self.leaves(&fields)?;
+        let root = merkle::root(&leaves)
+            .ok_or_else(|| EnclaveError::InternalError("Empty evidence commitment".to_string()))?;
+        Ok(FieldOpening {
+            evidence_hash: hex::encode(root),
+            name: name.to_string(),
+            value: fields[index].value.clone(),
+            salt: hex::encode(self.leaf_salt(name)?),
+            index,
+            leaf_count: leaves.len(),
+            proof: merkle::proof(&leaves, index).iter().map(hex::encode).collect(),
+        })
+    }
+}
+
+/// Latest verified evidence for a wallet and DID type, consulted before issuing.
+#[derive(Debug, Clone)]
+pub struct IssuedEvidence {
+    pub evidence_hash: String,
+    pub document_class: DocumentClass,
+    pub issuer_signed: bool,
+    pub nullifier: Option<String>,
+    pub timestamp_ms: u64,
+}
+
+/// Retains openings (including salts) in memory so their wallets can open
+/// commitments, and indexes verified evidence by wallet and DID type for the
+/// processor. Only salt-free summaries are written to the audit file.
+pub struct EvidenceStore {
+    capacity: usize,
+    audit_file: Option<String>,
+    inner: Mutex<EvidenceState>,
+}
+
+#[derive(Default)]
+struct EvidenceState {
+    openings: HashMap<String, EvidenceOpening>,
+    order: VecDeque<String>,
+    /// (wallet, did_type) -> evidence hash of the latest verified submission
+    verified: HashMap<(String, u8), String>,
+}
+
+impl EvidenceStore {
+    /// Load from `EVIDENCE_CAPACITY` (openings kept in memory) and
+    /// `EVIDENCE_AUDIT_FILE` (optional JSON-lines file of [`AuditEntry`] records).
+    pub fn from_env() -> Self {
+        let audit_file = std::env::var("EVIDENCE_AUDIT_FILE").ok().filter(|p| !p.is_empty());
+        if let Some(path) = &audit_file {
+            info!("Evidence audit entries appended to {}", path);
+        }
+        Self {
+            capacity: std::env::var("EVIDENCE_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(100_000),
+            audit_file,
+            inner: Mutex::new(EvidenceState::default()),
+        }
+    }
+
+    /// Keep the opening under its evidence hash; `verified` openings also become
+    /// the evidence on file for their wallet and DID type.
+    pub fn record(&self, evidence_hash: &str, opening: EvidenceOpening, verified: bool) {
+        if let Some(path) = &self.audit_file {
+            if let Err(e) = append_line(path, &AuditEntry::new(evidence_hash, &opening, verified)) {
+                warn!("Failed to append evidence audit entry to {}: {}", path, e);
+            }
+        }
+
+        let mut state = self.inner.lock().expect("evidence store poisoned");
+        while state.order.len() >= self.capacity {
+            match state.order.pop_front() {
+                Some(old) => {
+                    if let Some(evicted) = state.openings.remove(&old) {
+                        let key = (evicted.wallet_address, evicted.did_type);
+                        if state.verified.get(&key) == Some(&old) {
+                            state.verified.remove(&key);
+                        }
+                    }
+                }
+                None => break,
+            }
+        }
+        if verified {
+            state.verified.insert(
+                (opening.wallet_address.clone(), opening.did_type),
+                evidence_hash.to_string(),
+            );
+        }
+        if state.openings.insert(evidence_hash.to_string(), opening).is_none() {
+            state.order.push_back(evidence_hash.to_string());
+        }
+    }
+
+    /// Opening for a previously issued evidence hash, if still retained.
+    pub fn opening(&self, evidence_hash: &str) -> Option<EvidenceOpening> {
+        let state = self.inner.lock().expect("evidence store poisoned");
+        state.openings.get(evidence_hash).cloned()
+    }
+
+    /// Selective openings of the named fields of a retained commitment, for the
+    /// wallet it was issued to only. The caller authenticates `wallet_address`.
+    pub fn open_fields(
+        &self,
+        evidence_hash: &str,
+        wallet_address: &str,
+        names: &[String],
+    ) -> Result<Vec<FieldOpening>, EnclaveError> {
+        let opening = self
+            .opening(evidence_hash)
+            .filter(|opening| opening.wallet_address == wallet_address)
+            .ok_or_else(|| {
+                EnclaveError::InvalidInput(format!(
+                    "No opening retained for evidence {} of this wallet",
+                    evidence_hash
+                ))
+            })?;
+        names.iter().map(|name| opening.open_field(name)).collect()
+    }
+
+    /// Latest verified evidence for a normalized wallet address and DID type.
+    pub fn issued(&self, wallet_address: &str, did_type: u8) -> Option<IssuedEvidence> {
+        let state = self.inner.lock().expect("evidence store poisoned");
+        let evidence_hash = state.verified.get(&(wallet_address.to_string(), did_type))?;
+        let opening = state.openings.get(evidence_hash)?;
+        Some(IssuedEvidence {
+            evidence_hash: evidence_hash.clone(),
+            document_class: opening.document_class,
+            issuer_signed: opening.issuer_signed,
+            nullifier: opening.nullifier.clone(),
+            timestamp_ms: opening.timestamp_ms,
+        })
+    }
+}
+
+/// What the audit file records about a commitment: enough to account for
+/// every issued hash, without salts or anything that opens a leaf.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub evidence_hash: String,
+    pub wallet_address: String,
+    pub did_type: u8,
+    pub document_class: DocumentClass,
+    pub verified: bool,
+    pub timestamp_ms: u64,
+}
+
+impl AuditEntry {
+    fn new(evidence_hash: &str, opening: &EvidenceOpening, verified: bool) -> Self {
+        Self {
+            evidence_hash: evidence_hash.to_string(),
+            wallet_address: opening.wallet_address.clone(),
+            did_type: opening.did_type,
+            document_class: opening.document_class,
+            verified,
+            timestamp_ms: opening.timestamp_ms,
+        }
+    }
+}
+
+fn append_line(path: &str, entry: &AuditEntry) -> std::io::Result<()> {
+    let line = serde_json::to_string(entry)?;
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::age::DateOfBirth;
+    use crate::documents::DocumentType;
+
+    const WALLET: &str = "0x00000000000000000000000000000000000000000000000000000000000000aa";
+    const OTHER_WALLET: &str = "0x00000000000000000000000000000000000000000000000000000000000000bb";
+
+    fn record() -> IdentityRecord {
+        IdentityRecord {
+            document_type: DocumentType::PassportChip,
+            document_class: DocumentClass::IndianPassport,
+            reference_id: "IND-4567".to_string(),
+            name: "PRIYA SHARMA".to_string(),
+            date_of_birth: Some(DateOfBirth::new("1990-01-15")),
+            gender: "F".to_string(),
+            nationality: Some("IND".to_string()),
+            document_number: Some("Z1234567".to_string()),
+            expiry_date: Some("2032-06-01".to_string()),
+            issuer_signed: true,
+            photo: vec![1, 2, 3],
+            source_digest: "ab".repeat(32),
+        }
+    }
+
+    fn opening() -> EvidenceOpening {
+        let age = AgePredicate { satisfied: true, threshold_years: 18, reference_date: "2026-01-01".to_string() };
+        let record = record();
+        EvidenceOpening::new(
+            WALLET,
+            b"document",
+            Some(&record),
+            record.document_class,
+            Some("cd".repeat(32)),
+            Some(&age),
+            &[b"face".as_slice()],
+            DID_AGE_VERIFY,
+            1_767_225_600_000,
+            "nonce-0000000001",
+        )
+    }
+
+    #[test]
+    fn commits_to_predicate_and_digests_only() {
+        let opening = opening();
+        let values: Vec<String> = opening.fields().into_iter().map(|f| f.value).collect();
+        for pii in ["PRIYA", "SHARMA", "1990", "Z1234567", "IND-4567"] {
+            assert!(values.iter().all(|v| !v.contains(pii)), "{} is committed", pii);
+        }
+        let field = |name: &str| opening.open_field(name).unwrap().value;
+        assert_eq!(field("age_satisfied"), "true");
+        assert_eq!(field("age_threshold_years"), "18");
+        assert_eq!(field("record_digest"), "ab".repeat(32));
+    }
+
+    #[test]
+    fn field_openings_verify_against_the_commitment() {
+        let opening = opening();
+        let evidence_hash = opening.evidence_hash().unwrap();
+        for field in opening.fields() {
+            let opened = opening.open_field(&field.name).unwrap();
+            assert_eq!(opened.evidence_hash, evidence_hash);
+            assert!(opened.verify());
+            assert!(!FieldOpening { value: format!("{}x", opened.value), ..opened }.verify());
+        }
+        assert!(opening.open_field("record.name").is_err());
+    }
+
+    #[test]
+    fn only_the_owning_wallet_opens_fields() {
+        let store = EvidenceStore { capacity: 10, audit_file: None, inner: Mutex::new(EvidenceState::default()) };
+        let opening = opening();
+        let evidence_hash = opening.evidence_hash().unwrap();
+        store.record(&evidence_hash, opening, true);
+
+        let fields = vec!["age_satisfied".to_string()];
+        assert_eq!(store.open_fields(&evidence_hash, WALLET, &fields).unwrap().len(), 1);
+        assert!(store.open_fields(&evidence_hash, OTHER_WALLET, &fields).is_err());
+        assert!(store.issued(WALLET, DID_AGE_VERIFY).unwrap().issuer_signed);
+    }
+
+    #[test]
+    fn audit_file_has_no_salt_or_identity_data() {
+        let path = std::env::temp_dir().join(format!("evidence-audit-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let store = EvidenceStore {
+            capacity: 10,
+            audit_file: Some(path.to_string_lossy().into_owned()),
+            inner: Mutex::new(EvidenceState::default()),
+        };
+        let opening = opening();
+        let salt = opening.salt.clone();
+        let evidence_hash = opening.evidence_hash().unwrap();
+        store.record(&evidence_hash, opening, true);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let entry: AuditEntry = serde_json::from_str(contents.trim()).unwrap();
+        assert_eq!(entry.evidence_hash, evidence_hash);
+        assert!(entry.verified);
+        for secret in [salt.as_str(), "salt", "PRIYA", "Z1234567", "1990"] {
+            assert!(!contents.contains(secret), "{} written to the audit file", secret);
+        }
+    }
+}
@@ -0,0 +1,440 @@
+// health.rs
+use crate::supervisor::{SubsystemState, SubsystemStatus};
+use crate::AppState;
+use anyhow::{anyhow, Result};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use fastcrypto::encoding::{Encoding, Hex};
+use fastcrypto::traits::{KeyPair as FcKeyPair, ToFromBytes};
+use futures::future::join_all;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Sui host proxy forwarded into the enclave by run.sh.
+const SUI_PROXY_URL: &str = "http://localhost:9999";
+
+/// Name under which main.rs registers the Redis processor with the supervisor.
+const PROCESSOR_SUBSYSTEM: &str = "redis_processor";
+
+/// How a single allowed endpoint is probed.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EndpointProbe {
+    /// Path appended to `https://{host}`, e.g. `/ping`.
+    #[serde(default = "default_probe_path")]
+    pub path: String,
+    /// If set, the response body must contain this string (case-insensitive);
+    /// otherwise any 2xx status counts as reachable.
+    #[serde(default)]
+    pub expect_body: Option<String>,
+}
+
+fn default_probe_path() -> String {
+    "/".to_string()
+}
+
+impl EndpointProbe {
+    /// Probe used when `probes` has no entry for `host`: AWS service endpoints
+    /// answer `/ping` with a body containing "healthy", anything else just
+    /// needs a 2xx on `/`.
+    pub fn default_for(host: &str) -> Self {
+        if host.contains(".amazonaws.com") {
+            Self {
+                path: "/ping".to_string(),
+                expect_body: Some("healthy".to_string()),
+            }
+        } else {
+            Self {
+                path: default_probe_path(),
+                expect_body: None,
+            }
+        }
+    }
+}
+
+/// Layout of `allowed_endpoints.yaml`. `endpoints` stays a plain host list because
+/// configure_enclave.sh feeds it to vsock-proxy; probe overrides live under `probes`.
+#[derive(Debug, Default, Deserialize)]
+struct EndpointsFile {
+    #[serde(default)]
+    endpoints: Vec<String>,
+    #[serde(default)]
+    probes: HashMap<String, EndpointProbe>,
+}
+
+/// Prober configuration, read once at startup.
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    pub endpoints: BTreeMap<String, EndpointProbe>,
+    pub interval: Duration,
+    pub timeout: Duration,
+    pub redis_url: String,
+    /// Gas balance (in MIST) below which the `gas` check fails.
+    pub min_gas_mist: u64,
+    /// Dependency checks that must pass for `/readyz` to report ready.
+    pub required_checks: Vec<String>,
+}
+
+impl HealthConfig {
+    /// Load from `HEALTH_ENDPOINTS_FILE` (default `allowed_endpoints.yaml`),
+    /// `HEALTH_PROBE_INTERVAL_SECS`, `HEALTH_PROBE_TIMEOUT_SECS`,
+    /// `HEALTH_MIN_GAS_MIST` and `HEALTH_REQUIRED_CHECKS` (comma separated).
+    pub fn from_env() -> Self {
+        let env_u64 = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+
+        let path = std::env::var("HEALTH_ENDPOINTS_FILE")
+            .unwrap_or_else(|_| "allowed_endpoints.yaml".to_string());
+        let file = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_yaml::from_str::<EndpointsFile>(&content).unwrap_or_else(|e| {
+                warn!("Failed to parse {}: {}", path, e);
+                EndpointsFile::default()
+            }),
+            Err(e) => {
+                info!("No endpoints file at {}: {}", path, e);
+                EndpointsFile::default()
+            }
+        };
+
+        let mut probes = file.probes;
+        let endpoints = file
+            .endpoints
+            .into_iter()
+            .map(|host| {
+                let probe = probes.remove(&host).unwrap_or_else(|| EndpointProbe::default_for(&host));
+                (host, probe)
+            })
+            .collect();
+
+        Self {
+            endpoints,
+            interval: Duration::from_secs(env_u64("HEALTH_PROBE_INTERVAL_SECS").unwrap_or(30)),
+            timeout: Duration::from_secs(env_u64("HEALTH_PROBE_TIMEOUT_SECS").unwrap_or(5)),
+            redis_url: std::env::var("REDIS_URL")
+                .unwrap_or_else(|_| "redis://localhost:6379".to_string()),
+            min_gas_mist: env_u64("HEALTH_MIN_GAS_MIST").unwrap_or(0),
+            required_checks: std::env::var("HEALTH_REQUIRED_CHECKS")
+                .unwrap_or_else(|_| "redis,sui_proxy".to_string())
+                .split(',')
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect(),
+        }
+    }
+}
+
+/// Outcome of one probe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResult {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    pub latency_ms: u64,
+    pub checked_at_ms: u64,
+}
+
+impl CheckResult {
+    fn new(started: Instant, outcome: Result<Option<String>>) -> Self {
+        let (ok, detail) = match outcome {
+            Ok(detail) => (true, detail),
+            Err(e) => (false, Some(e.to_string())),
+        };
+        Self {
+            ok,
+            detail,
+            latency_ms: started.elapsed().as_millis() as u64,
+            checked_at_ms: now_ms(),
+        }
+    }
+}
+
+/// Last completed probe round.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HealthSnapshot {
+    pub endpoints: BTreeMap<String, CheckResult>,
+    pub dependencies: BTreeMap<String, CheckResult>,
+    pub last_probe_ms: Option<u64>,
+}
+
+/// Probes endpoints and dependencies in the background and caches the results,
+/// so health requests never block on the network.
+pub struct HealthProber {
+    config: HealthConfig,
+    client: Client,
+    snapshot: RwLock<HealthSnapshot>,
+}
+
+impl HealthProber {
+    pub fn new(config: HealthConfig) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
+
+        Ok(Self {
+            config,
+            client,
+            snapshot: RwLock::new(HealthSnapshot::default()),
+        })
+    }
+
+    pub fn config(&self) -> &HealthConfig {
+        &self.config
+    }
+
+    pub fn snapshot(&self) -> HealthSnapshot {
+        self.snapshot.read().expect("health snapshot poisoned").clone()
+    }
+
+    /// Probe loop; run under the supervisor.
+    pub async fn run(self: Arc<Self>) -> Result<()> {
+        info!(
+            "Health prober started: {} endpoints every {:?}",
+            self.config.endpoints.len(),
+            self.config.interval
+        );
+        let mut ticker = tokio::time::interval(self.config.interval);
+        loop {
+            ticker.tick().await;
+            self.probe_once().await;
+        }
+    }
+
+    /// Run every probe concurrently and replace the cached snapshot.
+    pub async fn probe_once(&self) {
+        let endpoint_checks = join_all(self.config.endpoints.iter().map(|(host, probe)| async move {
+            let started = Instant::now();
+            (host.clone(), CheckResult::new(started, self.probe_endpoint(host, probe).await))
+        }));
+
+        let (endpoint_results, redis, proxy, gas) = tokio::join!(
+            endpoint_checks,
+            self.timed(self.probe_redis()),
+            self.timed(self.probe_sui_proxy()),
+            self.timed(self.probe_gas()),
+        );
+
+        let mut dependencies = BTreeMap::new();
+        dependencies.insert("redis".to_string(), redis);
+        dependencies.insert("sui_proxy".to_string(), proxy);
+        dependencies.insert("gas".to_string(), gas);
+
+        for (host, result) in endpoint_results.iter() {
+            if !result.ok {
+                info!("Endpoint {} unreachable: {:?}", host, result.detail);
+            }
+        }
+
+        let mut snapshot = self.snapshot.write().expect("health snapshot poisoned");
+        *snapshot = HealthSnapshot {
+            endpoints: endpoint_results.into_iter().collect(),
+            dependencies,
+            last_probe_ms: Some(now_ms()),
+        };
+    }
+
+    async fn timed<F>(&self, probe: F) -> CheckResult
+    where
+        F: std::future::Future<Output = Result<Option<String>>>,
+    {
+        let started = Instant::now();
+        let outcome = match tokio::time::timeout(self.config.timeout, probe).await {
+            Ok(outcome) => outcome,
+            Err(_) => Err(anyhow!("timed out after {:?}", self.config.timeout)),
+        };
+        CheckResult::new(started, outcome)
+    }
+
+    async fn probe_endpoint(&self, host: &str, probe: &EndpointProbe) -> Result<Option<String>> {
+        let url = format!("https://{}{}", host, probe.path);
+        let response = self.client.get(&url).send().await?;
+        let status = response.status();
+
+        match &probe.expect_body {
+            Some(expected) => {
+                let body = response.text().await?;
+                if body.to_lowercase().contains(&expected.to_lowercase()) {
+                    Ok(None)
+                } else {
+                    Err(anyhow!("response from {} did not contain '{}'", url, expected))
+                }
+            }
+            None if status.is_success() => Ok(None),
+            None => Err(anyhow!("{} returned {}", url, status)),
+        }
+    }
+
+    async fn probe_redis(&self) -> Result<Option<String>> {
+        let client = redis::Client::open(self.config.redis_url.as_str())?;
+        let mut con = client.get_multiplexed_tokio_connection().await?;
+        let pong: String = redis::cmd("PING").query_async(&mut con).await?;
+        if pong == "PONG" {
+            Ok(None)
+        } else {
+            Err(anyhow!("unexpected PING response: {}", pong))
+        }
+    }
+
+    async fn probe_sui_proxy(&self) -> Result<Option<String>> {
+        let response = self
+            .client
+            .get(format!("{}/health", SUI_PROXY_URL))
+            .send()
+            .await?;
+        if response.status().is_success() {
+            Ok(None)
+        } else {
+            Err(anyhow!("Sui proxy returned {}", response.status()))
+        }
+    }
+
+    async fn probe_gas(&self) -> Result<Option<String>> {
+        let result: serde_json::Value = self
+            .client
+            .get(format!("{}/sui/client/gas", SUI_PROXY_URL))
+            .send()
+            .await?
+            .json()
+            .await?;
+
+        if !result["success"].as_bool().unwrap_or(false) {
+            let error = result["stderr"].as_str().unwrap_or("unknown error");
+            return Err(anyhow!("Sui client gas error: {}", error));
+        }
+
+        let balance = parse_gas_balance(result["stdout"].as_str().unwrap_or(""));
+        match balance {
+            Some(balance) if balance < self.config.min_gas_mist => Err(anyhow!(
+                "gas balance {} MIST below minimum {}",
+                balance,
+                self.config.min_gas_mist
+            )),
+            Some(balance) => Ok(Some(format!("{} MIST", balance))),
+            None if self.config.min_gas_mist > 0 => Err(anyhow!("could not read gas balance")),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Sum the MIST balance column of `sui client gas` table output
+/// (`│ <gasCoinId> │ <mistBalance> │ <suiBalance> │`).
+fn parse_gas_balance(output: &str) -> Option<u64> {
+    let balances: Vec<u64> = output
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split('│').map(str::trim).filter(|c| !c.is_empty());
+            let coin = columns.next()?;
+            if !coin.starts_with("0x") {
+                return None;
+            }
+            columns.next()?.parse::<u64>().ok()
+        })
+        .collect();
+
+    if balances.is_empty() {
+        None
+    } else {
+        Some(balances.iter().sum())
+    }
+}
+
+/// Readiness report served by `/readyz`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    pub key: KeyStatus,
+    pub checks: BTreeMap<String, CheckResult>,
+    pub subsystems: BTreeMap<String, SubsystemStatus>,
+    pub last_probe_ms: Option<u64>,
+}
+
+/// Enclave signing key. It is generated before the server starts listening,
+/// so readiness reports which key is in use rather than whether one exists.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyStatus {
+    pub public_key: String,
+}
+
+/// Liveness: the process is up and serving requests.
+pub async fn livez() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Readiness: cached dependency probes, key status and subsystem states.
+/// Returns 503 until the first probe round completes or when any required
+/// check or critical subsystem is unhealthy.
+pub async fn readyz(State(state): State<Arc<AppState>>) -> (StatusCode, Json<ReadinessResponse>) {
+    let snapshot = state.health.snapshot();
+    let subsystems = state.subsystems.snapshot();
+    let pk = state.eph_kp.public();
+
+    let mut checks = snapshot.dependencies;
+    if let Some(processor) = subsystems.get(PROCESSOR_SUBSYSTEM) {
+        let ok = matches!(processor.state, SubsystemState::Running { .. });
+        checks.insert(
+            "processor".to_string(),
+            CheckResult {
+                ok,
+                detail: serde_json::to_value(&processor.state)
+                    .ok()
+                    .and_then(|v| v["state"].as_str().map(str::to_string)),
+                latency_ms: 0,
+                checked_at_ms: now_ms(),
+            },
+        );
+    }
+
+    let required_ok = state
+        .health
+        .config()
+        .required_checks
+        .iter()
+        .all(|name| checks.get(name).map(|c| c.ok).unwrap_or(false));
+    let ready = snapshot.last_probe_ms.is_some() && required_ok && state.subsystems.critical_healthy();
+
+    let status = if ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (
+        status,
+        Json(ReadinessResponse {
+            ready,
+            key: KeyStatus {
+                public_key: Hex::encode(pk.as_bytes()),
+            },
+            checks,
+            subsystems,
+            last_probe_ms: snapshot.last_probe_ms,
+        }),
+    )
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aws_endpoints_keep_the_ping_probe_by_default() {
+        let aws = EndpointProbe::default_for("secretsmanager.us-east-1.amazonaws.com");
+        assert_eq!(aws.path, "/ping");
+        assert_eq!(aws.expect_body.as_deref(), Some("healthy"));
+
+        let other = EndpointProbe::default_for("fullnode.testnet.sui.io");
+        assert_eq!(other.path, "/");
+        assert!(other.expect_body.is_none());
+    }
+}
@@ -0,0 +1,243 @@
+// jwks.rs
+use crate::EnclaveError;
+use base64::{engine::general_purpose, Engine as _};
+use rsa::{BigUint, Pkcs1v15Sign, RsaPublicKey};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Where a provider publishes its signing keys.
+#[derive(Debug, Clone)]
+pub enum JwksSource {
+    /// HTTPS `jwks_uri` from the provider's OpenID configuration
+    Url(String),
+    /// Local JWKS file, for offline testing and pinned deployments
+    File(String),
+}
+
+#[derive(Deserialize)]
+struct JwkSet {
+    keys: Vec<Jwk>,
+}
+
+#[derive(Deserialize)]
+struct Jwk {
+    kty: String,
+    kid: Option<String>,
+    alg: Option<String>,
+    n: Option<String>,
+    e: Option<String>,
+}
+
+/// Seconds from environment variable `name`, or `default`.
+pub(crate) fn env_secs(name: &str, default: u64) -> Duration {
+    Duration::from_secs(std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default))
+}
+
+/// HTTP client for upstream calls made while serving a request, so that a
+/// stalled provider fails the request instead of hanging it. Connecting is
+/// bounded by `ZKLOGIN_CONNECT_TIMEOUT_SECS` (default 5).
+pub(crate) fn http_client(timeout: Duration) -> Result<reqwest::Client, EnclaveError> {
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .connect_timeout(env_secs("ZKLOGIN_CONNECT_TIMEOUT_SECS", 5).min(timeout))
+        .build()
+        .map_err(|e| EnclaveError::InternalError(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// JOSE header of a compact JWT.
+#[derive(Debug, Deserialize)]
+pub struct JwtHeader {
+    pub alg: String,
+    pub kid: Option<String>,
+}
+
+struct KeySet {
+    keys: HashMap<String, RsaPublicKey>,
+    fetched_at: Instant,
+}
+
+/// RS256 signing keys per issuer. Sets are refreshed after `ttl`, or early
+/// when a token names an unknown `kid` (the provider rotated its keys), but
+/// not more than once per `min_refresh`.
+pub struct JwksCache {
+    ttl: Duration,
+    min_refresh: Duration,
+    http: reqwest::Client,
+    sets: Mutex<HashMap<String, KeySet>>,
+}
+
+impl JwksCache {
+    /// Load from `ZKLOGIN_JWKS_TTL_SECS` (default 3600),
+    /// `ZKLOGIN_JWKS_MIN_REFRESH_SECS` (default 60) and
+    /// `ZKLOGIN_JWKS_TIMEOUT_SECS` (default 10).
+    pub fn from_env() -> Result<Self, EnclaveError> {
+        let http = http_client(env_secs("ZKLOGIN_JWKS_TIMEOUT_SECS", 10))?;
+        Ok(Self::new(
+            env_secs("ZKLOGIN_JWKS_TTL_SECS", 3600),
+            env_secs("ZKLOGIN_JWKS_MIN_REFRESH_SECS", 60),
+            http,
+        ))
+    }
+
+    pub fn new(ttl: Duration, min_refresh: Duration, http: reqwest::Client) -> Self {
+        Self {
+            ttl,
+            min_refresh,
+            http,
+            sets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Key `kid` of `issuer`, fetching or refreshing its set as needed.
+    pub async fn key(&self, issuer: &str, source: &JwksSource, kid: &str) -> Result<RsaPublicKey, EnclaveError> {
+        let cached = {
+            let sets = self.sets.lock().expect("jwks cache poisoned");
+            sets.get(issuer).map(|set| {
+                let age = set.fetched_at.elapsed();
+                (set.keys.get(kid).cloned(), age)
+            })
+        };
+        let refresh = match &cached {
+            None => true,
+            Some((Some(_), age)) => *age >= self.ttl,
+            Some((None, age)) => *age >= self.min_refresh,
+        };
+        if !refresh {
+            return cached
+                .and_then(|(key, _)| key)
+                .ok_or_else(|| unknown_key(issuer, kid));
+        }
+
+        match self.load(source).await {
+            Ok(keys) => {
+                info!("Loaded {} signing keys for {}", keys.len(), issuer);
+                let key = keys.get(kid).cloned();
+                let mut sets = self.sets.lock().expect("jwks cache poisoned");
+                sets.insert(issuer.to_string(), KeySet { keys, fetched_at: Instant::now() });
+                key.ok_or_else(|| unknown_key(issuer, kid))
+            }
+            // Keep serving a key we already trust while the provider is unreachable
+            Err(e) => match cached.and_then(|(key, _)| key) {
+                Some(key) => {
+                    warn!("Refreshing signing keys for {} failed, using cached set: {}", issuer, e);
+                    Ok(key)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    async fn load(&self, source: &JwksSource) -> Result<HashMap<String, RsaPublicKey>, EnclaveError> {
+        let set: JwkSet = match source {
+            JwksSource::File(path) => {
+                let content = std::fs::read_to_string(path)
+                    .map_err(|e| EnclaveError::InternalError(format!("Cannot read JWKS {}: {}", path, e)))?;
+                serde_json::from_str(&content)
+                    .map_err(|e| EnclaveError::InternalError(format!("Invalid JWKS {}: {}", path, e)))?
+            }
+            JwksSource::Url(url) => {
+                let upstream = |e: String| EnclaveError::UpstreamUnavailable(format!("JWKS {}: {}", url, e));
+                self.http
+                    .get(url)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status())
+                    .map_err(|e| upstream(e.to_string()))?
+                    .json()
+                    .await
+                    .map_err(|e| upstream(e.to_string()))?
+            }
+        };
+
+        // Only RSA keys usable for RS256; others (e.g. EC keys) are skipped
+        Ok(set
+            .keys
+            .into_iter()
+            .filter(|jwk| jwk.kty == "RSA" && jwk.alg.as_deref().is_none_or(|alg| alg == "RS256"))
+            .filter_map(|jwk| {
+                let key = RsaPublicKey::new(base64url_uint(jwk.n.as_deref()?)?, base64url_uint(jwk.e.as_deref()?)?);
+                match key {
+                    Ok(key) => Some((jwk.kid?, key)),
+                    Err(e) => {
+                        warn!("Skipping invalid RSA JWK: {}", e);
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+}
+
+fn base64url_uint(value: &str) -> Option<BigUint> {
+    let bytes = general_purpose::URL_SAFE_NO_PAD.decode(value.trim_end_matches('=')).ok()?;
+    Some(BigUint::from_bytes_be(&bytes))
+}
+
+fn unknown_key(issuer: &str, kid: &str) -> EnclaveError {
+    EnclaveError::Unauthorized(format!("Unknown signing key {} for {}", kid, issuer))
+}
+
+/// Split a compact JWT and decode its header and payload, without checking
+/// the signature.
+pub fn decode(jwt: &str) -> Result<(JwtHeader, Vec<u8>), EnclaveError> {
+    let parts: Vec<&str> = jwt.split('.').collect();
+    let [header, payload, _] = parts.as_slice() else {
+        return Err(EnclaveError::InvalidInput("Invalid JWT format".to_string()));
+    };
+    let decode_part = |part: &str| {
+        general_purpose::URL_SAFE_NO_PAD
+            .decode(part)
+            .map_err(|e| EnclaveError::InvalidInput(format!("JWT decode error: {}", e)))
+    };
+    let header = serde_json::from_slice(&decode_part(header)?)
+        .map_err(|e| EnclaveError::InvalidInput(format!("JWT header parse error: {}", e)))?;
+    Ok((header, decode_part(payload)?))
+}
+
+/// Check the RS256 signature of a compact JWT over `header.payload`.
+pub fn verify_rs256(jwt: &str, key: &RsaPublicKey) -> Result<(), EnclaveError> {
+    let (signing_input, signature) = jwt
+        .rsplit_once('.')
+        .ok_or_else(|| EnclaveError::InvalidInput("Invalid JWT format".to_string()))?;
+    let signature = general_purpose::URL_SAFE_NO_PAD
+        .decode(signature)
+        .map_err(|e| EnclaveError::InvalidInput(format!("JWT signature decode error: {}", e)))?;
+    key.verify(
+        Pkcs1v15Sign::new::<Sha256>(),
+        &Sha256::digest(signing_input.as_bytes()),
+        &signature,
+    )
+    .map_err(|_| EnclaveError::Unauthorized("JWT signature is invalid".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stalled_endpoint_times_out() {
+        // Accepts connections and never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/jwks", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut open = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                open.push(socket);
+            }
+        });
+
+        let cache = JwksCache::new(
+            Duration::from_secs(3600),
+            Duration::ZERO,
+            http_client(Duration::from_millis(200)).unwrap(),
+        );
+        let started = Instant::now();
+        let result = cache.key("https://issuer.test", &JwksSource::Url(url), "kid").await;
+        assert!(matches!(result, Err(EnclaveError::UpstreamUnavailable(_))));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
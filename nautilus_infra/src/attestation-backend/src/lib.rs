@@ -1,36 +1,189 @@
-use axum::http::StatusCode;
+use age::AgePolicy;
+use axum::http::{header, HeaderValue, StatusCode};
 use axum::response::IntoResponse;
 use axum::response::Response;
 use axum::Json;
 use fastcrypto::ed25519::Ed25519KeyPair;
 use serde_json::json;
+use did_nft::DidNftVerifier;
+use documents::DocumentRegistry;
+use evidence::EvidenceStore;
+use health::HealthProber;
+use limits::{RateLimiter, RequestLimits};
+use media::MediaProcessor;
+use nullifier::NullifierStore;
+use replay::NonceStore;
+use std::sync::Arc;
+use supervisor::SubsystemRegistry;
+use tracing::error;
+use verifier::IdentityVerifier;
+use wallet::WalletVerifier;
 
+pub mod aadhaar;
+pub mod age;
 pub mod app;
+pub mod auth;
 pub mod common;
-// pub mod zklogin; // COMMENTED OUT - No longer using zkLogin functionality in this version
+pub mod did_nft;
+pub mod documents;
+pub mod emrtd;
+pub mod evidence;
+pub mod health;
+#[cfg(feature = "zklogin")]
+pub mod jwks;
+pub mod limits;
+pub mod media;
+pub mod merkle;
+pub mod metrics;
+pub mod nullifier;
+pub mod replay;
+pub mod request_id;
+pub mod redact;
+pub mod rules;
+pub mod secrets;
+pub mod sui_rpc;
+pub mod supervisor;
+pub mod verifier;
+pub mod wallet;
+#[cfg(feature = "zklogin")]
+pub mod zklogin;
+#[cfg(feature = "zklogin")]
+pub mod zkprover;
 
 /// App state, at minimum needs to maintain the ephemeral keypair.  
 pub struct AppState {
     /// Ephemeral keypair on boot
     pub eph_kp: Ed25519KeyPair,
+    /// Lifecycle state of supervised subsystems, reported by health checks
+    pub subsystems: SubsystemRegistry,
+    /// Background prober whose cached results back /health and /readyz
+    pub health: Arc<HealthProber>,
+    /// Size limits for KYC submissions
+    pub request_limits: RequestLimits,
+    /// Token buckets per source, API client and wallet
+    pub rate_limiter: RateLimiter,
+    /// Recently seen KYC nonces for replay protection
+    pub nonces: NonceStore,
+    /// Checks that KYC submissions are signed by the wallet they name
+    pub wallet_verifier: WalletVerifier,
+    /// Openings of issued evidence hashes, shared with the Redis processor
+    pub evidence: Arc<EvidenceStore>,
+    /// Face match and liveness engine
+    pub verifier: Box<dyn IdentityVerifier>,
+    /// Image decoding and normalization for submitted media
+    pub media: MediaProcessor,
+    /// Parsers for issuer-signed identity documents
+    pub documents: DocumentRegistry,
+    /// Minimum ages per DID type
+    pub age_policy: AgePolicy,
+    /// Document nullifiers and the wallets they are bound to
+    pub nullifiers: NullifierStore,
+    /// Checks issued DID NFTs against the chain and the registered enclave key
+    pub did_verifier: DidNftVerifier,
+    /// zkLogin salt seeds and provider allowlist
+    #[cfg(feature = "zklogin")]
+    pub zklogin: zklogin::ZkLoginService,
 }
 
-/// Enclave errors enum.
+/// Enclave errors enum. Each variant maps to an HTTP status and a stable
+/// machine-readable `code` that clients can branch on.
 #[derive(Debug)]
 pub enum EnclaveError {
-    GenericError(String),
+    /// Malformed or out-of-policy request data.
+    InvalidInput(String),
+    /// Missing or invalid client credentials.
+    Unauthorized(String),
+    /// Encrypted payload could not be decrypted or decoded.
+    DecryptionFailed(String),
+    /// The submitted evidence did not pass verification.
+    VerificationFailed(String),
+    /// NSM attestation could not be produced.
+    AttestationUnavailable(String),
+    /// A dependency (Sui proxy, Redis, external API) is unreachable or misbehaving.
+    UpstreamUnavailable(String),
+    /// Too many requests; retry after the given number of seconds.
+    RateLimited { message: String, retry_after_secs: u64 },
+    /// Bug or environment failure inside the enclave (clock, serialization, ...).
+    InternalError(String),
 }
 
+impl EnclaveError {
+    /// Stable error code included in the JSON body.
+    pub fn code(&self) -> &'static str {
+        match self {
+            EnclaveError::InvalidInput(_) => "invalid_input",
+            EnclaveError::Unauthorized(_) => "unauthorized",
+            EnclaveError::DecryptionFailed(_) => "decryption_failed",
+            EnclaveError::VerificationFailed(_) => "verification_failed",
+            EnclaveError::AttestationUnavailable(_) => "attestation_unavailable",
+            EnclaveError::UpstreamUnavailable(_) => "upstream_unavailable",
+            EnclaveError::RateLimited { .. } => "rate_limited",
+            EnclaveError::InternalError(_) => "internal_error",
+        }
+    }
+
+    /// HTTP status for this error.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            EnclaveError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            EnclaveError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            EnclaveError::DecryptionFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            EnclaveError::VerificationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            EnclaveError::AttestationUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            EnclaveError::UpstreamUnavailable(_) => StatusCode::BAD_GATEWAY,
+            EnclaveError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            EnclaveError::InternalError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            EnclaveError::InvalidInput(m)
+            | EnclaveError::Unauthorized(m)
+            | EnclaveError::DecryptionFailed(m)
+            | EnclaveError::VerificationFailed(m)
+            | EnclaveError::AttestationUnavailable(m)
+            | EnclaveError::UpstreamUnavailable(m)
+            | EnclaveError::RateLimited { message: m, .. }
+            | EnclaveError::InternalError(m) => m,
+        }
+    }
+}
+
+impl std::fmt::Display for EnclaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code(), self.message())
+    }
+}
+
+impl std::error::Error for EnclaveError {}
+
 /// Implement IntoResponse for EnclaveError.
 impl IntoResponse for EnclaveError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            EnclaveError::GenericError(e) => (StatusCode::BAD_REQUEST, e),
-        };
-        let body = Json(json!({
-            "error": error_message,
-        }));
-        (status, body).into_response()
+        let status = self.status();
+        let request_id = request_id::current();
+        if status.is_server_error() {
+            error!("Request {} failed: {}", request_id.as_deref().unwrap_or("-"), self);
+        }
+
+        let mut body = json!({
+            "error": self.message(),
+            "code": self.code(),
+        });
+        if let Some(request_id) = request_id {
+            body["request_id"] = json!(request_id);
+        }
+        if let EnclaveError::RateLimited { retry_after_secs, .. } = &self {
+            body["retry_after_secs"] = json!(retry_after_secs);
+        }
+
+        let mut response = (status, Json(body)).into_response();
+        if let EnclaveError::RateLimited { retry_after_secs, .. } = self {
+            if let Ok(value) = HeaderValue::from_str(&retry_after_secs.to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+        }
+        response
     }
 }
-
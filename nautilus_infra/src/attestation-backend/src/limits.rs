@@ -0,0 +1,389 @@
+// limits.rs
+use crate::EnclaveError;
+use redis::aio::MultiplexedConnection;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Size limits applied to `/process_kyc` payloads.
+#[derive(Debug, Clone)]
+pub struct RequestLimits {
+    /// Maximum request body size in bytes (enforced before JSON parsing).
+    pub max_body_bytes: usize,
+    /// Maximum number of entries in `encrypted_faces`.
+    pub max_frames: usize,
+    /// Maximum encoded size of the document and of each face frame.
+    pub max_frame_bytes: usize,
+}
+
+impl RequestLimits {
+    /// Load from `KYC_MAX_BODY_BYTES`, `KYC_MAX_FRAMES` and `KYC_MAX_FRAME_BYTES`.
+    pub fn from_env() -> Self {
+        let env_usize = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<usize>().ok());
+        Self {
+            max_body_bytes: env_usize("KYC_MAX_BODY_BYTES").unwrap_or(8 * 1024 * 1024),
+            max_frames: env_usize("KYC_MAX_FRAMES").unwrap_or(10),
+            max_frame_bytes: env_usize("KYC_MAX_FRAME_BYTES").unwrap_or(1024 * 1024),
+        }
+    }
+
+    /// Reject requests with too many or too large frames.
+    pub fn check_frames(&self, doc: &str, faces: &[String]) -> Result<(), EnclaveError> {
+        if faces.len() > self.max_frames {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Too many face frames: {} (max {})",
+                faces.len(),
+                self.max_frames
+            )));
+        }
+        if doc.len() > self.max_frame_bytes {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Document exceeds {} bytes",
+                self.max_frame_bytes
+            )));
+        }
+        if let Some(index) = faces.iter().position(|f| f.len() > self.max_frame_bytes) {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Face frame {} exceeds {} bytes",
+                index, self.max_frame_bytes
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Token bucket parameters: `capacity` requests, refilled at `refill_per_sec`.
+#[derive(Debug, Clone, Copy)]
+pub struct BucketConfig {
+    pub capacity: f64,
+    pub refill_per_sec: f64,
+}
+
+impl BucketConfig {
+    fn from_env(prefix: &str, capacity: f64, per_minute: f64) -> Self {
+        let env_f64 = |name: String| std::env::var(name).ok().and_then(|v| v.parse::<f64>().ok());
+        Self {
+            capacity: env_f64(format!("{}_BURST", prefix)).unwrap_or(capacity),
+            refill_per_sec: env_f64(format!("{}_PER_MINUTE", prefix)).unwrap_or(per_minute) / 60.0,
+        }
+    }
+}
+
+/// What a bucket is keyed on.
+#[derive(Debug, Clone, Copy)]
+pub enum LimitKind {
+    Source,
+    Client,
+    Wallet,
+}
+
+impl LimitKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            LimitKind::Source => "source",
+            LimitKind::Client => "client",
+            LimitKind::Wallet => "wallet",
+        }
+    }
+}
+
+/// Rate limit settings per key kind plus optional shared Redis backend.
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub per_source: BucketConfig,
+    pub per_client: BucketConfig,
+    pub per_wallet: BucketConfig,
+    /// When set, buckets live in Redis so every enclave shares them.
+    pub redis_url: Option<String>,
+}
+
+impl RateLimitConfig {
+    /// Load from `RATE_LIMIT_{SOURCE,CLIENT,WALLET}_{BURST,PER_MINUTE}` and
+    /// `RATE_LIMIT_REDIS_URL`.
+    pub fn from_env() -> Self {
+        Self {
+            per_source: BucketConfig::from_env("RATE_LIMIT_SOURCE", 20.0, 60.0),
+            per_client: BucketConfig::from_env("RATE_LIMIT_CLIENT", 100.0, 600.0),
+            per_wallet: BucketConfig::from_env("RATE_LIMIT_WALLET", 3.0, 6.0),
+            redis_url: std::env::var("RATE_LIMIT_REDIS_URL").ok().filter(|u| !u.is_empty()),
+        }
+    }
+
+    fn bucket(&self, kind: LimitKind) -> BucketConfig {
+        match kind {
+            LimitKind::Source => self.per_source,
+            LimitKind::Client => self.per_client,
+            LimitKind::Wallet => self.per_wallet,
+        }
+    }
+}
+
+/// Atomic token bucket in Redis. Returns {allowed, retry_after_ms}.
+const TOKEN_BUCKET_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local refill_per_ms = tonumber(ARGV[2])
+local now = tonumber(ARGV[3])
+local state = redis.call('HMGET', KEYS[1], 'tokens', 'ts')
+local tokens = tonumber(state[1]) or capacity
+local ts = tonumber(state[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - ts) * refill_per_ms)
+local allowed = 0
+local retry_after = 0
+if tokens >= 1 then
+  tokens = tokens - 1
+  allowed = 1
+else
+  retry_after = math.ceil((1 - tokens) / refill_per_ms)
+end
+redis.call('HSET', KEYS[1], 'tokens', tokens, 'ts', now)
+redis.call('PEXPIRE', KEYS[1], math.ceil(capacity / refill_per_ms) + 1000)
+return {allowed, retry_after}
+"#;
+
+/// Most keys kept in memory before idle buckets are evicted.
+const MAX_MEMORY_BUCKETS: usize = 100_000;
+
+struct Bucket {
+    /// Parameters of the kind the bucket belongs to
+    config: BucketConfig,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn is_full(&self, now: Instant) -> bool {
+        self.tokens + now.duration_since(self.updated).as_secs_f64() * self.config.refill_per_sec
+            >= self.config.capacity
+    }
+}
+
+/// Token-bucket limiter keyed per source IP, API client and wallet address.
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, Bucket>>,
+    max_memory_buckets: usize,
+    redis: Option<(redis::Client, tokio::sync::Mutex<Option<MultiplexedConnection>>)>,
+    script: redis::Script,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        let redis = config.redis_url.as_deref().and_then(|url| match redis::Client::open(url) {
+            Ok(client) => {
+                info!("Rate limits shared through Redis");
+                Some((client, tokio::sync::Mutex::new(None)))
+            }
+            Err(e) => {
+                warn!("Invalid RATE_LIMIT_REDIS_URL, using in-memory limits: {}", e);
+                None
+            }
+        });
+
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+            max_memory_buckets: MAX_MEMORY_BUCKETS,
+            redis,
+            script: redis::Script::new(TOKEN_BUCKET_SCRIPT),
+        }
+    }
+
+    /// Take one token for `key`, or fail with a rate-limit error carrying retry-after.
+    pub async fn check(&self, kind: LimitKind, key: &str) -> Result<(), EnclaveError> {
+        let bucket = self.config.bucket(kind);
+        if bucket.refill_per_sec <= 0.0 {
+            return Ok(());
+        }
+        let key = format!("ratelimit:{}:{}", kind.as_str(), key);
+
+        let retry_after = match self.check_redis(&key, bucket).await {
+            Some(retry_after) => retry_after,
+            None => self.check_memory(&key, bucket),
+        };
+
+        match retry_after {
+            None => Ok(()),
+            Some(wait) => Err(EnclaveError::RateLimited {
+                message: format!("Rate limit exceeded for {}", kind.as_str()),
+                retry_after_secs: wait.as_secs_f64().ceil().max(1.0) as u64,
+            }),
+        }
+    }
+
+    /// Shared bucket in Redis. `None` means Redis is not configured or failed,
+    /// in which case the caller falls back to the local bucket.
+    async fn check_redis(&self, key: &str, bucket: BucketConfig) -> Option<Option<Duration>> {
+        let (client, connection) = self.redis.as_ref()?;
+        let mut guard = connection.lock().await;
+        if guard.is_none() {
+            match client.get_multiplexed_tokio_connection().await {
+                Ok(con) => *guard = Some(con),
+                Err(e) => {
+                    warn!("Rate limit Redis unavailable, using local buckets: {}", e);
+                    return None;
+                }
+            }
+        }
+        let con = guard.as_mut()?;
+
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        let result: redis::RedisResult<(i64, i64)> = self
+            .script
+            .key(key)
+            .arg(bucket.capacity)
+            .arg(bucket.refill_per_sec / 1000.0)
+            .arg(now_ms)
+            .invoke_async(con)
+            .await;
+
+        match result {
+            Ok((1, _)) => Some(None),
+            Ok((_, retry_ms)) => Some(Some(Duration::from_millis(retry_ms.max(0) as u64))),
+            Err(e) => {
+                warn!("Rate limit Redis script failed, using local buckets: {}", e);
+                *guard = None;
+                None
+            }
+        }
+    }
+
+    fn check_memory(&self, key: &str, bucket: BucketConfig) -> Option<Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("rate limit buckets poisoned");
+
+        if buckets.len() >= self.max_memory_buckets && !buckets.contains_key(key) {
+            // Drop buckets that have refilled completely; they carry no state
+            buckets.retain(|_, b| !b.is_full(now));
+            // Still full (keys rotated faster than they refill): drop the least
+            // recently used tenth, so the scan runs once per many new keys
+            if buckets.len() >= self.max_memory_buckets {
+                let mut by_age: Vec<(Instant, String)> =
+                    buckets.iter().map(|(k, b)| (b.updated, k.clone())).collect();
+                let evict = (self.max_memory_buckets / 10).max(1).min(by_age.len());
+                by_age.select_nth_unstable_by_key(evict - 1, |(updated, _)| *updated);
+                for (_, k) in &by_age[..evict] {
+                    buckets.remove(k);
+                }
+            }
+        }
+
+        let entry = buckets.entry(key.to_string()).or_insert(Bucket {
+            config: bucket,
+            tokens: bucket.capacity,
+            updated: now,
+        });
+        let elapsed = now.duration_since(entry.updated).as_secs_f64();
+        entry.tokens = (entry.tokens + elapsed * bucket.refill_per_sec).min(bucket.capacity);
+        entry.updated = now;
+
+        if entry.tokens >= 1.0 {
+            entry.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64((1.0 - entry.tokens) / bucket.refill_per_sec))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+
+    fn bucket(capacity: f64, per_minute: f64) -> BucketConfig {
+        BucketConfig { capacity, refill_per_sec: per_minute / 60.0 }
+    }
+
+    fn limiter(wallet: BucketConfig, redis_url: Option<&str>) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            per_source: bucket(20.0, 60.0),
+            per_client: bucket(100.0, 600.0),
+            per_wallet: wallet,
+            redis_url: redis_url.map(str::to_string),
+        })
+    }
+
+    fn retry_after(result: Result<(), EnclaveError>) -> u64 {
+        match result {
+            Err(EnclaveError::RateLimited { retry_after_secs, .. }) => retry_after_secs,
+            other => panic!("expected a rate limit, got {:?}", other.map_err(|e| e.to_string())),
+        }
+    }
+
+    #[tokio::test]
+    async fn bucket_refills_and_reports_retry_after() {
+        let slow = limiter(bucket(2.0, 6.0), None);
+        slow.check(LimitKind::Wallet, "0xa").await.unwrap();
+        slow.check(LimitKind::Wallet, "0xa").await.unwrap();
+        let error = slow.check(LimitKind::Wallet, "0xa").await.unwrap_err();
+        let response = error.into_response();
+        assert_eq!(response.status(), axum::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()["retry-after"], "10");
+        // Other keys and kinds have their own buckets
+        slow.check(LimitKind::Wallet, "0xb").await.unwrap();
+        slow.check(LimitKind::Source, "0xa").await.unwrap();
+
+        let fast = limiter(bucket(1.0, 6000.0), None);
+        fast.check(LimitKind::Wallet, "0xa").await.unwrap();
+        assert_eq!(retry_after(fast.check(LimitKind::Wallet, "0xa").await), 1);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        fast.check(LimitKind::Wallet, "0xa").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn memory_buckets_stay_bounded_under_rotating_keys() {
+        let mut limiter = limiter(bucket(3.0, 6.0), None);
+        limiter.max_memory_buckets = 50;
+        for i in 0..1000 {
+            limiter.check(LimitKind::Wallet, &format!("0x{:x}", i)).await.unwrap();
+            assert!(limiter.buckets.lock().unwrap().len() <= 50);
+        }
+        // The most recent keys survive eviction with their state
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets["ratelimit:wallet:0x3e7"].tokens < 3.0);
+        assert!(!buckets.contains_key("ratelimit:wallet:0x0"));
+    }
+
+    #[tokio::test]
+    async fn eviction_uses_each_buckets_own_config() {
+        let mut limiter = limiter(bucket(3.0, 6.0), None);
+        limiter.max_memory_buckets = 2;
+        // Source buckets refill within a second; wallet ones take 20 seconds
+        limiter.check(LimitKind::Wallet, "0xa").await.unwrap();
+        limiter.check(LimitKind::Source, "10.0.0.1").await.unwrap();
+        limiter.buckets.lock().unwrap().get_mut("ratelimit:source:10.0.0.1").unwrap().updated -=
+            Duration::from_secs(2);
+        limiter.check(LimitKind::Client, "partner").await.unwrap();
+
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets.contains_key("ratelimit:wallet:0xa"));
+        assert!(!buckets.contains_key("ratelimit:source:10.0.0.1"));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_memory_when_redis_is_unavailable() {
+        for url in ["redis://127.0.0.1:1", "not a redis url"] {
+            let limiter = limiter(bucket(1.0, 6.0), Some(url));
+            limiter.check(LimitKind::Wallet, "0xa").await.unwrap();
+            assert_eq!(retry_after(limiter.check(LimitKind::Wallet, "0xa").await), 10);
+        }
+    }
+
+    #[test]
+    fn frames_are_limited_by_count_and_size() {
+        let limits = RequestLimits { max_body_bytes: 1024, max_frames: 2, max_frame_bytes: 4 };
+        let frame = |s: &str| s.to_string();
+        limits.check_frames("doc", &[frame("ab"), frame("abcd")]).unwrap();
+
+        let too_many = limits.check_frames("doc", &[frame("a"), frame("b"), frame("c")]).unwrap_err();
+        assert!(too_many.message().contains("Too many face frames: 3"));
+        let large_doc = limits.check_frames("docs!", &[]).unwrap_err();
+        assert!(large_doc.message().contains("Document exceeds 4 bytes"));
+        let large_face = limits.check_frames("doc", &[frame("a"), frame("abcde")]).unwrap_err();
+        assert!(large_face.message().contains("Face frame 1 exceeds 4 bytes"));
+    }
+}
@@ -1,14 +1,37 @@
 // main.rs
 use anyhow::Result;
-use axum::{routing::get, routing::post, Router};
+use axum::extract::DefaultBodyLimit;
+use axum::{middleware, routing::get, routing::post, Router};
 use fastcrypto::{ed25519::Ed25519KeyPair, traits::{KeyPair, ToFromBytes}};
+use attestation_server::age::AgePolicy;
+use attestation_server::auth::{cors_layer, with_auth, AuthConfig, AuthRequirement, Authenticator};
 use attestation_server::common::{get_attestation, health_check};
-use attestation_server::app::{process_kyc};
-// use attestation_server::zklogin::{get_salt, get_zk_proof}; // COMMENTED OUT - No longer using zkLogin
+use attestation_server::did_nft::{verify_did_nfts, DidNftVerifier};
+use attestation_server::app::{open_evidence, process_kyc, revoke_binding};
+#[cfg(feature = "zklogin")]
+use attestation_server::zklogin::{get_salt, get_zk_proof, ZkLoginService};
+use attestation_server::documents::DocumentRegistry;
+use attestation_server::evidence::EvidenceStore;
+use attestation_server::health::{livez, readyz, HealthConfig, HealthProber};
+use attestation_server::limits::{RateLimitConfig, RateLimiter, RequestLimits};
+use attestation_server::media::{MediaLimits, MediaProcessor};
+use attestation_server::metrics::metrics_handler;
+use attestation_server::nullifier::NullifierStore;
+use attestation_server::redact;
+use attestation_server::replay::{NonceStore, ReplayConfig};
+use attestation_server::request_id::request_id_middleware;
+use attestation_server::secrets::SecretStore;
+use attestation_server::sui_rpc::EpochCache;
+use attestation_server::supervisor::{BackoffPolicy, Criticality, Supervisor, SubsystemRegistry};
+use attestation_server::verifier::verifier_from_env;
+use attestation_server::wallet::{WalletVerifier, ZkLoginConfig};
 use attestation_server::AppState;
+use std::net::SocketAddr;
 use std::sync::Arc;
-// CORS imports moved to function scope
-use tracing::{info, error};
+use tracing::info;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+use zeroize::Zeroizing;
 
 mod redis_sui_processor;
 use redis_sui_processor::start_redis_sui_processor;
@@ -26,8 +49,11 @@ async fn main() -> Result<()> {
         info!("No local .env file found, using system environment variables");
     }
     
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    // Initialize tracing; wallet addresses, hashes and PII fields are masked
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(redact::layer())
+        .init();
     
     // Debug: Log key environment variables (without sensitive data)
     info!("Environment variables loaded (.env files only, no secrets.json):");
@@ -51,9 +77,10 @@ async fn main() -> Result<()> {
             match driver::nsm_process_request(fd, request) {
                 Response::GetRandom { random } => {
                     driver::nsm_exit(fd);
-                    let seed: [u8; 32] = random[..32].try_into().expect("Invalid entropy length");
+                    let random = Zeroizing::new(random);
+                    let seed = Zeroizing::new(<[u8; 32]>::try_from(&random[..32]).expect("Invalid entropy length"));
                     use rand::SeedableRng;
-                    let mut rng = rand::rngs::StdRng::from_seed(seed);
+                    let mut rng = rand::rngs::StdRng::from_seed(*seed);
                     Ed25519KeyPair::generate(&mut rng)
                 }
                 _ => {
@@ -73,60 +100,88 @@ async fn main() -> Result<()> {
         Ed25519KeyPair::generate(&mut rand::thread_rng())
     };
 
-    // Clone the keypair for the Redis processor
-    let redis_keypair = Ed25519KeyPair::from_bytes(eph_kp.as_bytes())?;
-    let state = Arc::new(AppState { eph_kp });
+    // Keep the raw keypair bytes so the Redis processor can rebuild its keypair on restart
+    let redis_keypair_bytes = Zeroizing::new(eph_kp.as_bytes().to_vec());
+    let subsystems = SubsystemRegistry::new();
+    let health = Arc::new(HealthProber::new(HealthConfig::from_env())?);
+    let evidence = Arc::new(EvidenceStore::from_env());
+    let secrets = SecretStore::from_env();
+    let state = Arc::new(AppState {
+        eph_kp,
+        subsystems: subsystems.clone(),
+        health: health.clone(),
+        request_limits: RequestLimits::from_env(),
+        rate_limiter: RateLimiter::new(RateLimitConfig::from_env()),
+        nonces: NonceStore::new(ReplayConfig::from_env()),
+        wallet_verifier: WalletVerifier::new(ZkLoginConfig::from_env(), EpochCache::from_env()),
+        evidence: evidence.clone(),
+        verifier: verifier_from_env(),
+        media: MediaProcessor::new(MediaLimits::from_env()),
+        documents: DocumentRegistry::from_env(),
+        age_policy: AgePolicy::from_env(),
+        nullifiers: NullifierStore::from_env(&secrets)?,
+        did_verifier: DidNftVerifier::from_env(),
+        #[cfg(feature = "zklogin")]
+        zklogin: ZkLoginService::from_env(&secrets)?,
+    });
 
     info!("Starting attestation server with API and Redis processor");
 
-    // Start both API server and Redis processor concurrently
-    let api_handle = tokio::spawn(run_api_server(state));
-    let redis_handle = tokio::spawn(start_redis_sui_processor(redis_keypair));
+    // The API must stay up; the processor is restarted with backoff while it
+    // waits for Redis or the Sui proxy to become available.
+    let mut supervisor = Supervisor::new(subsystems);
+    let policy = BackoffPolicy::from_env();
 
-    // Wait for either to complete (or fail)
-    tokio::select! {
-        result = api_handle => {
-            match result {
-                Ok(Ok(())) => info!("API server completed successfully"),
-                Ok(Err(e)) => error!("API server failed: {}", e),
-                Err(e) => error!("API server task panicked: {}", e),
-            }
-        }
-        result = redis_handle => {
-            match result {
-                Ok(Ok(())) => info!("Redis processor completed successfully"),
-                Ok(Err(e)) => error!("Redis processor failed: {}", e),
-                Err(e) => error!("Redis processor task panicked: {}", e),
-            }
+    supervisor.spawn("api_server", Criticality::Critical, policy.clone(), move || {
+        run_api_server(state.clone())
+    });
+    supervisor.spawn("health_prober", Criticality::Optional, policy.clone(), move || {
+        health.clone().run()
+    });
+    supervisor.spawn("redis_processor", Criticality::Optional, policy, move || {
+        let keypair_bytes = redis_keypair_bytes.clone();
+        let evidence = evidence.clone();
+        async move {
+            let redis_keypair = Ed25519KeyPair::from_bytes(&keypair_bytes)
+                .map_err(|e| anyhow::anyhow!("Invalid enclave keypair: {}", e))?;
+            start_redis_sui_processor(redis_keypair, evidence).await
         }
-    }
+    });
 
-    Ok(())
+    supervisor.run().await
 }
 
 async fn run_api_server(state: Arc<AppState>) -> Result<()> {
-    use tower_http::cors::CorsLayer;
-    use tower_http::cors::Any;
-    
-    let cors = CorsLayer::new()
-        .allow_methods(Any)
-        .allow_headers(Any)
-        .allow_origin(Any); // For development
+    let auth_config = AuthConfig::from_env();
+    let auth = Arc::new(Authenticator::new(&auth_config, state.request_limits.max_body_bytes));
+    let cors = cors_layer(&auth_config);
+    let body_limit = DefaultBodyLimit::max(state.request_limits.max_body_bytes);
 
+    // Every route declares who may call it
     let app = Router::new()
-        .route("/", get(ping))
-        .route("/health", get(health_check))
-        .route("/get_attestation", get(get_attestation))
-        .route("/process_kyc", post(process_kyc))
-        // zkLogin endpoints - COMMENTED OUT - No longer using zkLogin for now
-        // .route("/get_salt", post(get_salt))
-        // .route("/get_zk_proof", post(get_zk_proof))
+        .route("/", with_auth(get(ping), AuthRequirement::Public, &auth))
+        .route("/health", with_auth(get(health_check), AuthRequirement::Public, &auth))
+        .route("/livez", with_auth(get(livez), AuthRequirement::Public, &auth))
+        .route("/readyz", with_auth(get(readyz), AuthRequirement::Public, &auth))
+        .route("/get_attestation", with_auth(get(get_attestation), AuthRequirement::Public, &auth))
+        .route("/metrics", with_auth(get(metrics_handler), AuthRequirement::Client, &auth))
+        .route("/process_kyc", with_auth(post(process_kyc).layer(body_limit), AuthRequirement::Client, &auth))
+        .route("/revoke_binding", with_auth(post(revoke_binding), AuthRequirement::Client, &auth))
+        .route("/open_evidence", with_auth(post(open_evidence), AuthRequirement::Client, &auth))
+        .route("/verify_did_nfts", with_auth(post(verify_did_nfts), AuthRequirement::Client, &auth));
+    // Walletless onboarding; callers authenticate with their provider JWT
+    #[cfg(feature = "zklogin")]
+    let app = app
+        .route("/get_salt", with_auth(post(get_salt), AuthRequirement::Public, &auth))
+        .route("/get_zk_proof", with_auth(post(get_zk_proof), AuthRequirement::Public, &auth));
+    let app = app
         .with_state(state)
+        .layer(middleware::from_fn(request_id_middleware))
         .layer(cors);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:4000").await?;
     info!("Attestation server listening on {}", listener.local_addr().unwrap());
-    axum::serve(listener, app)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .map_err(|e| anyhow::anyhow!("Server error: {}", e))
 }
@@ -0,0 +1,259 @@
+// media.rs
+use crate::EnclaveError;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Cursor;
+
+/// Decoding and normalization limits for KYC images.
+#[derive(Debug, Clone)]
+pub struct MediaLimits {
+    /// Maximum encoded size of one image
+    pub max_encoded_bytes: usize,
+    /// Smallest accepted width and height
+    pub min_dimension: u32,
+    /// Largest accepted width and height (checked from the header, before decoding)
+    pub max_dimension: u32,
+    /// Largest accepted width * height
+    pub max_pixels: u64,
+    /// Decoder allocation cap, guards against decompression bombs
+    pub max_alloc_bytes: u64,
+    /// Longest side of normalized frames handed to the verifier
+    pub normalized_size: u32,
+}
+
+impl MediaLimits {
+    /// Load from `MEDIA_MAX_ENCODED_BYTES`, `MEDIA_MIN_DIMENSION`, `MEDIA_MAX_DIMENSION`,
+    /// `MEDIA_MAX_PIXELS`, `MEDIA_MAX_ALLOC_BYTES` and `MEDIA_NORMALIZED_SIZE`.
+    pub fn from_env() -> Self {
+        let env_u64 = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            max_encoded_bytes: env_u64("MEDIA_MAX_ENCODED_BYTES").unwrap_or(1024 * 1024) as usize,
+            min_dimension: env_u64("MEDIA_MIN_DIMENSION").unwrap_or(64) as u32,
+            max_dimension: env_u64("MEDIA_MAX_DIMENSION").unwrap_or(4096) as u32,
+            max_pixels: env_u64("MEDIA_MAX_PIXELS").unwrap_or(12_000_000),
+            max_alloc_bytes: env_u64("MEDIA_MAX_ALLOC_BYTES").unwrap_or(128 * 1024 * 1024),
+            normalized_size: env_u64("MEDIA_NORMALIZED_SIZE").unwrap_or(640) as u32,
+        }
+    }
+}
+
+/// A decoded image reduced to RGB pixels. Metadata (EXIF, GPS, XMP, ICC) is
+/// dropped by construction; only the orientation is applied before discarding it.
+#[derive(Debug, Clone)]
+pub struct NormalizedImage {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    /// Row-major RGB8 pixels
+    pub rgb: Vec<u8>,
+    /// Hex SHA-256 of the encoded input
+    pub source_digest: String,
+    /// Hex SHA-256 of the normalized pixels
+    pub pixel_digest: String,
+}
+
+/// Decodes and normalizes the images of one KYC submission.
+pub struct MediaProcessor {
+    limits: MediaLimits,
+}
+
+impl MediaProcessor {
+    pub fn new(limits: MediaLimits) -> Self {
+        Self { limits }
+    }
+
+    /// Decode a single JPEG, PNG or WebP image.
+    pub fn decode(&self, label: &str, bytes: &[u8]) -> Result<NormalizedImage, EnclaveError> {
+        let invalid = |reason: String| EnclaveError::InvalidInput(format!("{}: {}", label, reason));
+
+        if bytes.len() > self.limits.max_encoded_bytes {
+            return Err(invalid(format!(
+                "image exceeds {} bytes",
+                self.limits.max_encoded_bytes
+            )));
+        }
+
+        let format = image::guess_format(bytes)
+            .map_err(|_| invalid("unrecognized image format".to_string()))?;
+        if !matches!(format, ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP) {
+            return Err(invalid(format!("unsupported image format {:?}", format)));
+        }
+
+        // Check the header before allocating any pixel buffer
+        let (width, height) = ImageReader::with_format(Cursor::new(bytes), format)
+            .into_dimensions()
+            .map_err(|e| invalid(format!("unreadable image header: {}", e)))?;
+        self.check_dimensions(width, height).map_err(invalid)?;
+
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(self.limits.max_dimension);
+        limits.max_image_height = Some(self.limits.max_dimension);
+        limits.max_alloc = Some(self.limits.max_alloc_bytes);
+
+        let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+        reader.limits(limits);
+        let mut decoder = reader
+            .into_decoder()
+            .map_err(|e| invalid(format!("undecodable image: {}", e)))?;
+        let orientation = decoder
+            .orientation()
+            .map_err(|e| invalid(format!("invalid image metadata: {}", e)))?;
+        let mut decoded = DynamicImage::from_decoder(decoder)
+            .map_err(|e| invalid(format!("corrupt or truncated image: {}", e)))?;
+        decoded.apply_orientation(orientation);
+
+        let normalized = self.normalize(decoded);
+        let rgb = normalized.into_rgb8();
+        let (width, height) = rgb.dimensions();
+        let rgb = rgb.into_raw();
+
+        Ok(NormalizedImage {
+            format,
+            width,
+            height,
+            source_digest: hex::encode(Sha256::digest(bytes)),
+            pixel_digest: hex::encode(Sha256::digest(&rgb)),
+            rgb,
+        })
+    }
+
+    /// Decode all face frames of a session, rejecting frames that repeat an
+    /// earlier one byte-for-byte or pixel-for-pixel.
+    pub fn decode_frames<F: AsRef<[u8]>>(&self, frames: &[F]) -> Result<Vec<NormalizedImage>, EnclaveError> {
+        let mut seen = HashSet::new();
+        frames
+            .iter()
+            .enumerate()
+            .map(|(index, bytes)| {
+                let frame = self.decode(&format!("Face frame {}", index), bytes.as_ref())?;
+                if !seen.insert(frame.source_digest.clone()) || !seen.insert(frame.pixel_digest.clone()) {
+                    return Err(EnclaveError::InvalidInput(format!(
+                        "Face frame {} duplicates an earlier frame",
+                        index
+                    )));
+                }
+                Ok(frame)
+            })
+            .collect()
+    }
+
+    fn check_dimensions(&self, width: u32, height: u32) -> Result<(), String> {
+        let limits = &self.limits;
+        if width < limits.min_dimension || height < limits.min_dimension {
+            return Err(format!(
+                "image is {}x{}, smaller than {}px",
+                width, height, limits.min_dimension
+            ));
+        }
+        if width > limits.max_dimension || height > limits.max_dimension {
+            return Err(format!(
+                "image is {}x{}, larger than {}px",
+                width, height, limits.max_dimension
+            ));
+        }
+        if width as u64 * height as u64 > limits.max_pixels {
+            return Err(format!(
+                "image has more than {} pixels",
+                limits.max_pixels
+            ));
+        }
+        Ok(())
+    }
+
+    /// Downscale so the longest side is at most `normalized_size`.
+    fn normalize(&self, image: DynamicImage) -> DynamicImage {
+        let size = self.limits.normalized_size;
+        if image.width() <= size && image.height() <= size {
+            return image;
+        }
+        image.resize(size, size, FilterType::Triangle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID_PNG: &[u8] = include_bytes!("../fixtures/media/valid.png");
+    const VALID_JPG: &[u8] = include_bytes!("../fixtures/media/valid.jpg");
+    const VALID_WEBP: &[u8] = include_bytes!("../fixtures/media/valid.webp");
+    const CORRUPT_JPG: &[u8] = include_bytes!("../fixtures/media/corrupt.jpg");
+    const TRUNCATED_PNG: &[u8] = include_bytes!("../fixtures/media/truncated.png");
+    const BOMB_PNG: &[u8] = include_bytes!("../fixtures/media/bomb.png");
+
+    fn limits() -> MediaLimits {
+        MediaLimits {
+            max_encoded_bytes: 1024 * 1024,
+            min_dimension: 64,
+            max_dimension: 4096,
+            max_pixels: 12_000_000,
+            max_alloc_bytes: 128 * 1024 * 1024,
+            normalized_size: 640,
+        }
+    }
+
+    fn rejection(processor: &MediaProcessor, bytes: &[u8]) -> String {
+        match processor.decode("Document", bytes) {
+            Err(EnclaveError::InvalidInput(message)) => message,
+            other => panic!("expected InvalidInput, got {:?}", other.map(|i| (i.width, i.height))),
+        }
+    }
+
+    #[test]
+    fn decodes_supported_formats_to_rgb() {
+        let processor = MediaProcessor::new(limits());
+        for (bytes, format) in [
+            (VALID_PNG, ImageFormat::Png),
+            (VALID_JPG, ImageFormat::Jpeg),
+            (VALID_WEBP, ImageFormat::WebP),
+        ] {
+            let image = processor.decode("Document", bytes).unwrap();
+            assert_eq!(image.format, format);
+            assert_eq!((image.width, image.height), (128, 128));
+            assert_eq!(image.rgb.len(), 128 * 128 * 3);
+            assert_eq!(image.source_digest, hex::encode(Sha256::digest(bytes)));
+        }
+    }
+
+    #[test]
+    fn rejects_corrupt_and_truncated_images() {
+        let processor = MediaProcessor::new(limits());
+        rejection(&processor, CORRUPT_JPG);
+        rejection(&processor, TRUNCATED_PNG);
+        assert!(rejection(&processor, b"not an image").contains("unrecognized image format"));
+    }
+
+    #[test]
+    fn rejects_decompression_bomb_from_the_header() {
+        let processor = MediaProcessor::new(limits());
+        assert!(rejection(&processor, BOMB_PNG).contains("larger than 4096px"));
+    }
+
+    #[test]
+    fn enforces_size_and_dimension_limits() {
+        let processor = MediaProcessor::new(MediaLimits { max_encoded_bytes: 16, ..limits() });
+        assert!(rejection(&processor, VALID_PNG).contains("exceeds 16 bytes"));
+
+        let processor = MediaProcessor::new(MediaLimits { min_dimension: 256, ..limits() });
+        assert!(rejection(&processor, VALID_PNG).contains("smaller than 256px"));
+
+        let processor = MediaProcessor::new(MediaLimits { max_pixels: 128 * 127, ..limits() });
+        assert!(rejection(&processor, VALID_PNG).contains("more than"));
+    }
+
+    #[test]
+    fn downscales_to_the_normalized_size() {
+        let processor = MediaProcessor::new(MediaLimits { normalized_size: 64, ..limits() });
+        let image = processor.decode("Document", VALID_PNG).unwrap();
+        assert_eq!((image.width, image.height), (64, 64));
+    }
+
+    #[test]
+    fn rejects_repeated_face_frames() {
+        let processor = MediaProcessor::new(limits());
+        assert_eq!(processor.decode_frames(&[VALID_PNG, VALID_JPG]).unwrap().len(), 2);
+        assert!(processor.decode_frames(&[VALID_PNG, VALID_JPG, VALID_PNG]).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+// merkle.rs
+use sha2::{Digest, Sha256};
+
+/// Prefixes keeping leaf and interior hashes apart, so a node can never be
+/// presented as a leaf.
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// SHA-256(0x00 || domain || salt || len(name) || name || len(value) || value).
+/// Lengths are little-endian u32, so field boundaries cannot shift.
+pub fn leaf_hash(domain: &[u8], salt: &[u8], name: &str, value: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(domain);
+    hasher.update(salt);
+    for part in [name.as_bytes(), value.as_bytes()] {
+        hasher.update((part.len() as u32).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Hash one level up; an odd last node is promoted unchanged rather than
+/// paired with itself.
+fn parent_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+/// Root over the leaves in order; `None` for an empty tree.
+pub fn root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = parent_level(&level);
+    }
+    level.first().copied()
+}
+
+/// Sibling hashes from the leaf at `index` up to the root. Levels where the
+/// node was promoted contribute no sibling.
+pub fn proof(leaves: &[[u8; 32]], mut index: usize) -> Vec<[u8; 32]> {
+    let mut siblings = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            siblings.push(level[sibling]);
+        }
+        level = parent_level(&level);
+        index /= 2;
+    }
+    siblings
+}
+
+/// Recompute the root from a leaf, its position in a tree of `leaf_count`
+/// leaves and its [`proof`].
+pub fn verify(
+    root: &[u8; 32],
+    leaf: [u8; 32],
+    mut index: usize,
+    mut leaf_count: usize,
+    proof: &[[u8; 32]],
+) -> bool {
+    if index >= leaf_count {
+        return false;
+    }
+    let mut siblings = proof.iter();
+    let mut hash = leaf;
+    while leaf_count > 1 {
+        let sibling = index ^ 1;
+        if sibling < leaf_count {
+            let Some(sibling_hash) = siblings.next() else {
+                return false;
+            };
+            hash = if index % 2 == 0 {
+                node_hash(&hash, sibling_hash)
+            } else {
+                node_hash(sibling_hash, &hash)
+            };
+        }
+        index /= 2;
+        leaf_count = leaf_count.div_ceil(2);
+    }
+    siblings.next().is_none() && hash == *root
+}
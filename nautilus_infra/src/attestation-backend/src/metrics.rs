@@ -0,0 +1,153 @@
+// metrics.rs
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Prometheus series exported on `/metrics`.
+pub struct Metrics {
+    registry: Registry,
+    /// Redis stream messages by DID type, verification result and outcome
+    /// (consumed, acked, failed, dead_lettered).
+    pub stream_messages: IntCounterVec,
+    /// Time from the Python service's `verified_at` to on-chain confirmation.
+    pub end_to_end_latency: HistogramVec,
+    /// Sui proxy round-trip time per Move function.
+    pub transaction_latency: HistogramVec,
+    /// Net gas (computation + storage - rebate, in MIST) per Move function.
+    pub transaction_gas_used: HistogramVec,
+    /// Entries in the stream not yet delivered to the consumer group.
+    pub stream_lag: IntGauge,
+    /// Entries delivered to the consumer group but not yet acknowledged.
+    pub stream_pending: IntGauge,
+    /// `/process_kyc` requests by outcome.
+    pub kyc_requests: IntCounterVec,
+    /// Enclave signing operations by intent scope.
+    pub signing_operations: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("suiverify".to_string()), None)
+            .expect("valid metrics namespace");
+
+        let stream_messages = IntCounterVec::new(
+            Opts::new("stream_messages_total", "Redis stream messages by DID type, result and outcome"),
+            &["did_type", "result", "outcome"],
+        )
+        .expect("valid metric");
+        let end_to_end_latency = HistogramVec::new(
+            HistogramOpts::new(
+                "end_to_end_latency_seconds",
+                "Time from verified_at to on-chain confirmation",
+            )
+            .buckets(vec![1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0]),
+            &["did_type"],
+        )
+        .expect("valid metric");
+        let transaction_latency = HistogramVec::new(
+            HistogramOpts::new("transaction_latency_seconds", "Sui transaction latency per Move function")
+                .buckets(vec![0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0]),
+            &["function", "status"],
+        )
+        .expect("valid metric");
+        let transaction_gas_used = HistogramVec::new(
+            HistogramOpts::new("transaction_gas_used_mist", "Net gas used per Move function in MIST")
+                .buckets(prometheus::exponential_buckets(100_000.0, 2.0, 12).expect("valid buckets")),
+            &["function"],
+        )
+        .expect("valid metric");
+        let stream_lag = IntGauge::new("stream_lag", "Redis stream entries not yet delivered to the consumer group")
+            .expect("valid metric");
+        let stream_pending = IntGauge::new("stream_pending", "Redis stream entries delivered but not acknowledged")
+            .expect("valid metric");
+        let kyc_requests = IntCounterVec::new(
+            Opts::new("kyc_requests_total", "KYC requests by outcome"),
+            &["outcome"],
+        )
+        .expect("valid metric");
+        let signing_operations = IntCounterVec::new(
+            Opts::new("signing_operations_total", "Enclave signing operations by intent scope"),
+            &["scope"],
+        )
+        .expect("valid metric");
+
+        registry.register(Box::new(stream_messages.clone())).expect("unique metric");
+        registry.register(Box::new(end_to_end_latency.clone())).expect("unique metric");
+        registry.register(Box::new(transaction_latency.clone())).expect("unique metric");
+        registry.register(Box::new(transaction_gas_used.clone())).expect("unique metric");
+        registry.register(Box::new(stream_lag.clone())).expect("unique metric");
+        registry.register(Box::new(stream_pending.clone())).expect("unique metric");
+        registry.register(Box::new(kyc_requests.clone())).expect("unique metric");
+        registry.register(Box::new(signing_operations.clone())).expect("unique metric");
+
+        Self {
+            registry,
+            stream_messages,
+            end_to_end_latency,
+            transaction_latency,
+            transaction_gas_used,
+            stream_lag,
+            stream_pending,
+            kyc_requests,
+            signing_operations,
+        }
+    }
+
+    pub fn record_stream_message(&self, did_type: &str, result: &str, outcome: &str) {
+        self.stream_messages
+            .with_label_values(&[did_type, result, outcome])
+            .inc();
+    }
+
+    pub fn record_transaction(&self, function: &str, success: bool, elapsed: Duration, gas_used: Option<u64>) {
+        let status = if success { "success" } else { "failure" };
+        self.transaction_latency
+            .with_label_values(&[function, status])
+            .observe(elapsed.as_secs_f64());
+        if let Some(gas) = gas_used {
+            self.transaction_gas_used
+                .with_label_values(&[function])
+                .observe(gas as f64);
+        }
+    }
+
+    pub fn record_kyc(&self, outcome: &str) {
+        self.kyc_requests.with_label_values(&[outcome]).inc();
+    }
+
+    pub fn record_signing(&self, scope: &str) {
+        self.signing_operations.with_label_values(&[scope]).inc();
+    }
+
+    /// Render all series in the Prometheus text exposition format.
+    pub fn render(&self) -> Result<String, String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .map_err(|e| format!("Failed to encode metrics: {}", e))?;
+        String::from_utf8(buffer).map_err(|e| format!("Metrics are not valid UTF-8: {}", e))
+    }
+}
+
+/// Process-wide metrics, shared by the API handlers and the Redis processor.
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+/// Endpoint that serves metrics in Prometheus text format.
+pub async fn metrics_handler() -> Response {
+    match metrics().render() {
+        Ok(body) => (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, TextEncoder::new().format_type().to_string())],
+            body,
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
@@ -0,0 +1,280 @@
+// nullifier.rs
+use crate::redact::SecretBytes;
+use crate::secrets::SecretStore;
+use crate::EnclaveError;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::sync::Mutex;
+use tracing::{info, warn};
+
+/// Domain separator for nullifiers.
+const NULLIFIER_DOMAIN: &[u8] = b"SuiVerify/nullifier/v1";
+/// Name of the PRF key in the secret store.
+const NULLIFIER_KEY_SECRET: &str = "nullifier_key";
+
+/// What happens when a document that is already bound shows up with another wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingPolicy {
+    /// A document backs one wallet per DID type, for good
+    OneWallet,
+    /// A document may move to a new wallet once its binding is revoked
+    RebindAfterRevocation,
+}
+
+/// One change to the nullifier bindings, as appended to the store file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BindingEvent {
+    nullifier: String,
+    wallet_address: String,
+    revoked: bool,
+    timestamp_ms: u64,
+}
+
+struct Binding {
+    wallet_address: String,
+    revoked: bool,
+}
+
+/// Derives nullifiers from document identity and binds each to one wallet, so
+/// a document cannot verify any number of wallets. Nullifiers are
+/// HMAC-SHA256(key, domain || did_type || identity): stable for a document and
+/// DID type, and unlinkable to the document number without the enclave key.
+pub struct NullifierStore {
+    key: SecretBytes,
+    policy: BindingPolicy,
+    store_file: Option<String>,
+    bindings: Mutex<HashMap<String, Binding>>,
+}
+
+impl NullifierStore {
+    /// Key from the secret store (`nullifier_key`), `NULLIFIER_POLICY`
+    /// (`one_wallet` or `rebind_after_revocation`) and `NULLIFIER_STORE_FILE`
+    /// (optional JSON-lines file of bindings, replayed on start).
+    pub fn from_env(secrets: &SecretStore) -> Result<Self, EnclaveError> {
+        let policy = match std::env::var("NULLIFIER_POLICY").as_deref() {
+            Ok("rebind_after_revocation") => BindingPolicy::RebindAfterRevocation,
+            Ok("one_wallet") | Err(_) => BindingPolicy::OneWallet,
+            Ok(other) => {
+                warn!("Unknown NULLIFIER_POLICY {}, using one_wallet", other);
+                BindingPolicy::OneWallet
+            }
+        };
+        let store_file = std::env::var("NULLIFIER_STORE_FILE").ok().filter(|p| !p.is_empty());
+        Self::new(secrets.get(NULLIFIER_KEY_SECRET)?, policy, store_file)
+    }
+
+    /// Without a key, one is generated per boot, which is only acceptable when
+    /// nothing is persisted: replayed bindings would never match again and a
+    /// document could bind a second wallet after every restart.
+    pub fn new(
+        key: Option<SecretBytes>,
+        policy: BindingPolicy,
+        store_file: Option<String>,
+    ) -> Result<Self, EnclaveError> {
+        let key = match (key, &store_file) {
+            (Some(key), _) => key,
+            (None, Some(path)) => {
+                return Err(EnclaveError::InternalError(format!(
+                    "Nullifier store {} is configured but no {} is provisioned",
+                    path, NULLIFIER_KEY_SECRET
+                )))
+            }
+            (None, None) => {
+                warn!("No nullifier key provisioned: using a random key, nullifiers change on restart");
+                let mut key = SecretBytes::new(vec![0u8; 32]);
+                rand::thread_rng().fill_bytes(&mut key);
+                key
+            }
+        };
+
+        let mut bindings = HashMap::new();
+        if let Some(path) = &store_file {
+            let replayed = replay(path, &mut bindings)
+                .map_err(|e| EnclaveError::InternalError(format!("Cannot read nullifier store {}: {}", path, e)))?;
+            info!("Replayed {} nullifier bindings from {}", replayed, path);
+        }
+        info!("Nullifier binding policy: {:?}", policy);
+
+        Ok(Self {
+            key,
+            policy,
+            store_file,
+            bindings: Mutex::new(bindings),
+        })
+    }
+
+    /// Hex nullifier for a document identity key and DID type.
+    pub fn derive(&self, identity_key: &str, did_type: u8) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(NULLIFIER_DOMAIN);
+        mac.update(&[did_type]);
+        mac.update(identity_key.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// Whether `wallet_address` may be bound to `nullifier` under the policy.
+    pub fn check(&self, nullifier: &str, wallet_address: &str) -> Result<(), EnclaveError> {
+        let bindings = self.bindings.lock().expect("nullifier store poisoned");
+        self.check_binding(bindings.get(nullifier), wallet_address)
+    }
+
+    /// Bind `nullifier` to `wallet_address`; re-verifying the same wallet is allowed.
+    pub fn bind(&self, nullifier: &str, wallet_address: &str, timestamp_ms: u64) -> Result<(), EnclaveError> {
+        let mut bindings = self.bindings.lock().expect("nullifier store poisoned");
+        self.check_binding(bindings.get(nullifier), wallet_address)?;
+        self.apply(
+            &mut bindings,
+            BindingEvent {
+                nullifier: nullifier.to_string(),
+                wallet_address: wallet_address.to_string(),
+                revoked: false,
+                timestamp_ms,
+            },
+        )
+    }
+
+    /// Revoke the binding of `nullifier` to `wallet_address`.
+    pub fn revoke(&self, nullifier: &str, wallet_address: &str, timestamp_ms: u64) -> Result<(), EnclaveError> {
+        let mut bindings = self.bindings.lock().expect("nullifier store poisoned");
+        match bindings.get(nullifier) {
+            Some(binding) if binding.wallet_address == wallet_address => {}
+            _ => {
+                return Err(EnclaveError::InvalidInput(
+                    "Nullifier is not bound to this wallet".to_string(),
+                ))
+            }
+        }
+        self.apply(
+            &mut bindings,
+            BindingEvent {
+                nullifier: nullifier.to_string(),
+                wallet_address: wallet_address.to_string(),
+                revoked: true,
+                timestamp_ms,
+            },
+        )
+    }
+
+    fn check_binding(&self, binding: Option<&Binding>, wallet_address: &str) -> Result<(), EnclaveError> {
+        match binding {
+            None => Ok(()),
+            Some(binding) if binding.wallet_address == wallet_address => Ok(()),
+            Some(binding) if binding.revoked && self.policy == BindingPolicy::RebindAfterRevocation => Ok(()),
+            Some(_) => Err(EnclaveError::VerificationFailed(
+                "This document is already bound to another wallet".to_string(),
+            )),
+        }
+    }
+
+    /// Persist first, so a binding the store file does not have is never honoured.
+    fn apply(&self, bindings: &mut HashMap<String, Binding>, event: BindingEvent) -> Result<(), EnclaveError> {
+        if let Some(path) = &self.store_file {
+            append_event(path, &event)
+                .map_err(|e| EnclaveError::InternalError(format!("Cannot write nullifier store: {}", e)))?;
+        }
+        bindings.insert(
+            event.nullifier,
+            Binding {
+                wallet_address: event.wallet_address,
+                revoked: event.revoked,
+            },
+        );
+        Ok(())
+    }
+}
+
+fn replay(path: &str, bindings: &mut HashMap<String, Binding>) -> std::io::Result<usize> {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    let mut count = 0;
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<BindingEvent>(&line) {
+            Ok(event) => {
+                bindings.insert(
+                    event.nullifier,
+                    Binding {
+                        wallet_address: event.wallet_address,
+                        revoked: event.revoked,
+                    },
+                );
+                count += 1;
+            }
+            Err(e) => warn!("Skipping malformed nullifier store line: {}", e),
+        }
+    }
+    Ok(count)
+}
+
+fn append_event(path: &str, event: &BindingEvent) -> std::io::Result<()> {
+    let line = serde_json::to_string(event)?;
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLET_A: &str = "0x00000000000000000000000000000000000000000000000000000000000000aa";
+    const WALLET_B: &str = "0x00000000000000000000000000000000000000000000000000000000000000bb";
+
+    fn key() -> Option<SecretBytes> {
+        Some(SecretBytes::new(vec![7u8; 32]))
+    }
+
+    fn store_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("nullifier-{}-{}.jsonl", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn refuses_persisted_store_without_a_key() {
+        let path = store_path("no-key");
+        assert!(matches!(
+            NullifierStore::new(None, BindingPolicy::OneWallet, Some(path)),
+            Err(EnclaveError::InternalError(_))
+        ));
+        assert!(NullifierStore::new(None, BindingPolicy::OneWallet, None).is_ok());
+    }
+
+    #[test]
+    fn bindings_survive_a_restart() {
+        let path = store_path("restart");
+        let store = NullifierStore::new(key(), BindingPolicy::OneWallet, Some(path.clone())).unwrap();
+        let nullifier = store.derive("passport:IND:Z1234567", 1);
+        assert_ne!(nullifier, store.derive("passport:IND:Z1234567", 2));
+        store.bind(&nullifier, WALLET_A, 1).unwrap();
+
+        let restarted = NullifierStore::new(key(), BindingPolicy::OneWallet, Some(path.clone())).unwrap();
+        assert_eq!(restarted.derive("passport:IND:Z1234567", 1), nullifier);
+        restarted.check(&nullifier, WALLET_A).unwrap();
+        assert!(restarted.check(&nullifier, WALLET_B).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn rebinds_only_after_revocation_when_allowed() {
+        let store = NullifierStore::new(key(), BindingPolicy::RebindAfterRevocation, None).unwrap();
+        store.bind("n", WALLET_A, 1).unwrap();
+        assert!(store.bind("n", WALLET_B, 2).is_err());
+        store.revoke("n", WALLET_A, 3).unwrap();
+        store.bind("n", WALLET_B, 4).unwrap();
+
+        let strict = NullifierStore::new(key(), BindingPolicy::OneWallet, None).unwrap();
+        strict.bind("n", WALLET_A, 1).unwrap();
+        strict.revoke("n", WALLET_A, 2).unwrap();
+        assert!(strict.bind("n", WALLET_B, 3).is_err());
+    }
+}
@@ -0,0 +1,294 @@
+// redact.rs
+use std::fmt;
+use tracing::field::{Field, Visit};
+use tracing::Subscriber;
+use tracing_subscriber::field::RecordFields;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FormatFields, MakeWriter};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+use zeroize::Zeroizing;
+
+/// Decrypted document bytes, face frames and key material: wiped on drop.
+pub type SecretBytes = Zeroizing<Vec<u8>>;
+
+/// Hex runs at least this long (wallet addresses, digests, evidence hashes,
+/// nullifiers) are masked wherever they appear in a log line.
+const MIN_HEX_LEN: usize = 32;
+/// Hex characters kept so masked values can still be correlated.
+const KEPT_HEX_LEN: usize = 4;
+/// Digit runs at least this long (mobile and Aadhaar numbers, reference IDs,
+/// passport numbers) are masked, alone or mixed with letters.
+const MIN_DIGITS: usize = 8;
+/// Mixed letter and digit words with at least this many digits look like
+/// document numbers or MRZ lines.
+const MIN_DOCUMENT_DIGITS: usize = 6;
+/// Replacement for values that are masked entirely.
+const REDACTED: &str = "[redacted]";
+
+/// Fields whose values are never logged. Any field named `pii.*` is treated
+/// the same, e.g. `info!(pii.name = %name, "...")`.
+const PII_FIELDS: &[&str] = &[
+    "name",
+    "date_of_birth",
+    "gender",
+    "mobile",
+    "email",
+    "document_number",
+    "share_code",
+];
+
+fn is_pii(field: &str) -> bool {
+    field.starts_with("pii.") || PII_FIELDS.contains(&field)
+}
+
+/// Mask identifying values in free text: long hex values keep their first few
+/// characters, while dates, email addresses and long numbers are replaced
+/// entirely. Names have no recognizable shape, so they must only reach the log
+/// through `pii.*` fields or types whose `Debug` output leaves them out, such
+/// as `IdentityRecord`.
+pub fn redact(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        out.push_str(&rest[..start]);
+        let token = &rest[start..];
+        let len = token.find(|c: char| !is_token_char(c)).unwrap_or(token.len());
+        // Punctuation ending a sentence or clause is not part of the token
+        let token = token[..len].trim_end_matches(|c: char| !c.is_ascii_alphanumeric());
+        out.push_str(&mask_token(token));
+        rest = &rest[start + token.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_token_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '@' | '.' | '-' | '/' | '_' | '+')
+}
+
+/// Mask a whole token if it is a date or an email address, otherwise each
+/// alphanumeric word in it.
+fn mask_token(token: &str) -> String {
+    if is_date(token) || is_email(token) {
+        return REDACTED.to_string();
+    }
+    let mut out = String::with_capacity(token.len());
+    let mut rest = token;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_alphanumeric()) {
+        out.push_str(&rest[..start]);
+        let word = &rest[start..];
+        let len = word.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(word.len());
+        out.push_str(&mask_word(&word[..len]));
+        rest = &word[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn mask_word(word: &str) -> String {
+    let (prefix, digits) = match word.strip_prefix("0x") {
+        Some(digits) => ("0x", digits),
+        None => ("", word),
+    };
+    if digits.len() >= MIN_HEX_LEN && digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return format!("{}{}…", prefix, &digits[..KEPT_HEX_LEN]);
+    }
+    let digit_count = word.bytes().filter(u8::is_ascii_digit).count();
+    let has_letter = word.bytes().any(|b| b.is_ascii_alphabetic());
+    if digit_count >= MIN_DIGITS || (has_letter && digit_count >= MIN_DOCUMENT_DIGITS) {
+        REDACTED.to_string()
+    } else {
+        word.to_string()
+    }
+}
+
+/// `DD-MM-YYYY`, `DD/MM/YYYY`, `YYYY-MM-DD` or `YYYY/MM/DD`.
+fn is_date(token: &str) -> bool {
+    let parts: Vec<&str> = token.split(['-', '/']).collect();
+    let lengths: Vec<usize> = parts.iter().map(|p| p.len()).collect();
+    parts.iter().all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+        && matches!(lengths.as_slice(), [2, 2, 4] | [4, 2, 2])
+}
+
+fn is_email(token: &str) -> bool {
+    token
+        .split_once('@')
+        .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+}
+
+/// Field formatter for the fmt layer: PII fields are dropped and everything
+/// else, including the message, goes through [`redact`].
+pub struct RedactingFields;
+
+impl<'writer> FormatFields<'writer> for RedactingFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        let mut visitor = RedactingVisitor { writer, result: Ok(()), first: true };
+        fields.record(&mut visitor);
+        visitor.result
+    }
+}
+
+struct RedactingVisitor<'writer> {
+    writer: Writer<'writer>,
+    result: fmt::Result,
+    first: bool,
+}
+
+impl RedactingVisitor<'_> {
+    fn write(&mut self, field: &Field, value: &str) {
+        if self.result.is_err() {
+            return;
+        }
+        if !self.first {
+            self.result = self.writer.write_char(' ');
+        }
+        self.first = false;
+        self.result = self.result.and_then(|_| match field.name() {
+            "message" => self.writer.write_str(&redact(value)),
+            name if is_pii(name) => write!(self.writer, "{}=[redacted]", name),
+            name => write!(self.writer, "{}={}", name, redact(value)),
+        });
+    }
+}
+
+impl Visit for RedactingVisitor<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.write(field, value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.write(field, &format!("{:?}", value));
+    }
+}
+
+/// The fmt layer with redacted fields; install it instead of `fmt::init()`.
+pub fn layer<S>() -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    layer_with_writer(std::io::stdout)
+}
+
+/// [`layer`] writing somewhere other than stdout.
+pub fn layer_with_writer<S, W>(writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + 'static,
+{
+    tracing_subscriber::fmt::layer().fmt_fields(RedactingFields).with_writer(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aadhaar::offline_ekyc::decode_offline_ekyc;
+    use crate::aadhaar::UidaiTrust;
+    use crate::documents::mrz::PassportMrzParser;
+    use crate::documents::{DocumentInput, DocumentParser};
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing::{info, warn};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Personal data in the Aadhaar and passport fixtures.
+    const FIXTURE_PII: &[&str] = &[
+        "Test Resident",
+        "01-01-1990",
+        "1990-01-01",
+        "9876543210",
+        "resident@example.com",
+        "123420190101120000000",
+        "SHARMA",
+        "PRIYA",
+        "Z1234567",
+        "1990-01-15",
+    ];
+
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Capture {
+        type Writer = Capture;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    fn fixture(path: &str) -> String {
+        format!("{}/fixtures/{}", env!("CARGO_MANIFEST_DIR"), path)
+    }
+
+    #[test]
+    fn masks_identifiers_in_free_text() {
+        assert_eq!(
+            redact("wallet 0xABCDEF0123456789abcdef0123456789abcdef0123456789abcdef0123456789 verified."),
+            "wallet 0xABCD… verified."
+        );
+        assert_eq!(
+            redact("born 01-01-1990, 1990/01/15 or 15/01/1990; mail a.b+c@example.co.in."),
+            "born [redacted], [redacted] or [redacted]; mail [redacted]."
+        );
+        assert_eq!(redact("mobile 9876543210 passport Z1234567"), "mobile [redacted] passport [redacted]");
+        assert_eq!(
+            redact("Verified aadhaar_xml document (aadhaar) with Ed25519 key, 3 frames, epoch 812"),
+            "Verified aadhaar_xml document (aadhaar) with Ed25519 key, 3 frames, epoch 812"
+        );
+    }
+
+    #[test]
+    fn no_log_line_contains_fixture_pii() {
+        let capture = Capture::default();
+        let subscriber = tracing_subscriber::registry().with(layer_with_writer(capture.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let cert = fixture("aadhaar/test_uidai_cert.pem");
+            let zip = std::fs::read(fixture("aadhaar/offline_ekyc.zip")).unwrap();
+            let aadhaar = decode_offline_ekyc(&zip, "1234", &UidaiTrust::from_files(&[&cert])).unwrap();
+            info!("Decoded {:?}", aadhaar);
+            info!(pii.name = %aadhaar.name, reference = %aadhaar.reference_id, "Aadhaar holder");
+            if let Err(e) = aadhaar.verify_mobile("9876543211", "1234") {
+                warn!("Contact check failed: {}", e);
+            }
+            info!(mobile = "9876543210", email = "resident@example.com", "Contact details checked");
+            info!("Contact 9876543210 / resident@example.com, born 01-01-1990 (1990-01-01)");
+
+            let mrz = std::fs::read(fixture("passport/td3_indian.txt")).unwrap();
+            let input = DocumentInput {
+                data: &mrz,
+                secret: None,
+                mobile: None,
+                email: None,
+                timestamp_ms: 1_767_225_600_000,
+            };
+            let passport = PassportMrzParser.parse(&input).unwrap();
+            info!("Read {:?}", passport);
+            info!(
+                pii.name = %passport.name,
+                document_number = ?passport.document_number,
+                date_of_birth = ?passport.date_of_birth,
+                "Passport holder"
+            );
+            info!("Passport {} born 1990-01-15", passport.document_number.as_deref().unwrap_or_default());
+            warn!("Unparsed MRZ line {}", String::from_utf8_lossy(&mrz).lines().nth(1).unwrap_or_default());
+        });
+
+        let logs = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(logs.lines().count(), 10, "{}", logs);
+        for pii in FIXTURE_PII {
+            assert!(!logs.contains(pii), "log contains {:?}:\n{}", pii, logs);
+        }
+    }
+}
@@ -6,15 +6,15 @@ use tokio::time::{Duration, Instant};
 use tracing::{error, info, warn};
 use fastcrypto::{ed25519::Ed25519KeyPair, traits::Signer};
 use chrono::DateTime;
-use hex;
 use base64::{Engine as _, engine::general_purpose};
-use reqwest;
-use serde_json;
 use std::collections::HashMap;
-
-// DID type constants (matching your Move contract)
-const DID_AGE_VERIFY: u8 = 1;        // Contract value for age verification
-const DID_CITIZENSHIP_VERIFY: u8 = 2; // Contract value for citizenship verification
+use attestation_server::common::IntentScope;
+use attestation_server::did_nft::{signing_payload, DidIssuance};
+use attestation_server::evidence::{EvidenceStore, IssuedEvidence, DID_AGE_VERIFY, DID_CITIZENSHIP_VERIFY};
+use attestation_server::metrics::metrics;
+use attestation_server::rules::check_document_supports;
+use attestation_server::wallet::normalize_sui_address;
+use std::sync::Arc;
 
 // Redis message structure from your verification service
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -76,58 +76,23 @@ where
     deserializer.deserialize_any(StringToU8Visitor)
 }
 
-// Throughput tracker
-#[derive(Debug)]
-pub struct ThroughputTracker {
-    total_messages: u64,
-    start_time: Instant,
-    last_report_time: Instant,
-}
-
-impl ThroughputTracker {
-    pub fn new() -> Self {
-        let now = Instant::now();
-        Self {
-            total_messages: 0,
-            start_time: now,
-            last_report_time: now,
-        }
-    }
-
-    pub fn record_message(&mut self) {
-        self.total_messages += 1;
-    }
-
-    pub fn get_throughput(&self) -> f64 {
-        let elapsed = self.start_time.elapsed().as_secs_f64();
-        if elapsed > 0.0 {
-            self.total_messages as f64 / elapsed
-        } else {
-            0.0
-        }
-    }
-
-    pub fn maybe_report(&mut self, interval_secs: u64) -> bool {
-        let elapsed = self.last_report_time.elapsed();
-        
-        if elapsed >= Duration::from_secs(interval_secs) {
-            let throughput = self.get_throughput();
-            info!("THROUGHPUT: {:.1} messages/sec (total: {})", throughput, self.total_messages);
-            self.last_report_time = Instant::now();
-            true
-        } else {
-            false
-        }
-    }
-}
-
 pub struct RedisSuiProcessor {
     keypair: Ed25519KeyPair,
+    /// Evidence recorded by the enclave's KYC handler
+    evidence: Arc<EvidenceStore>,
     redis_client: Client,
     stream_name: String,
     consumer_group: String,
     consumer_name: String,
-    throughput_tracker: ThroughputTracker,
+    dead_letter_stream: String,
+    /// Hash of message ID -> UserDID created for it, so a retried message
+    /// resumes at `update_verification_status` instead of starting again
+    started_dids_key: String,
+    /// Deliveries (first read plus retries) before a retryable failure is dead-lettered
+    max_deliveries: u64,
+    /// How long a failed message stays pending before it is reclaimed
+    retry_idle_ms: u64,
+    last_backlog_sample: Instant,
     // Sui contract parameters
     package_id: String,
     registry_id: String,
@@ -136,9 +101,9 @@ pub struct RedisSuiProcessor {
 }
 
 impl RedisSuiProcessor {
-    const REPORT_INTERVAL_SECS: u64 = 10;
+    const BACKLOG_SAMPLE_INTERVAL_SECS: u64 = 10;
 
-    pub fn new(keypair: Ed25519KeyPair) -> Result<Self> {
+    pub fn new(keypair: Ed25519KeyPair, evidence: Arc<EvidenceStore>) -> Result<Self> {
         // Redis configuration from .env files (no secrets.json)
         // Priority: .env file values > defaults (no external secrets)
         let redis_url = std::env::var("REDIS_URL")
@@ -156,16 +121,32 @@ impl RedisSuiProcessor {
         let client = Client::open(redis_url.as_str())
             .map_err(|e| anyhow!("Failed to create Redis client: {}", e))?;
 
+        let stream_name = std::env::var("REDIS_STREAM_NAME")
+            .unwrap_or_else(|_| "verification_stream".to_string());
+
         Ok(RedisSuiProcessor {
             keypair,
+            evidence,
             redis_client: client,
-            stream_name: std::env::var("REDIS_STREAM_NAME")
-                .unwrap_or_else(|_| "verification_stream".to_string()),
+            dead_letter_stream: std::env::var("REDIS_DEAD_LETTER_STREAM")
+                .unwrap_or_else(|_| format!("{}_dead_letter", stream_name)),
+            started_dids_key: std::env::var("REDIS_STARTED_DIDS_KEY")
+                .unwrap_or_else(|_| format!("{}_started_dids", stream_name)),
+            max_deliveries: std::env::var("REDIS_MAX_DELIVERIES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n: &u64| n > 0)
+                .unwrap_or(5),
+            retry_idle_ms: std::env::var("REDIS_RETRY_IDLE_MS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(30_000),
+            stream_name,
             consumer_group: std::env::var("REDIS_CONSUMER_GROUP")
                 .unwrap_or_else(|_| "attestation_processors".to_string()),
             consumer_name: std::env::var("REDIS_CONSUMER_NAME")
                 .unwrap_or_else(|_| "rust_processor_1".to_string()),
-            throughput_tracker: ThroughputTracker::new(),
+            last_backlog_sample: Instant::now(),
             package_id: std::env::var("SUI_PACKAGE_ID")
                 .unwrap_or_else(|_| "0x6ec40d30e636afb906e621748ee60a9b72bc59a39325adda43deadd28dc89e09".to_string()),
             registry_id: std::env::var("SUI_REGISTRY_ID")
@@ -186,6 +167,8 @@ impl RedisSuiProcessor {
         info!("   Stream: {}", self.stream_name);
         info!("   Consumer Group: {}", self.consumer_group);
         info!("   Consumer Name: {}", self.consumer_name);
+        info!("   Dead Letter Stream: {}", self.dead_letter_stream);
+        info!("   Max Deliveries: {} (retry after {} ms idle)", self.max_deliveries, self.retry_idle_ms);
         
         // Test sui client configuration (via host proxy)
         self.test_sui_host_proxy().await?;
@@ -281,7 +264,10 @@ impl RedisSuiProcessor {
     async fn consume_messages(&mut self) -> Result<usize> {
         let mut con = self.redis_client.get_connection()
             .map_err(|e| anyhow!("Failed to get Redis connection: {}", e))?;
-        
+
+        // Messages whose last attempt failed with a retryable error go first
+        let mut message_count = self.retry_pending(&mut con).await?;
+
         // Use raw Redis command for XREADGROUP - more compatible with older redis-rs versions
         let result: RedisResult<redis::Value> = redis::cmd("XREADGROUP")
             .arg("GROUP")
@@ -298,85 +284,280 @@ impl RedisSuiProcessor {
         
         match result {
             Ok(redis::Value::Bulk(streams)) => {
-                let mut message_count = 0;
-                
                 for stream in streams {
                     if let redis::Value::Bulk(stream_data) = stream {
-                        if stream_data.len() >= 2 {
-                            // stream_data[0] is stream name, stream_data[1] is messages
-                            if let redis::Value::Bulk(messages) = &stream_data[1] {
-                                for message in messages {
-                                    if let redis::Value::Bulk(msg_data) = message {
-                                        if msg_data.len() >= 2 {
-                                            // msg_data[0] is message ID, msg_data[1] is fields
-                                            let message_id = redis::from_redis_value::<String>(&msg_data[0])?;
-                                            
-                                            if let redis::Value::Bulk(fields) = &msg_data[1] {
-                                                let mut field_map = std::collections::HashMap::new();
-                                                
-                                                // Parse field-value pairs
-                                                for i in (0..fields.len()).step_by(2) {
-                                                    if i + 1 < fields.len() {
-                                                        let field_name = redis::from_redis_value::<String>(&fields[i])?;
-                                                        let field_value = fields[i + 1].clone();
-                                                        field_map.insert(field_name, field_value);
-                                                    }
-                                                }
-                                                
-                                                message_count += 1;
-                                                self.throughput_tracker.record_message();
-                                                
-                                                info!("Processing message ID: {}", message_id);
-                                                
-                                                match self.process_redis_message(&message_id, &field_map).await {
-                                                    Ok(_) => {
-                                                        // Acknowledge the message
-                                                        let _: RedisResult<i32> = redis::cmd("XACK")
-                                                            .arg(&self.stream_name)
-                                                            .arg(&self.consumer_group)
-                                                            .arg(&message_id)
-                                                            .query(&mut con);
-                                                        info!("✅ Message {} processed and acknowledged", message_id);
-                                                    }
-                                                    Err(e) => {
-                                                        error!("Failed to process message {}: {}", message_id, e);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
+                        // stream_data[0] is stream name, stream_data[1] is messages
+                        if let Some(messages) = stream_data.get(1) {
+                            for (message_id, field_map) in parse_entries(messages)? {
+                                message_count += 1;
+                                self.handle_message(&mut con, &message_id, &field_map, 1).await;
                             }
                         }
                     }
                 }
                 
-                // Report throughput
-                self.throughput_tracker.maybe_report(Self::REPORT_INTERVAL_SECS);
+                self.maybe_sample_backlog(&mut con);
                 
                 Ok(message_count)
             }
             Ok(redis::Value::Nil) => {
                 // No messages available
-                Ok(0)
+                self.maybe_sample_backlog(&mut con);
+                Ok(message_count)
             }
             Err(e) => {
                 if e.to_string().contains("NOGROUP") {
                     warn!("Consumer group doesn't exist, recreating...");
                     self.create_consumer_group().await;
-                    return Ok(0);
+                    return Ok(message_count);
                 }
                 Err(anyhow!("Failed to read from Redis stream: {}", e))
             }
             Ok(other) => {
                 warn!("Unexpected Redis response type: {:?}", other);
-                Ok(0)
+                Ok(message_count)
             }
         }
     }
 
-    async fn process_redis_message(&mut self, message_id: &str, fields: &HashMap<String, Value>) -> Result<()> {
-        info!("Processing Redis message {}: {:?}", message_id, fields);
+    /// Process one delivery. Success is acknowledged; a permanent failure, or
+    /// a retryable one on its last allowed delivery, is dead-lettered; any
+    /// other failure stays pending for `retry_pending` to reclaim.
+    async fn handle_message(
+        &mut self,
+        con: &mut redis::Connection,
+        message_id: &str,
+        field_map: &HashMap<String, Value>,
+        deliveries: u64,
+    ) {
+        let (did_label, result_label) = message_labels(field_map);
+        metrics().record_stream_message(&did_label, &result_label, if deliveries == 1 { "consumed" } else { "redelivered" });
+
+        info!("Processing message ID: {} (delivery {})", message_id, deliveries);
+
+        match self.process_redis_message(con, message_id, field_map).await {
+            Ok(_) => {
+                self.forget_started_did(con, message_id);
+                // Acknowledge the message
+                let ack: RedisResult<i32> = redis::cmd("XACK")
+                    .arg(&self.stream_name)
+                    .arg(&self.consumer_group)
+                    .arg(message_id)
+                    .query(con);
+                if ack.is_ok() {
+                    metrics().record_stream_message(&did_label, &result_label, "acked");
+                }
+                info!("✅ Message {} processed and acknowledged", message_id);
+            }
+            Err(e) => {
+                error!("Failed to process message {}: {}", message_id, e);
+                metrics().record_stream_message(&did_label, &result_label, "failed");
+
+                let permanent = e.downcast_ref::<PermanentFailure>().is_some();
+                if !permanent && deliveries < self.max_deliveries {
+                    warn!(
+                        "Message {} will be retried after {} ms (delivery {} of {})",
+                        message_id, self.retry_idle_ms, deliveries, self.max_deliveries
+                    );
+                    return;
+                }
+
+                match self.dead_letter(con, message_id, field_map, &e, deliveries) {
+                    Ok(()) => {
+                        self.forget_started_did(con, message_id);
+                        metrics().record_stream_message(&did_label, &result_label, "dead_lettered");
+                        warn!("Message {} moved to dead letter stream '{}'", message_id, self.dead_letter_stream);
+                    }
+                    Err(dl_err) => {
+                        error!("Failed to dead-letter message {}: {}", message_id, dl_err);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reclaim messages left pending for at least `retry_idle_ms` (by this or
+    /// a crashed consumer) with XCLAIM and process them again.
+    async fn retry_pending(&mut self, con: &mut redis::Connection) -> Result<usize> {
+        // Extended XPENDING form (Redis 6.2+): [id, consumer, idle_ms, deliveries]
+        let pending: RedisResult<Value> = redis::cmd("XPENDING")
+            .arg(&self.stream_name)
+            .arg(&self.consumer_group)
+            .arg("IDLE")
+            .arg(self.retry_idle_ms)
+            .arg("-")
+            .arg("+")
+            .arg(10)
+            .query(con);
+        let entries = match pending {
+            Ok(Value::Bulk(entries)) => entries,
+            Ok(_) => return Ok(0),
+            Err(e) if e.to_string().contains("NOGROUP") => return Ok(0),
+            Err(e) => return Err(anyhow!("XPENDING failed: {}", e)),
+        };
+
+        let mut retried = 0;
+        for entry in entries {
+            let Value::Bulk(entry) = entry else { continue };
+            let (Some(id), Some(deliveries)) = (entry.first(), entry.get(3)) else { continue };
+            let message_id = redis::from_redis_value::<String>(id)?;
+            let deliveries = redis::from_redis_value::<u64>(deliveries)?;
+
+            // XCLAIM bumps the delivery count and only succeeds for one consumer
+            let claimed: Value = redis::cmd("XCLAIM")
+                .arg(&self.stream_name)
+                .arg(&self.consumer_group)
+                .arg(&self.consumer_name)
+                .arg(self.retry_idle_ms)
+                .arg(&message_id)
+                .query(con)
+                .map_err(|e| anyhow!("XCLAIM failed: {}", e))?;
+            let claimed = parse_entries(&claimed)?;
+            if claimed.is_empty() {
+                // Either another consumer claimed it first, in which case it is
+                // theirs to finish, or it was trimmed and can never be processed
+                let remaining: Value = redis::cmd("XRANGE")
+                    .arg(&self.stream_name)
+                    .arg(&message_id)
+                    .arg(&message_id)
+                    .query(con)
+                    .map_err(|e| anyhow!("XRANGE failed: {}", e))?;
+                if matches!(&remaining, Value::Bulk(entries) if entries.is_empty()) {
+                    warn!("Pending message {} was deleted from the stream; acknowledging it", message_id);
+                    let _: RedisResult<i32> = redis::cmd("XACK")
+                        .arg(&self.stream_name)
+                        .arg(&self.consumer_group)
+                        .arg(&message_id)
+                        .query(con);
+                    self.forget_started_did(con, &message_id);
+                }
+                continue;
+            }
+            for (message_id, field_map) in claimed {
+                retried += 1;
+                self.handle_message(con, &message_id, &field_map, deliveries + 1).await;
+            }
+        }
+        Ok(retried)
+    }
+
+    /// Copy a failed message to the dead letter stream with the failure reason and
+    /// acknowledge it so it does not sit in the pending list forever.
+    fn dead_letter(
+        &self,
+        con: &mut redis::Connection,
+        message_id: &str,
+        fields: &HashMap<String, Value>,
+        error: &anyhow::Error,
+        deliveries: u64,
+    ) -> Result<()> {
+        let mut cmd = redis::cmd("XADD");
+        cmd.arg(&self.dead_letter_stream).arg("*");
+        for (name, value) in fields {
+            if let Ok(bytes) = redis::from_redis_value::<Vec<u8>>(value) {
+                cmd.arg(name).arg(bytes);
+            }
+        }
+        cmd.arg("original_id").arg(message_id)
+            .arg("error").arg(error.to_string())
+            .arg("deliveries").arg(deliveries);
+
+        let _: String = cmd.query(con)
+            .map_err(|e| anyhow!("XADD to dead letter stream failed: {}", e))?;
+        let _: i32 = redis::cmd("XACK")
+            .arg(&self.stream_name)
+            .arg(&self.consumer_group)
+            .arg(message_id)
+            .query(con)
+            .map_err(|e| anyhow!("XACK after dead-lettering failed: {}", e))?;
+
+        Ok(())
+    }
+
+    /// UserDID an earlier delivery of this message created.
+    fn started_did(&self, con: &mut redis::Connection, message_id: &str) -> Result<Option<String>> {
+        redis::cmd("HGET")
+            .arg(&self.started_dids_key)
+            .arg(message_id)
+            .query(con)
+            .map_err(|e| anyhow!("Failed to look up UserDID for message {}: {}", message_id, e))
+    }
+
+    fn record_started_did(&self, con: &mut redis::Connection, message_id: &str, user_did_id: &str) -> Result<()> {
+        let _: i32 = redis::cmd("HSET")
+            .arg(&self.started_dids_key)
+            .arg(message_id)
+            .arg(user_did_id)
+            .query(con)
+            .map_err(|e| anyhow!("Failed to record UserDID {} for message {}: {}", user_did_id, message_id, e))?;
+        Ok(())
+    }
+
+    /// Drop the record once the message is acknowledged.
+    fn forget_started_did(&self, con: &mut redis::Connection, message_id: &str) {
+        let removed: RedisResult<i32> = redis::cmd("HDEL")
+            .arg(&self.started_dids_key)
+            .arg(message_id)
+            .query(con);
+        if let Err(e) = removed {
+            warn!("Failed to clear UserDID record for message {}: {}", message_id, e);
+        }
+    }
+
+    /// Update the stream lag and pending gauges from `XINFO GROUPS`.
+    fn maybe_sample_backlog(&mut self, con: &mut redis::Connection) {
+        if self.last_backlog_sample.elapsed() < Duration::from_secs(Self::BACKLOG_SAMPLE_INTERVAL_SECS) {
+            return;
+        }
+        self.last_backlog_sample = Instant::now();
+
+        let groups: RedisResult<Value> = redis::cmd("XINFO")
+            .arg("GROUPS")
+            .arg(&self.stream_name)
+            .query(con);
+
+        match groups {
+            Ok(Value::Bulk(groups)) => {
+                for group in groups {
+                    let Value::Bulk(entries) = group else { continue };
+                    let info: HashMap<String, Value> = entries
+                        .chunks(2)
+                        .filter_map(|pair| match pair {
+                            [key, value] => redis::from_redis_value::<String>(key)
+                                .ok()
+                                .map(|key| (key, value.clone())),
+                            _ => None,
+                        })
+                        .collect();
+
+                    let name = info.get("name")
+                        .and_then(|v| redis::from_redis_value::<String>(v).ok());
+                    if name.as_deref() != Some(self.consumer_group.as_str()) {
+                        continue;
+                    }
+
+                    if let Some(pending) = info.get("pending").and_then(|v| redis::from_redis_value::<i64>(v).ok()) {
+                        metrics().stream_pending.set(pending);
+                    }
+                    // `lag` is only reported by Redis 7+ and may be nil when unknown
+                    if let Some(lag) = info.get("lag").and_then(|v| redis::from_redis_value::<i64>(v).ok()) {
+                        metrics().stream_lag.set(lag);
+                    }
+                }
+            }
+            Ok(other) => warn!("Unexpected XINFO GROUPS response: {:?}", other),
+            Err(e) => warn!("Failed to sample stream backlog: {}", e),
+        }
+    }
+
+    async fn process_redis_message(
+        &mut self,
+        con: &mut redis::Connection,
+        message_id: &str,
+        fields: &HashMap<String, Value>,
+    ) -> Result<()> {
+        // Field values carry wallet addresses and hashes; log only the shape
+        info!("Processing Redis message {} with fields {:?}", message_id, fields.keys().collect::<Vec<_>>());
 
         // Helper function to extract string from Redis Value
         let get_string_field = |field_name: &str| -> Result<String> {
@@ -389,32 +570,60 @@ impl RedisSuiProcessor {
             }
         };
 
-        // Convert HashMap to VerificationMessage
-        let verification = VerificationMessage {
-            user_wallet: get_string_field("user_wallet")?,
-            did_id: get_string_field("did_id")?
-                .parse::<u8>()
-                .map_err(|e| anyhow!("Invalid did_id: {}", e))?,
-            result: get_string_field("result")?,
-            evidence_hash: get_string_field("evidence_hash")?,
-            verified_at: get_string_field("verified_at")?,
+        // Convert HashMap to VerificationMessage; a malformed message never succeeds
+        let parse = || -> Result<VerificationMessage> {
+            Ok(VerificationMessage {
+                user_wallet: get_string_field("user_wallet")?,
+                did_id: get_string_field("did_id")?
+                    .parse::<u8>()
+                    .map_err(|e| anyhow!("Invalid did_id: {}", e))?,
+                result: get_string_field("result")?,
+                evidence_hash: get_string_field("evidence_hash")?,
+                verified_at: get_string_field("verified_at")?,
+            })
         };
+        let verification = parse().map_err(permanent)?;
+        contract_did_type(verification.did_id).map_err(permanent)?;
         
         info!("User: {}, DID: {}, Result: {}", 
               verification.user_wallet, verification.did_id, verification.result);
         
-        // Process the verification
-        if let Some(user_did_id) = self.execute_start_verification(
-            &verification.user_wallet,
-            verification.did_id,
-        ).await? {
-            info!("UserDID created successfully: {}", user_did_id);
+        // Refuse to issue a DID type the verified evidence cannot back, before
+        // anything is written on-chain
+        let issued = if verification.result == "verified" {
+            Some(self.backing_evidence(&verification).map_err(permanent)?)
+        } else {
+            None
+        };
+        let signature_timestamp_ms = self
+            .parse_timestamp_to_ms(&verification.verified_at)
+            .map_err(permanent)?;
+
+        // Process the verification; start_verification aborts with
+        // EAlreadyHasDID on a second call, so a retry resumes with its UserDID
+        let user_did_id = match self.started_did(con, message_id)? {
+            Some(user_did_id) => {
+                info!("Resuming message {} with UserDID {}", message_id, user_did_id);
+                Some(user_did_id)
+            }
+            None => {
+                let user_did_id = self.execute_start_verification(
+                    &verification.user_wallet,
+                    verification.did_id,
+                ).await?;
+                if let Some(user_did_id) = &user_did_id {
+                    info!("UserDID created successfully: {}", user_did_id);
+                    self.record_started_did(con, message_id, user_did_id)?;
+                }
+                user_did_id
+            }
+        };
+        if let Some(user_did_id) = user_did_id {
             
-            if verification.result == "verified" {
+            if let Some(issued) = issued {
                 info!("Processing verified result - calling update_verification_status");
                 
-                let signature = self.generate_nautilus_signature(&verification)?;
-                let signature_timestamp_ms = self.parse_timestamp_to_ms(&verification.verified_at)?;
+                let signature = self.generate_nautilus_signature(&verification, &issued, signature_timestamp_ms)?;
                 
                 self.execute_update_verification_status(
                     &verification.user_wallet,
@@ -422,8 +631,14 @@ impl RedisSuiProcessor {
                     true,
                     signature,
                     signature_timestamp_ms,
-                    &verification.evidence_hash,
+                    &issued.evidence_hash,
                 ).await?;
+                
+                let confirmed_ms = chrono::Utc::now().timestamp_millis() as u64;
+                metrics()
+                    .end_to_end_latency
+                    .with_label_values(&[&verification.did_id.to_string()])
+                    .observe(confirmed_ms.saturating_sub(signature_timestamp_ms) as f64 / 1000.0);
             } else {
                 info!("Skipping update for non-verified result: {}", verification.result);
             }
@@ -503,17 +718,7 @@ impl RedisSuiProcessor {
     ) -> Result<Option<String>> {
         info!("Executing start_verification transaction...");
         
-        // Map Redis DID ID to contract DID type:
-        // Redis 0 → Contract 1 (DID_AGE_VERIFY)
-        // Redis 1 → Contract 2 (DID_CITIZENSHIP_VERIFY)
-        let contract_did_type = match redis_did_id {
-            0 => DID_AGE_VERIFY,        // Age verification
-            1 => DID_CITIZENSHIP_VERIFY, // Citizenship verification
-            _ => {
-                warn!("Unknown DID ID from Redis: {}, defaulting to age verification", redis_did_id);
-                DID_AGE_VERIFY
-            }
-        };
+        let contract_did_type = contract_did_type(redis_did_id)?;
         
         info!("Mapping: Redis DID {} → Contract DID {}", redis_did_id, contract_did_type);
         
@@ -533,6 +738,7 @@ impl RedisSuiProcessor {
             "gas_budget": "10000000"
         });
 
+        let started = Instant::now();
         let response = client
             .post("http://localhost:9999/sui/client/call")
             .json(&call_data)
@@ -545,8 +751,16 @@ impl RedisSuiProcessor {
             .await
             .map_err(|e| anyhow!("Failed to parse response: {}", e))?;
 
+        let success = result["success"].as_bool().unwrap_or(false);
+        metrics().record_transaction(
+            "start_verification",
+            success,
+            started.elapsed(),
+            result["stdout"].as_str().and_then(parse_gas_used),
+        );
+
         // Process the output
-        if result["success"].as_bool().unwrap_or(false) {
+        if success {
             info!("start_verification executed successfully for user: {}", user_address);
             let output_str = result["stdout"].as_str().unwrap_or("");
             info!("Output: {}", output_str);
@@ -580,22 +794,61 @@ impl RedisSuiProcessor {
         Ok(None)
     }
 
-    fn generate_nautilus_signature(&self, verification: &VerificationMessage) -> Result<Vec<u8>> {
-        // Create a payload to sign (this should match your verification format)
-        let payload = format!(
-            "{}:{}:{}:{}:{}",
-            verification.user_wallet,
-            verification.did_id,
-            verification.result,
-            verification.evidence_hash,
-            verification.verified_at
+    /// Verified evidence on file for this wallet and DID type, checked against
+    /// the document class rules.
+    fn backing_evidence(&self, verification: &VerificationMessage) -> Result<IssuedEvidence> {
+        let did_type = contract_did_type(verification.did_id)?;
+        let wallet = normalize_sui_address(&verification.user_wallet)
+            .map_err(|e| anyhow!("Invalid user_wallet: {}", e))?;
+        let issued = self.evidence.issued(&wallet, did_type).ok_or_else(|| {
+            anyhow!("No verified evidence for {} and DID type {}", wallet, did_type)
+        })?;
+        // The commitment is computed by the enclave; the message only names it
+        if !verification.evidence_hash.eq_ignore_ascii_case(&issued.evidence_hash) {
+            return Err(anyhow!(
+                "Evidence hash {} does not match the enclave commitment {} for {}",
+                verification.evidence_hash,
+                issued.evidence_hash,
+                wallet
+            ));
+        }
+        check_document_supports(issued.document_class, issued.issuer_signed, did_type)
+            .map_err(|e| anyhow!("Refusing to issue DID type {}: {}", did_type, e))?;
+        info!(
+            "DID type {} for {} backed by {} evidence {}",
+            did_type,
+            wallet,
+            issued.document_class.as_str(),
+            issued.evidence_hash
         );
-        
+        Ok(issued)
+    }
+
+    /// Sign the owner, contract DID type and evidence commitment as stored on
+    /// the NFT, so that `did_registry::verify_nft_signature` and
+    /// `did_nft::DidNftVerifier` can check it from the NFT's fields. The
+    /// commitment also binds the document class and nullifier.
+    fn generate_nautilus_signature(
+        &self,
+        verification: &VerificationMessage,
+        issued: &IssuedEvidence,
+        signature_timestamp_ms: u64,
+    ) -> Result<Vec<u8>> {
+        let evidence_hash = hex::decode(&issued.evidence_hash)
+            .map_err(|e| anyhow!("Failed to decode evidence hash: {}", e))?;
+        let issuance = DidIssuance::new(
+            &verification.user_wallet,
+            contract_did_type(verification.did_id)?,
+            &evidence_hash,
+        )?;
+        let payload = signing_payload(signature_timestamp_ms, &issuance);
+
         // Sign the payload with the enclave keypair
-        let signature = self.keypair.sign(payload.as_bytes());
-        
+        let signature = self.keypair.sign(&payload);
+        metrics().record_signing(IntentScope::DIDIssuance.as_str());
+
         info!("Generated Nautilus signature for user: {}", verification.user_wallet);
-        
+
         Ok(signature.as_ref().to_vec())
     }
 
@@ -626,7 +879,7 @@ impl RedisSuiProcessor {
         verified: bool,
         nautilus_signature: Vec<u8>,
         signature_timestamp_ms: u64,  // When signature was created
-        evidence_hash: &str,          // OCR hash from Python
+        evidence_hash: &str,          // Enclave evidence commitment
     ) -> Result<()> {
         info!("Executing update_verification_status transaction...");
         info!("Signature timestamp: {}", signature_timestamp_ms);
@@ -661,6 +914,7 @@ impl RedisSuiProcessor {
             "gas_budget": "10000000"
         });
 
+        let started = Instant::now();
         let response = client
             .post("http://localhost:9999/sui/client/call")
             .json(&call_data)
@@ -673,8 +927,16 @@ impl RedisSuiProcessor {
             .await
             .map_err(|e| anyhow!("Failed to parse response: {}", e))?;
 
+        let success = result["success"].as_bool().unwrap_or(false);
+        metrics().record_transaction(
+            "update_verification_status",
+            success,
+            started.elapsed(),
+            result["stdout"].as_str().and_then(parse_gas_used),
+        );
+
         // Process the output
-        if result["success"].as_bool().unwrap_or(false) {
+        if success {
             info!("update_verification_status executed successfully for user: {}", user_address);
             let output_str = result["stdout"].as_str().unwrap_or("");
             info!("Output: {}", output_str);
@@ -701,6 +963,92 @@ impl RedisSuiProcessor {
     }
 }
 
+/// Map a Redis DID ID to the contract DID type (Redis 0 → age, 1 → citizenship).
+fn contract_did_type(redis_did_id: u8) -> Result<u8> {
+    match redis_did_id {
+        0 => Ok(DID_AGE_VERIFY),
+        1 => Ok(DID_CITIZENSHIP_VERIFY),
+        other => Err(anyhow!("Unknown DID ID from Redis: {}", other)),
+    }
+}
+
+/// Stream entries `[[id, [field, value, ...]], ...]` as returned by XREADGROUP
+/// and XCLAIM. Entries deleted from the stream (nil fields) are skipped.
+fn parse_entries(value: &Value) -> Result<Vec<(String, HashMap<String, Value>)>> {
+    let Value::Bulk(messages) = value else { return Ok(Vec::new()) };
+    let mut entries = Vec::new();
+    for message in messages {
+        let Value::Bulk(msg_data) = message else { continue };
+        // msg_data[0] is message ID, msg_data[1] is fields
+        let (Some(id), Some(Value::Bulk(fields))) = (msg_data.first(), msg_data.get(1)) else { continue };
+        let message_id = redis::from_redis_value::<String>(id)?;
+        let mut field_map = HashMap::new();
+        // Parse field-value pairs
+        for pair in fields.chunks(2) {
+            if let [name, value] = pair {
+                field_map.insert(redis::from_redis_value::<String>(name)?, value.clone());
+            }
+        }
+        entries.push((message_id, field_map));
+    }
+    Ok(entries)
+}
+
+/// A message that can never succeed (malformed, or not backed by enclave
+/// evidence): dead-lettered on the first delivery instead of retried.
+#[derive(Debug)]
+struct PermanentFailure(String);
+
+impl std::fmt::Display for PermanentFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for PermanentFailure {}
+
+fn permanent(e: anyhow::Error) -> anyhow::Error {
+    anyhow::Error::new(PermanentFailure(e.to_string()))
+}
+
+/// Metric labels for a raw stream message: (did_id, result). Unknown values are
+/// collapsed so a malformed producer cannot blow up label cardinality.
+fn message_labels(fields: &HashMap<String, Value>) -> (String, String) {
+    let field = |name: &str| fields.get(name)
+        .and_then(|v| redis::from_redis_value::<String>(v).ok());
+
+    let did_label = match field("did_id").and_then(|v| v.parse::<u8>().ok()) {
+        Some(did) if did <= 1 => did.to_string(),
+        _ => "unknown".to_string(),
+    };
+    let result_label = match field("result").as_deref() {
+        Some("verified") => "verified".to_string(),
+        Some("rejected") => "rejected".to_string(),
+        Some(_) => "other".to_string(),
+        None => "unknown".to_string(),
+    };
+
+    (did_label, result_label)
+}
+
+/// Net gas used from the `Gas Cost Summary` block of Sui CLI output:
+/// computation + storage - storage rebate, in MIST.
+fn parse_gas_used(output: &str) -> Option<u64> {
+    let cost = |label: &str| -> Option<i128> {
+        output.lines()
+            .find(|line| line.contains(label))
+            .and_then(|line| line.split(':').nth(1))
+            .and_then(|value| value.split_whitespace().next())
+            .and_then(|value| value.parse::<i128>().ok())
+    };
+
+    let computation = cost("Computation Cost")?;
+    let storage = cost("Storage Cost")?;
+    let rebate = cost("Storage Rebate").unwrap_or(0);
+
+    u64::try_from((computation + storage - rebate).max(0)).ok()
+}
+
 /// Extract UserDID object ID from Sui transaction output
 fn extract_user_did_id(output: &str) -> Option<String> {
     let lines: Vec<&str> = output.lines().collect();
@@ -722,8 +1070,7 @@ fn extract_user_did_id(output: &str) -> Option<String> {
                 };
                 
                 // Look ahead for ObjectType line to check if this is a UserDID
-                for j in (i+1)..(i+5).min(lines.len()) {
-                    let next_line = lines[j];
+                for next_line in lines.iter().take((i+5).min(lines.len())).skip(i+1) {
                     if next_line.contains("ObjectType:") && next_line.contains("::did_registry::UserDID") {
                         info!("Found UserDID object: {}", object_id);
                         return Some(object_id.to_string());
@@ -743,10 +1090,45 @@ fn extract_user_did_id(output: &str) -> Option<String> {
 }
 
 // Function to start the Redis-Sui processor as a background task
-pub async fn start_redis_sui_processor(keypair: Ed25519KeyPair) -> Result<()> {
+pub async fn start_redis_sui_processor(keypair: Ed25519KeyPair, evidence: Arc<EvidenceStore>) -> Result<()> {
     info!("Starting Redis-Sui processor...");
     
-    let mut processor = RedisSuiProcessor::new(keypair)?;
+    let mut processor = RedisSuiProcessor::new(keypair, evidence)?;
     
     processor.start_processing().await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bulk(values: Vec<Value>) -> Value {
+        Value::Bulk(values)
+    }
+
+    fn data(s: &str) -> Value {
+        Value::Data(s.as_bytes().to_vec())
+    }
+
+    #[test]
+    fn parses_claimed_entries_and_skips_deleted_ones() {
+        let claimed = bulk(vec![
+            bulk(vec![data("1-0"), bulk(vec![data("did_id"), data("0"), data("result"), data("verified")])]),
+            // XCLAIM returns nil fields for entries trimmed from the stream
+            bulk(vec![data("2-0"), Value::Nil]),
+        ]);
+        let entries = parse_entries(&claimed).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, "1-0");
+        assert_eq!(message_labels(&entries[0].1), ("0".to_string(), "verified".to_string()));
+    }
+
+    #[test]
+    fn permanent_failures_are_distinguishable() {
+        let malformed = permanent(anyhow!("Missing field: did_id"));
+        assert!(malformed.downcast_ref::<PermanentFailure>().is_some());
+        assert_eq!(malformed.to_string(), "Missing field: did_id");
+        assert!(anyhow!("Failed to connect to Sui proxy").downcast_ref::<PermanentFailure>().is_none());
+        assert!(contract_did_type(2).is_err());
+    }
+}
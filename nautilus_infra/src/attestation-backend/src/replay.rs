@@ -0,0 +1,163 @@
+// replay.rs
+use crate::EnclaveError;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Accepted client nonce lengths (after trimming), in characters.
+const MIN_NONCE_LEN: usize = 16;
+const MAX_NONCE_LEN: usize = 128;
+
+/// Freshness window and nonce store sizing for KYC submissions.
+#[derive(Debug, Clone)]
+pub struct ReplayConfig {
+    /// Maximum difference between the request timestamp and enclave time.
+    pub max_skew: Duration,
+    /// Maximum number of nonces remembered at once.
+    pub capacity: usize,
+}
+
+impl ReplayConfig {
+    /// Load from `KYC_MAX_SKEW_SECS` and `KYC_NONCE_CAPACITY`.
+    pub fn from_env() -> Self {
+        let env_u64 = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            max_skew: Duration::from_secs(env_u64("KYC_MAX_SKEW_SECS").unwrap_or(300)),
+            capacity: env_u64("KYC_NONCE_CAPACITY").unwrap_or(100_000) as usize,
+        }
+    }
+}
+
+/// Remembers nonces seen within the freshness window. A nonce only needs to be
+/// kept for twice the skew window: after that its timestamp is rejected anyway.
+pub struct NonceStore {
+    config: ReplayConfig,
+    inner: Mutex<NonceState>,
+}
+
+#[derive(Default)]
+struct NonceState {
+    seen: HashSet<String>,
+    order: VecDeque<(Instant, String)>,
+}
+
+impl NonceStore {
+    pub fn new(config: ReplayConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(NonceState::default()),
+        }
+    }
+
+    fn retention(&self) -> Duration {
+        self.config.max_skew * 2
+    }
+
+    /// Check the nonce format and request timestamp without recording anything,
+    /// so stale requests can be rejected before their signature is checked.
+    pub fn validate(&self, nonce: &str, timestamp_ms: u64, now_ms: u64) -> Result<(), EnclaveError> {
+        if nonce.len() < MIN_NONCE_LEN || nonce.len() > MAX_NONCE_LEN {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Nonce must be {}-{} characters",
+                MIN_NONCE_LEN, MAX_NONCE_LEN
+            )));
+        }
+        if !nonce
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(EnclaveError::InvalidInput(
+                "Nonce must be hex or base64url".to_string(),
+            ));
+        }
+
+        let skew = now_ms.abs_diff(timestamp_ms);
+        if skew > self.config.max_skew.as_millis() as u64 {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Request timestamp is {} ms away from enclave time (max {:?})",
+                skew, self.config.max_skew
+            )));
+        }
+        Ok(())
+    }
+
+    /// Validate the request timestamp and nonce, and record the nonce so the
+    /// same request cannot be accepted twice. Call this only once the request
+    /// is authenticated, or anyone could burn nonces and fill the store.
+    pub fn check(&self, nonce: &str, timestamp_ms: u64, now_ms: u64) -> Result<(), EnclaveError> {
+        self.validate(nonce, timestamp_ms, now_ms)?;
+
+        let now = Instant::now();
+        let retention = self.retention();
+        let mut state = self.inner.lock().expect("nonce store poisoned");
+
+        while let Some((inserted, _)) = state.order.front() {
+            if now.duration_since(*inserted) < retention {
+                break;
+            }
+            if let Some((_, old)) = state.order.pop_front() {
+                state.seen.remove(&old);
+            }
+        }
+
+        if state.seen.contains(nonce) {
+            return Err(EnclaveError::InvalidInput(
+                "Nonce has already been used".to_string(),
+            ));
+        }
+
+        // Never evict a live nonce to make room: that would reopen a replay window.
+        if state.seen.len() >= self.config.capacity {
+            let retry_after = state
+                .order
+                .front()
+                .map(|(inserted, _)| retention.saturating_sub(now.duration_since(*inserted)))
+                .unwrap_or(retention);
+            return Err(EnclaveError::RateLimited {
+                message: "Replay protection store is full".to_string(),
+                retry_after_secs: retry_after.as_secs().max(1),
+            });
+        }
+
+        state.seen.insert(nonce.to_string());
+        state.order.push_back((now, nonce.to_string()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(capacity: usize) -> NonceStore {
+        NonceStore::new(ReplayConfig { max_skew: Duration::from_secs(300), capacity })
+    }
+
+    #[test]
+    fn validate_does_not_record() {
+        let nonces = store(1);
+        for _ in 0..3 {
+            nonces.validate("nonce-0000000001", 1_000, 1_000).unwrap();
+        }
+        nonces.check("nonce-0000000001", 1_000, 1_000).unwrap();
+        assert!(nonces.check("nonce-0000000001", 1_000, 1_000).is_err());
+    }
+
+    #[test]
+    fn rejects_stale_and_malformed_nonces() {
+        let nonces = store(10);
+        assert!(nonces.validate("nonce-0000000001", 0, 301_000).is_err());
+        assert!(nonces.validate("short", 1_000, 1_000).is_err());
+        assert!(nonces.validate("nonce:0000000001", 1_000, 1_000).is_err());
+    }
+
+    #[test]
+    fn full_store_rate_limits_instead_of_evicting() {
+        let nonces = store(1);
+        nonces.check("nonce-0000000001", 1_000, 1_000).unwrap();
+        assert!(matches!(
+            nonces.check("nonce-0000000002", 1_000, 1_000),
+            Err(EnclaveError::RateLimited { .. })
+        ));
+    }
+}
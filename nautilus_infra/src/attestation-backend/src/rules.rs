@@ -0,0 +1,128 @@
+// rules.rs
+use crate::evidence::{DID_AGE_VERIFY, DID_CITIZENSHIP_VERIFY};
+use crate::EnclaveError;
+use serde::{Deserialize, Serialize};
+
+/// What a DID asserts about its holder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Claim {
+    /// Date of birth is known, so age predicates can be evaluated
+    Age,
+    /// Lives in India
+    Residency,
+    /// Is an Indian citizen
+    Citizenship,
+}
+
+/// Class of the verified document backing a claim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentClass {
+    Aadhaar,
+    IndianPassport,
+    ForeignPassport,
+    VoterId,
+    Pan,
+    DrivingLicence,
+    /// No issuer-signed document was verified
+    Unverified,
+}
+
+impl DocumentClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DocumentClass::Aadhaar => "aadhaar",
+            DocumentClass::IndianPassport => "indian_passport",
+            DocumentClass::ForeignPassport => "foreign_passport",
+            DocumentClass::VoterId => "voter_id",
+            DocumentClass::Pan => "pan",
+            DocumentClass::DrivingLicence => "driving_licence",
+            DocumentClass::Unverified => "unverified",
+        }
+    }
+
+    /// Claims a document of this class can back.
+    ///
+    /// Aadhaar is issued to anyone resident in India for 182 days, PAN to any
+    /// taxpayer and driving licences to residents, so none of them prove
+    /// citizenship. Voter IDs are only issued to citizens.
+    pub fn proves(&self) -> &'static [Claim] {
+        match self {
+            DocumentClass::Aadhaar => &[Claim::Age, Claim::Residency],
+            DocumentClass::IndianPassport => &[Claim::Age, Claim::Citizenship],
+            DocumentClass::ForeignPassport => &[Claim::Age],
+            DocumentClass::VoterId => &[Claim::Age, Claim::Residency, Claim::Citizenship],
+            DocumentClass::Pan => &[Claim::Age],
+            DocumentClass::DrivingLicence => &[Claim::Age, Claim::Residency],
+            DocumentClass::Unverified => &[],
+        }
+    }
+}
+
+/// The claim a DID type (as in did_registry.move) makes.
+pub fn claim_for_did_type(did_type: u8) -> Option<Claim> {
+    match did_type {
+        DID_AGE_VERIFY => Some(Claim::Age),
+        DID_CITIZENSHIP_VERIFY => Some(Claim::Citizenship),
+        _ => None,
+    }
+}
+
+/// Refuse DID types the presented document class cannot back. Only documents
+/// whose issuer signature was verified back any claim.
+pub fn check_document_supports(
+    class: DocumentClass,
+    issuer_signed: bool,
+    did_type: u8,
+) -> Result<(), EnclaveError> {
+    let claim = claim_for_did_type(did_type)
+        .ok_or_else(|| EnclaveError::InvalidInput(format!("Unknown DID type: {}", did_type)))?;
+    if !issuer_signed {
+        return Err(EnclaveError::VerificationFailed(format!(
+            "A document without a verified issuer signature cannot back DID type {}",
+            did_type
+        )));
+    }
+    if class.proves().contains(&claim) {
+        Ok(())
+    } else {
+        Err(EnclaveError::VerificationFailed(format!(
+            "A {} document cannot back a {:?} claim (DID type {})",
+            class.as_str(),
+            claim,
+            did_type
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_issuer_signed_documents_back_claims() {
+        check_document_supports(DocumentClass::IndianPassport, true, DID_CITIZENSHIP_VERIFY).unwrap();
+        check_document_supports(DocumentClass::Aadhaar, true, DID_AGE_VERIFY).unwrap();
+        for (class, did_type) in [
+            (DocumentClass::IndianPassport, DID_CITIZENSHIP_VERIFY),
+            (DocumentClass::Aadhaar, DID_AGE_VERIFY),
+        ] {
+            assert!(matches!(
+                check_document_supports(class, false, did_type),
+                Err(EnclaveError::VerificationFailed(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn class_limits_the_claims() {
+        assert!(check_document_supports(DocumentClass::Aadhaar, true, DID_CITIZENSHIP_VERIFY).is_err());
+        assert!(check_document_supports(DocumentClass::ForeignPassport, true, DID_CITIZENSHIP_VERIFY).is_err());
+        assert!(check_document_supports(DocumentClass::Unverified, true, DID_AGE_VERIFY).is_err());
+        assert!(matches!(
+            check_document_supports(DocumentClass::Aadhaar, true, 9),
+            Err(EnclaveError::InvalidInput(_))
+        ));
+    }
+}
@@ -0,0 +1,60 @@
+// secrets.rs
+use crate::redact::SecretBytes;
+use crate::EnclaveError;
+use std::path::PathBuf;
+use tracing::{info, warn};
+use zeroize::Zeroizing;
+
+/// Shortest secret accepted, in bytes.
+const MIN_SECRET_BYTES: usize = 32;
+
+/// Long-lived secrets provisioned to the enclave at boot, e.g. decrypted by
+/// KMS under an attestation-bound key policy and written to a tmpfs directory.
+/// They are never generated or persisted by the enclave itself.
+pub struct SecretStore {
+    dir: Option<PathBuf>,
+}
+
+impl SecretStore {
+    /// Load from `SECRET_STORE_DIR`, one hex-encoded file per secret.
+    pub fn from_env() -> Self {
+        let dir = std::env::var("SECRET_STORE_DIR").ok().filter(|d| !d.is_empty()).map(PathBuf::from);
+        match &dir {
+            Some(dir) => info!("Secret store at {}", dir.display()),
+            None => warn!("SECRET_STORE_DIR not set: secrets only from environment variables"),
+        }
+        Self { dir }
+    }
+
+    /// Secret `name` from the store directory, falling back to the upper-case
+    /// environment variable (for local development). `None` if neither is set.
+    pub fn get(&self, name: &str) -> Result<Option<SecretBytes>, EnclaveError> {
+        let from_file = match &self.dir {
+            Some(dir) => match std::fs::read_to_string(dir.join(name)) {
+                Ok(contents) => Some(Zeroizing::new(contents)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => {
+                    return Err(EnclaveError::InternalError(format!(
+                        "Cannot read secret {}: {}",
+                        name, e
+                    )))
+                }
+            },
+            None => None,
+        };
+        let Some(encoded) = from_file.or_else(|| std::env::var(name.to_ascii_uppercase()).ok().map(Zeroizing::new)) else {
+            return Ok(None);
+        };
+
+        let secret = hex::decode(encoded.trim())
+            .map(SecretBytes::new)
+            .map_err(|_| EnclaveError::InternalError(format!("Secret {} is not hex", name)))?;
+        if secret.len() < MIN_SECRET_BYTES {
+            return Err(EnclaveError::InternalError(format!(
+                "Secret {} is shorter than {} bytes",
+                name, MIN_SECRET_BYTES
+            )));
+        }
+        Ok(Some(secret))
+    }
+}
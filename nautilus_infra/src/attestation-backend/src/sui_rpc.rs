@@ -0,0 +1,265 @@
+// sui_rpc.rs
+use crate::EnclaveError;
+use futures::future::BoxFuture;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+const DEFAULT_SUI_RPC_URL: &str = "https://fullnode.testnet.sui.io:443";
+
+/// Full node from `SUI_RPC_URL` (default testnet).
+pub fn sui_rpc_url() -> String {
+    std::env::var("SUI_RPC_URL").unwrap_or_else(|_| DEFAULT_SUI_RPC_URL.to_string())
+}
+
+/// A Move object as returned by `sui_getObject`.
+#[derive(Debug, Clone)]
+pub struct SuiObject {
+    pub object_id: String,
+    /// Full Move type, e.g. `0x..::did_registry::DIDSoulBoundNFT`
+    pub type_: String,
+    /// Address owner, `None` for shared, immutable or wrapped objects
+    pub owner: Option<String>,
+    /// Move struct fields in Sui JSON-RPC encoding
+    pub fields: Value,
+}
+
+/// The reads the verifier needs from a Sui full node.
+pub trait SuiRpc: Send + Sync {
+    fn get_object<'a>(&'a self, object_id: &'a str) -> BoxFuture<'a, Result<Option<SuiObject>, EnclaveError>>;
+
+    /// IDs of objects of `struct_type` owned by `owner`.
+    fn get_owned_objects<'a>(
+        &'a self,
+        owner: &'a str,
+        struct_type: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<String>, EnclaveError>>;
+
+    fn current_epoch(&self) -> BoxFuture<'_, Result<u64, EnclaveError>>;
+}
+
+/// Sui JSON-RPC over HTTP.
+pub struct JsonRpcClient {
+    url: String,
+    http: reqwest::Client,
+}
+
+impl JsonRpcClient {
+    pub fn new(url: String) -> Self {
+        Self { url, http: reqwest::Client::new() }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, EnclaveError> {
+        let upstream = |e: String| EnclaveError::UpstreamUnavailable(format!("Sui RPC {}: {}", method, e));
+        let response: Value = self
+            .http
+            .post(&self.url)
+            .json(&json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }))
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| upstream(e.to_string()))?
+            .json()
+            .await
+            .map_err(|e| upstream(e.to_string()))?;
+        if let Some(error) = response.get("error") {
+            return Err(upstream(error.to_string()));
+        }
+        Ok(response["result"].clone())
+    }
+}
+
+impl SuiRpc for JsonRpcClient {
+    fn get_object<'a>(&'a self, object_id: &'a str) -> BoxFuture<'a, Result<Option<SuiObject>, EnclaveError>> {
+        Box::pin(async move {
+            let options = json!({ "showContent": true, "showOwner": true, "showType": true });
+            let result = self.call("sui_getObject", json!([object_id, options])).await?;
+            // Missing and deleted objects come back as `error` inside the result
+            let data = &result["data"];
+            if data.is_null() {
+                return Ok(None);
+            }
+            Ok(Some(SuiObject {
+                object_id: data["objectId"].as_str().unwrap_or(object_id).to_string(),
+                type_: data["type"].as_str().unwrap_or_default().to_string(),
+                owner: data["owner"]["AddressOwner"].as_str().map(str::to_string),
+                fields: data["content"]["fields"].clone(),
+            }))
+        })
+    }
+
+    fn get_owned_objects<'a>(
+        &'a self,
+        owner: &'a str,
+        struct_type: &'a str,
+    ) -> BoxFuture<'a, Result<Vec<String>, EnclaveError>> {
+        Box::pin(async move {
+            let query = json!({ "filter": { "StructType": struct_type }, "options": {} });
+            let mut ids = Vec::new();
+            let mut cursor = Value::Null;
+            loop {
+                let page = self
+                    .call("suix_getOwnedObjects", json!([owner, query, cursor, Value::Null]))
+                    .await?;
+                ids.extend(
+                    page["data"]
+                        .as_array()
+                        .into_iter()
+                        .flatten()
+                        .filter_map(|o| o["data"]["objectId"].as_str().map(str::to_string)),
+                );
+                if !page["hasNextPage"].as_bool().unwrap_or(false) {
+                    return Ok(ids);
+                }
+                cursor = page["nextCursor"].clone();
+            }
+        })
+    }
+
+    fn current_epoch(&self) -> BoxFuture<'_, Result<u64, EnclaveError>> {
+        Box::pin(async move {
+            let state = self.call("suix_getLatestSuiSystemState", json!([])).await?;
+            parse_u64(&state["epoch"])
+                .ok_or_else(|| EnclaveError::UpstreamUnavailable("Sui RPC returned no epoch".to_string()))
+        })
+    }
+}
+
+/// Sui JSON-RPC encodes u64 as a decimal string.
+pub(crate) fn parse_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        other => other.as_u64(),
+    }
+}
+
+/// `vector<u8>` is encoded as an array of numbers.
+pub(crate) fn parse_bytes(value: &Value) -> Option<Vec<u8>> {
+    value
+        .as_array()?
+        .iter()
+        .map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()))
+        .collect()
+}
+
+/// The current Sui epoch, refreshed from a full node. Epochs only move
+/// forward, so a value older than `max_age` is treated as unknown rather than
+/// used to accept signatures that may already have expired.
+pub struct EpochCache {
+    rpc: Box<dyn SuiRpc>,
+    refresh_after: Duration,
+    max_age: Duration,
+    current: Mutex<Option<(u64, Instant)>>,
+}
+
+impl EpochCache {
+    pub fn new(rpc: Box<dyn SuiRpc>, refresh_after: Duration, max_age: Duration) -> Self {
+        Self { rpc, refresh_after, max_age, current: Mutex::new(None) }
+    }
+
+    /// Load from `SUI_RPC_URL`, `SUI_EPOCH_REFRESH_SECS` (default 60) and
+    /// `SUI_EPOCH_MAX_AGE_SECS` (default 600).
+    pub fn from_env() -> Self {
+        let secs = |name: &str, default: u64| {
+            Duration::from_secs(std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default))
+        };
+        Self::new(
+            Box::new(JsonRpcClient::new(sui_rpc_url())),
+            secs("SUI_EPOCH_REFRESH_SECS", 60),
+            secs("SUI_EPOCH_MAX_AGE_SECS", 600),
+        )
+    }
+
+    /// Fetch the epoch if the cached one is due for a refresh. Failures are
+    /// logged; `current` reports the epoch unknown once it is too old.
+    pub async fn refresh(&self) {
+        let due = self
+            .current
+            .lock()
+            .expect("epoch cache poisoned")
+            .is_none_or(|(_, fetched_at)| fetched_at.elapsed() >= self.refresh_after);
+        if !due {
+            return;
+        }
+        match self.rpc.current_epoch().await {
+            Ok(epoch) => {
+                let mut current = self.current.lock().expect("epoch cache poisoned");
+                if current.is_none_or(|(previous, _)| previous != epoch) {
+                    info!("Current Sui epoch: {}", epoch);
+                }
+                *current = Some((epoch, Instant::now()));
+            }
+            Err(e) => warn!("Failed to refresh the Sui epoch: {}", e),
+        }
+    }
+
+    /// The last fetched epoch, if it is recent enough to rely on.
+    pub fn current(&self) -> Option<u64> {
+        self.current
+            .lock()
+            .expect("epoch cache poisoned")
+            .filter(|(_, fetched_at)| fetched_at.elapsed() < self.max_age)
+            .map(|(epoch, _)| epoch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    /// Serves `epoch`, or fails while it is zero.
+    struct EpochRpc(Arc<AtomicU64>);
+
+    impl SuiRpc for EpochRpc {
+        fn get_object<'a>(&'a self, _id: &'a str) -> BoxFuture<'a, Result<Option<SuiObject>, EnclaveError>> {
+            Box::pin(async { Ok(None) })
+        }
+
+        fn get_owned_objects<'a>(
+            &'a self,
+            _owner: &'a str,
+            _struct_type: &'a str,
+        ) -> BoxFuture<'a, Result<Vec<String>, EnclaveError>> {
+            Box::pin(async { Ok(Vec::new()) })
+        }
+
+        fn current_epoch(&self) -> BoxFuture<'_, Result<u64, EnclaveError>> {
+            Box::pin(async move {
+                match self.0.load(Ordering::SeqCst) {
+                    0 => Err(EnclaveError::UpstreamUnavailable("down".to_string())),
+                    epoch => Ok(epoch),
+                }
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn epoch_is_unknown_until_fetched_and_expires() {
+        let epoch = Arc::new(AtomicU64::new(0));
+        let cache = EpochCache::new(Box::new(EpochRpc(epoch.clone())), Duration::ZERO, Duration::from_millis(50));
+        cache.refresh().await;
+        assert_eq!(cache.current(), None);
+
+        epoch.store(42, Ordering::SeqCst);
+        cache.refresh().await;
+        assert_eq!(cache.current(), Some(42));
+
+        // A failed refresh keeps the value only until it is too old to trust
+        epoch.store(0, Ordering::SeqCst);
+        cache.refresh().await;
+        assert_eq!(cache.current(), Some(42));
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(cache.current(), None);
+    }
+
+    #[test]
+    fn parses_json_rpc_encodings() {
+        assert_eq!(parse_u64(&json!("18446744073709551615")), Some(u64::MAX));
+        assert_eq!(parse_u64(&json!(7)), Some(7));
+        assert_eq!(parse_bytes(&json!([0, 255])), Some(vec![0, 255]));
+        assert_eq!(parse_bytes(&json!([256])), None);
+    }
+}
@@ -0,0 +1,293 @@
+// supervisor.rs
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+/// Whether a subsystem failing for good should bring the whole server down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Criticality {
+    /// Permanent failure stops the supervisor (e.g. the HTTP API).
+    Critical,
+    /// Permanent failure is recorded in health but other subsystems keep running.
+    Optional,
+}
+
+/// Current lifecycle state of a supervised subsystem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum SubsystemState {
+    Starting,
+    Running {
+        since_ms: u64,
+        restarts: u32,
+    },
+    BackingOff {
+        attempt: u32,
+        retry_in_ms: u64,
+        last_error: String,
+    },
+    Failed {
+        attempts: u32,
+        last_error: String,
+    },
+    Stopped,
+}
+
+/// Status entry exposed through health endpoints.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsystemStatus {
+    pub criticality: Criticality,
+    #[serde(flatten)]
+    pub state: SubsystemState,
+}
+
+/// Shared view of all subsystem states, cheap to clone into `AppState`.
+#[derive(Debug, Clone, Default)]
+pub struct SubsystemRegistry {
+    inner: Arc<RwLock<BTreeMap<String, SubsystemStatus>>>,
+}
+
+impl SubsystemRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn set(&self, name: &str, criticality: Criticality, state: SubsystemState) {
+        let mut inner = self.inner.write().expect("subsystem registry poisoned");
+        inner.insert(name.to_string(), SubsystemStatus { criticality, state });
+    }
+
+    /// Snapshot of every registered subsystem.
+    pub fn snapshot(&self) -> BTreeMap<String, SubsystemStatus> {
+        self.inner.read().expect("subsystem registry poisoned").clone()
+    }
+
+    /// Status of a single subsystem, if registered.
+    pub fn get(&self, name: &str) -> Option<SubsystemStatus> {
+        self.inner.read().expect("subsystem registry poisoned").get(name).cloned()
+    }
+
+    /// True if no critical subsystem is backing off or failed.
+    pub fn critical_healthy(&self) -> bool {
+        self.snapshot().values().all(|status| {
+            status.criticality == Criticality::Optional
+                || matches!(status.state, SubsystemState::Running { .. } | SubsystemState::Starting)
+        })
+    }
+}
+
+/// Exponential backoff settings for restarting a failed subsystem.
+#[derive(Debug, Clone)]
+pub struct BackoffPolicy {
+    pub initial: Duration,
+    pub max: Duration,
+    pub multiplier: f64,
+    /// Give up after this many consecutive failures; `None` retries forever.
+    pub max_restarts: Option<u32>,
+    /// A run lasting at least this long resets the failure counter.
+    pub reset_after: Duration,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self {
+            initial: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+            multiplier: 2.0,
+            max_restarts: None,
+            reset_after: Duration::from_secs(120),
+        }
+    }
+}
+
+impl BackoffPolicy {
+    /// Load from `SUPERVISOR_BACKOFF_INITIAL_MS`, `SUPERVISOR_BACKOFF_MAX_MS` and
+    /// `SUPERVISOR_MAX_RESTARTS`, falling back to the defaults.
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let env_u64 = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+
+        Self {
+            initial: env_u64("SUPERVISOR_BACKOFF_INITIAL_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.initial),
+            max: env_u64("SUPERVISOR_BACKOFF_MAX_MS")
+                .map(Duration::from_millis)
+                .unwrap_or(default.max),
+            max_restarts: env_u64("SUPERVISOR_MAX_RESTARTS")
+                .map(|v| v as u32)
+                .or(default.max_restarts),
+            ..default
+        }
+    }
+
+    /// Delay before restart number `attempt` (1-based).
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.powi(attempt.saturating_sub(1) as i32);
+        let delay = self.initial.as_secs_f64() * factor;
+        Duration::from_secs_f64(delay.min(self.max.as_secs_f64()))
+    }
+}
+
+/// Restarts failed subsystems with backoff and tracks their state.
+pub struct Supervisor {
+    registry: SubsystemRegistry,
+    tasks: JoinSet<(String, Criticality, Result<()>)>,
+}
+
+impl Supervisor {
+    pub fn new(registry: SubsystemRegistry) -> Self {
+        Self {
+            registry,
+            tasks: JoinSet::new(),
+        }
+    }
+
+    pub fn registry(&self) -> SubsystemRegistry {
+        self.registry.clone()
+    }
+
+    /// Supervise a subsystem. `factory` is called again for every restart.
+    pub fn spawn<F, Fut>(
+        &mut self,
+        name: &str,
+        criticality: Criticality,
+        policy: BackoffPolicy,
+        factory: F,
+    ) where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let name = name.to_string();
+        let registry = self.registry.clone();
+        registry.set(&name, criticality, SubsystemState::Starting);
+
+        self.tasks.spawn(async move {
+            let result = supervise(&name, criticality, &policy, &registry, factory).await;
+            (name, criticality, result)
+        });
+    }
+
+    /// Drive all subsystems. Returns when a critical subsystem stops or fails
+    /// permanently, or when every subsystem has finished.
+    pub async fn run(mut self) -> Result<()> {
+        while let Some(joined) = self.tasks.join_next().await {
+            let (name, criticality, result) =
+                joined.map_err(|e| anyhow!("Supervisor task panicked: {}", e))?;
+
+            match (criticality, result) {
+                (Criticality::Critical, Ok(())) => {
+                    info!("Critical subsystem '{}' stopped, shutting down", name);
+                    return Ok(());
+                }
+                (Criticality::Critical, Err(e)) => {
+                    error!("Critical subsystem '{}' failed permanently: {}", name, e);
+                    return Err(e);
+                }
+                (Criticality::Optional, Ok(())) => {
+                    info!("Optional subsystem '{}' stopped", name);
+                }
+                (Criticality::Optional, Err(e)) => {
+                    warn!("Optional subsystem '{}' failed permanently, continuing without it: {}", name, e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+async fn supervise<F, Fut>(
+    name: &str,
+    criticality: Criticality,
+    policy: &BackoffPolicy,
+    registry: &SubsystemRegistry,
+    factory: F,
+) -> Result<()>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    let mut attempt: u32 = 0;
+    let mut restarts: u32 = 0;
+
+    loop {
+        registry.set(
+            name,
+            criticality,
+            SubsystemState::Running {
+                since_ms: now_ms(),
+                restarts,
+            },
+        );
+        info!("Subsystem '{}' running (restarts: {})", name, restarts);
+
+        let started = Instant::now();
+        // Run inside its own task. Returned errors are restarted with backoff;
+        // a panic only surfaces as `JoinError` in debug builds, since the
+        // release profile sets `panic = "abort"` and a panic ends the process.
+        let outcome = match tokio::spawn(factory()).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(e) => Err(format!("task panicked: {}", e)),
+        };
+
+        let last_error = match outcome {
+            Ok(()) => {
+                registry.set(name, criticality, SubsystemState::Stopped);
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        if started.elapsed() >= policy.reset_after {
+            attempt = 0;
+        }
+        attempt += 1;
+
+        if policy.max_restarts.is_some_and(|max| attempt > max) {
+            error!("Subsystem '{}' giving up after {} attempts: {}", name, attempt, last_error);
+            registry.set(
+                name,
+                criticality,
+                SubsystemState::Failed {
+                    attempts: attempt,
+                    last_error: last_error.clone(),
+                },
+            );
+            return Err(anyhow!(last_error));
+        }
+
+        let delay = policy.delay_for(attempt);
+        warn!(
+            "Subsystem '{}' failed (attempt {}), restarting in {:?}: {}",
+            name, attempt, delay, last_error
+        );
+        registry.set(
+            name,
+            criticality,
+            SubsystemState::BackingOff {
+                attempt,
+                retry_in_ms: delay.as_millis() as u64,
+                last_error,
+            },
+        );
+
+        tokio::time::sleep(delay).await;
+        restarts += 1;
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
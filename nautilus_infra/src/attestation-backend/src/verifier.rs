@@ -0,0 +1,394 @@
+// verifier.rs
+use crate::documents::IdentityRecord;
+use crate::media::NormalizedImage;
+use crate::EnclaveError;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+/// Evidence handed to an [`IdentityVerifier`].
+pub struct VerificationInput<'a> {
+    pub document: &'a NormalizedImage,
+    pub faces: &'a [NormalizedImage],
+    /// Embedding of the document photo
+    pub document_embedding: &'a [f32],
+    /// One embedding per face frame, in capture order
+    pub face_embeddings: &'a [Vec<f32>],
+    /// Identity record from a parsed document; `issuer_signed` says whether
+    /// its signature was verified
+    pub document_record: Option<&'a IdentityRecord>,
+}
+
+/// Outcome of a single check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckReason {
+    pub check: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl CheckReason {
+    fn new(check: &str, passed: bool, detail: String) -> Self {
+        Self {
+            check: check.to_string(),
+            passed,
+            detail,
+        }
+    }
+}
+
+/// Structured verdict from an identity verifier.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerificationResult {
+    pub verified: bool,
+    /// Mean face-to-document cosine similarity
+    pub match_score: f32,
+    /// Confidence the frames come from a live capture, in [0, 1]
+    pub liveness_score: f32,
+    pub reasons: Vec<CheckReason>,
+    /// Identifies the verifier and thresholds that produced the verdict
+    pub model_version: String,
+}
+
+/// Decides whether the submitted faces belong to the document holder.
+pub trait IdentityVerifier: Send + Sync {
+    /// Version string reported with every verdict.
+    fn version(&self) -> &str;
+
+    fn verify(&self, input: &VerificationInput) -> Result<VerificationResult, EnclaveError>;
+}
+
+/// Build the verifier selected by `IDENTITY_VERIFIER` (`embedding`, or `mock`
+/// in builds with the `mock` feature).
+pub fn verifier_from_env() -> Box<dyn IdentityVerifier> {
+    match std::env::var("IDENTITY_VERIFIER").as_deref() {
+        #[cfg(feature = "mock")]
+        Ok("mock") => {
+            let verified = std::env::var("MOCK_VERIFIER_RESULT")
+                .map(|v| v != "reject")
+                .unwrap_or(true);
+            info!("Using mock identity verifier (verified = {})", verified);
+            Box::new(MockVerifier::new(verified))
+        }
+        #[cfg(not(feature = "mock"))]
+        Ok("mock") => {
+            tracing::warn!("IDENTITY_VERIFIER=mock needs the mock feature; using the embedding verifier");
+            Box::new(EmbeddingVerifier::new(EmbeddingThresholds::from_env()))
+        }
+        _ => Box::new(EmbeddingVerifier::new(EmbeddingThresholds::from_env())),
+    }
+}
+
+// ====================================================================
+// Embedding verifier
+// ====================================================================
+
+/// Thresholds for [`EmbeddingVerifier`].
+#[derive(Debug, Clone)]
+pub struct EmbeddingThresholds {
+    /// Expected embedding length
+    pub embedding_dim: usize,
+    /// Minimum number of face frames
+    pub min_frames: usize,
+    /// Minimum mean cosine similarity between faces and the document photo
+    pub min_match_score: f32,
+    /// Minimum fraction of frames that must individually match
+    pub min_frame_match_ratio: f32,
+    /// Minimum fraction of consecutive frame pairs showing natural motion
+    pub min_liveness_score: f32,
+    /// Embedding change between consecutive frames below this looks like a still image
+    pub min_motion: f32,
+    /// Embedding change above this looks like a different person
+    pub max_motion: f32,
+    /// Reject submissions without an issuer-signed document record (default)
+    pub require_signed_document: bool,
+}
+
+impl EmbeddingThresholds {
+    /// Load from `VERIFIER_EMBEDDING_DIM`, `VERIFIER_MIN_FRAMES`,
+    /// `VERIFIER_MIN_MATCH_SCORE`, `VERIFIER_MIN_FRAME_MATCH_RATIO`,
+    /// `VERIFIER_MIN_LIVENESS_SCORE`, `VERIFIER_MIN_MOTION`, `VERIFIER_MAX_MOTION`
+    /// and `VERIFIER_REQUIRE_SIGNED_DOCUMENT`.
+    pub fn from_env() -> Self {
+        let env_f32 = |name: &str, default: f32| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        let env_usize = |name: &str, default: usize| {
+            std::env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+        };
+        Self {
+            embedding_dim: env_usize("VERIFIER_EMBEDDING_DIM", 512),
+            min_frames: env_usize("VERIFIER_MIN_FRAMES", 5),
+            min_match_score: env_f32("VERIFIER_MIN_MATCH_SCORE", 0.6),
+            min_frame_match_ratio: env_f32("VERIFIER_MIN_FRAME_MATCH_RATIO", 0.8),
+            min_liveness_score: env_f32("VERIFIER_MIN_LIVENESS_SCORE", 0.5),
+            min_motion: env_f32("VERIFIER_MIN_MOTION", 0.005),
+            max_motion: env_f32("VERIFIER_MAX_MOTION", 0.35),
+            require_signed_document: std::env::var("VERIFIER_REQUIRE_SIGNED_DOCUMENT")
+                .map(|v| v != "false" && v != "0")
+                .unwrap_or(true),
+        }
+    }
+}
+
+/// Compares client-supplied face embeddings against the document-photo embedding.
+pub struct EmbeddingVerifier {
+    thresholds: EmbeddingThresholds,
+    version: String,
+}
+
+impl EmbeddingVerifier {
+    pub fn new(thresholds: EmbeddingThresholds) -> Self {
+        let version = format!(
+            "embedding-v1/d{}/m{:.2}/l{:.2}",
+            thresholds.embedding_dim, thresholds.min_match_score, thresholds.min_liveness_score
+        );
+        info!("Identity verifier: {}", version);
+        Self { thresholds, version }
+    }
+
+    fn check_dimensions(&self, input: &VerificationInput) -> Result<(), EnclaveError> {
+        let dim = self.thresholds.embedding_dim;
+        if input.document_embedding.len() != dim {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Document embedding has {} values, expected {}",
+                input.document_embedding.len(),
+                dim
+            )));
+        }
+        if input.face_embeddings.len() != input.faces.len() {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Got {} face embeddings for {} face frames",
+                input.face_embeddings.len(),
+                input.faces.len()
+            )));
+        }
+        if let Some(index) = input.face_embeddings.iter().position(|e| e.len() != dim) {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Face embedding {} does not have {} values",
+                index, dim
+            )));
+        }
+        let finite = |embedding: &[f32]| embedding.iter().all(|v| v.is_finite());
+        if !finite(input.document_embedding) || !input.face_embeddings.iter().all(|e| finite(e)) {
+            return Err(EnclaveError::InvalidInput(
+                "Embeddings must be finite numbers".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl IdentityVerifier for EmbeddingVerifier {
+    fn version(&self) -> &str {
+        &self.version
+    }
+
+    fn verify(&self, input: &VerificationInput) -> Result<VerificationResult, EnclaveError> {
+        self.check_dimensions(input)?;
+        let t = &self.thresholds;
+        let mut reasons = Vec::new();
+
+        reasons.push(CheckReason::new(
+            "document_present",
+            !input.document.rgb.is_empty(),
+            format!("{}x{}", input.document.width, input.document.height),
+        ));
+        if let Some(record) = input.document_record.filter(|r| r.issuer_signed) {
+            reasons.push(CheckReason::new(
+                "signed_document",
+                !record.photo.is_empty(),
+                format!("{} record, {} byte photo", record.document_type.as_str(), record.photo.len()),
+            ));
+        } else if t.require_signed_document {
+            reasons.push(CheckReason::new(
+                "signed_document",
+                false,
+                "no issuer-signed document submitted".to_string(),
+            ));
+        }
+        reasons.push(CheckReason::new(
+            "frame_count",
+            input.faces.len() >= t.min_frames,
+            format!("{} frames (min {})", input.faces.len(), t.min_frames),
+        ));
+
+        // Face match: mean similarity, and most frames must match on their own
+        let similarities: Vec<f32> = input
+            .face_embeddings
+            .iter()
+            .map(|face| cosine_similarity(face, input.document_embedding))
+            .collect();
+        let match_score = mean(&similarities);
+        let matching_frames = similarities.iter().filter(|s| **s >= t.min_match_score).count();
+        let match_ratio = ratio(matching_frames, similarities.len());
+        reasons.push(CheckReason::new(
+            "face_match",
+            match_score >= t.min_match_score,
+            format!("mean similarity {:.3} (min {:.3})", match_score, t.min_match_score),
+        ));
+        reasons.push(CheckReason::new(
+            "frame_match_ratio",
+            match_ratio >= t.min_frame_match_ratio,
+            format!(
+                "{}/{} frames match (min ratio {:.2})",
+                matching_frames,
+                similarities.len(),
+                t.min_frame_match_ratio
+            ),
+        ));
+
+        // Liveness: a live capture moves a little between frames, a replayed
+        // still does not, and a swapped face moves too much
+        let live_pairs = input
+            .face_embeddings
+            .windows(2)
+            .filter(|pair| {
+                let motion = 1.0 - cosine_similarity(&pair[0], &pair[1]);
+                motion >= t.min_motion && motion <= t.max_motion
+            })
+            .count();
+        let liveness_score = ratio(live_pairs, input.face_embeddings.len().saturating_sub(1));
+        reasons.push(CheckReason::new(
+            "liveness",
+            liveness_score >= t.min_liveness_score,
+            format!(
+                "{} natural transitions, score {:.3} (min {:.3})",
+                live_pairs, liveness_score, t.min_liveness_score
+            ),
+        ));
+
+        Ok(VerificationResult {
+            verified: reasons.iter().all(|r| r.passed),
+            match_score,
+            liveness_score,
+            reasons,
+            model_version: self.version.clone(),
+        })
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    (dot / (norm_a * norm_b)).clamp(-1.0, 1.0)
+}
+
+fn mean(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f32>() / values.len() as f32
+}
+
+fn ratio(count: usize, total: usize) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    count as f32 / total as f32
+}
+
+// ====================================================================
+// Mock verifier
+// ====================================================================
+
+/// Deterministic verifier for tests: same verdict and scores for every input.
+/// Only compiled into test builds and builds with the `mock` feature.
+#[cfg(any(test, feature = "mock"))]
+pub struct MockVerifier {
+    verified: bool,
+}
+
+#[cfg(any(test, feature = "mock"))]
+impl MockVerifier {
+    pub fn new(verified: bool) -> Self {
+        Self { verified }
+    }
+}
+
+#[cfg(any(test, feature = "mock"))]
+impl IdentityVerifier for MockVerifier {
+    fn version(&self) -> &str {
+        "mock-v1"
+    }
+
+    fn verify(&self, input: &VerificationInput) -> Result<VerificationResult, EnclaveError> {
+        let score = if self.verified { 1.0 } else { 0.0 };
+        Ok(VerificationResult {
+            verified: self.verified,
+            match_score: score,
+            liveness_score: score,
+            reasons: vec![CheckReason::new(
+                "mock",
+                self.verified,
+                format!("{} frames", input.faces.len()),
+            )],
+            model_version: self.version().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::ImageFormat;
+
+    fn thresholds(require_signed_document: bool) -> EmbeddingThresholds {
+        EmbeddingThresholds {
+            embedding_dim: 4,
+            min_frames: 3,
+            min_match_score: 0.6,
+            min_frame_match_ratio: 0.8,
+            min_liveness_score: 0.5,
+            min_motion: 0.0001,
+            max_motion: 0.35,
+            require_signed_document,
+        }
+    }
+
+    fn image() -> NormalizedImage {
+        NormalizedImage {
+            format: ImageFormat::Png,
+            width: 1,
+            height: 1,
+            rgb: vec![0, 0, 0],
+            source_digest: String::new(),
+            pixel_digest: String::new(),
+        }
+    }
+
+    fn verify(verifier: &dyn IdentityVerifier) -> VerificationResult {
+        let faces = vec![image(), image(), image()];
+        let document = [1.0, 0.0, 0.0, 0.0];
+        let face_embeddings = vec![vec![1.0, 0.05, 0.0, 0.0], vec![1.0, 0.0, 0.05, 0.0], vec![1.0, 0.0, 0.0, 0.05]];
+        verifier
+            .verify(&VerificationInput {
+                document: &image(),
+                faces: &faces,
+                document_embedding: &document,
+                face_embeddings: &face_embeddings,
+                document_record: None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn matching_live_faces_pass_when_no_signed_document_is_required() {
+        assert!(verify(&EmbeddingVerifier::new(thresholds(false))).verified);
+    }
+
+    #[test]
+    fn unsigned_submission_fails_when_a_signed_document_is_required() {
+        let result = verify(&EmbeddingVerifier::new(thresholds(true)));
+        assert!(!result.verified);
+        assert!(result.reasons.iter().any(|r| r.check == "signed_document" && !r.passed));
+    }
+
+    #[test]
+    fn mock_verifier_returns_its_fixed_verdict() {
+        assert!(!verify(&MockVerifier::new(false)).verified);
+    }
+}
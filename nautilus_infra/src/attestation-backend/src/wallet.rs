@@ -0,0 +1,422 @@
+// wallet.rs
+use crate::sui_rpc::EpochCache;
+use crate::EnclaveError;
+use base64::{engine::general_purpose, Engine as _};
+use fastcrypto::ed25519::{Ed25519PublicKey, Ed25519Signature, ED25519_PUBLIC_KEY_LENGTH, ED25519_SIGNATURE_LENGTH};
+use fastcrypto::hash::{Blake2b256, HashFunction};
+use fastcrypto::secp256k1::{Secp256k1PublicKey, Secp256k1Signature, SECP256K1_PUBLIC_KEY_LENGTH, SECP256K1_SIGNATURE_LENGTH};
+use fastcrypto::secp256r1::{Secp256r1PublicKey, Secp256r1Signature, SECP256R1_PUBLIC_KEY_LENGTH};
+use fastcrypto::traits::{ToFromBytes, VerifyingKey};
+use fastcrypto_zkp::bn254::zk_login::{JwkId, ZkLoginInputs, JWK};
+use fastcrypto_zkp::bn254::zk_login_api::{verify_zk_login, ZkLoginEnv};
+use im::hashmap::HashMap as ImHashMap;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+/// Sui signature scheme flags (first byte of a serialized signature).
+const FLAG_ED25519: u8 = 0x00;
+const FLAG_SECP256K1: u8 = 0x01;
+const FLAG_SECP256R1: u8 = 0x02;
+const FLAG_ZKLOGIN: u8 = 0x05;
+
+/// Compact (r, s) secp256r1 signature length.
+const SECP256R1_SIGNATURE_LENGTH: usize = 64;
+
+/// Intent prefix for Sui personal messages: scope PersonalMessage, version V0, app Sui.
+const PERSONAL_MESSAGE_INTENT: [u8; 3] = [3, 0, 0];
+
+/// Normalize a Sui address to canonical `0x` + 64 lowercase hex characters.
+pub fn normalize_sui_address(address: &str) -> Result<String, EnclaveError> {
+    let trimmed = address.trim();
+    let hex_part = trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+        .unwrap_or(trimmed);
+
+    if hex_part.is_empty() || hex_part.len() > 64 || !hex_part.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(EnclaveError::InvalidInput(format!(
+            "Invalid Sui address: {}",
+            address
+        )));
+    }
+
+    Ok(format!("0x{:0>64}", hex_part.to_lowercase()))
+}
+
+/// Challenge the wallet signs as a Sui personal message for a KYC submission.
+/// It covers the requested DID type and the digest of every submitted blob, so
+/// a relayer cannot swap any of them; `document_data` is `-` when absent.
+pub fn kyc_challenge(
+    nonce: &str,
+    timestamp_ms: u64,
+    did_type: u8,
+    encrypted_doc: &str,
+    encrypted_faces: &[String],
+    encrypted_document_data: Option<&str>,
+) -> String {
+    let faces: Vec<String> = encrypted_faces.iter().map(|f| document_digest(f)).collect();
+    format!(
+        "SuiVerify KYC\nnonce: {}\ntimestamp: {}\ndid_type: {}\ndocument: {}\nfaces: {}\ndocument_data: {}",
+        nonce,
+        timestamp_ms,
+        did_type,
+        document_digest(encrypted_doc),
+        faces.join(","),
+        encrypted_document_data.map_or_else(|| "-".to_string(), document_digest)
+    )
+}
+
+/// Challenge the wallet signs to open fields of its own evidence commitment.
+pub fn evidence_opening_challenge(
+    nonce: &str,
+    timestamp_ms: u64,
+    evidence_hash: &str,
+    fields: &[String],
+) -> String {
+    format!(
+        "SuiVerify evidence opening\nnonce: {}\ntimestamp: {}\nevidence: {}\nfields: {}",
+        nonce,
+        timestamp_ms,
+        evidence_hash,
+        fields.join(",")
+    )
+}
+
+/// Hex SHA-256 of the document exactly as submitted.
+pub fn document_digest(encrypted_doc: &str) -> String {
+    hex::encode(Sha256::digest(encrypted_doc.as_bytes()))
+}
+
+/// A JWK entry in the zkLogin key file.
+#[derive(Debug, Deserialize)]
+struct JwkEntry {
+    iss: String,
+    kid: String,
+    #[serde(flatten)]
+    jwk: JWK,
+}
+
+/// Inputs needed to check zkLogin signatures.
+pub struct ZkLoginConfig {
+    pub jwks: ImHashMap<JwkId, JWK>,
+    pub env: ZkLoginEnv,
+    /// Optional floor on `max_epoch` on top of the current-epoch check.
+    pub min_epoch: Option<u64>,
+}
+
+impl ZkLoginConfig {
+    /// Load provider keys from `ZKLOGIN_JWKS_FILE` (a JSON list of
+    /// `{iss, kid, kty, e, n, alg}`) and the epoch floor from `ZKLOGIN_MIN_EPOCH`.
+    /// `ZKLOGIN_ENV=test` selects the insecure test verifying key.
+    pub fn from_env() -> Self {
+        let mut jwks = ImHashMap::new();
+        if let Ok(path) = std::env::var("ZKLOGIN_JWKS_FILE") {
+            match std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|c| serde_json::from_str::<Vec<JwkEntry>>(&c).map_err(|e| e.to_string()))
+            {
+                Ok(entries) => {
+                    for entry in entries {
+                        jwks.insert(JwkId::new(entry.iss, entry.kid), entry.jwk);
+                    }
+                    info!("Loaded {} zkLogin JWKs from {}", jwks.len(), path);
+                }
+                Err(e) => warn!("Failed to load zkLogin JWKs from {}: {}", path, e),
+            }
+        }
+
+        Self {
+            jwks,
+            env: zklogin_env(),
+            min_epoch: std::env::var("ZKLOGIN_MIN_EPOCH").ok().and_then(|v| v.parse().ok()),
+        }
+    }
+}
+
+/// Groth16 verifying key selection: `ZKLOGIN_ENV=test` selects the insecure
+/// test key, anything else the production key.
+pub fn zklogin_env() -> ZkLoginEnv {
+    match std::env::var("ZKLOGIN_ENV").as_deref() {
+        Ok("test") => ZkLoginEnv::Test,
+        _ => ZkLoginEnv::Prod,
+    }
+}
+
+/// BCS layout of a serialized zkLogin signature after the flag byte.
+#[derive(Deserialize)]
+struct ZkLoginAuthenticator {
+    inputs: ZkLoginInputs,
+    max_epoch: u64,
+    user_signature: Vec<u8>,
+}
+
+/// Verifies that a KYC submission was signed by the wallet it names.
+pub struct WalletVerifier {
+    zklogin: ZkLoginConfig,
+    /// zkLogin signatures expire after their `max_epoch`
+    epoch: EpochCache,
+}
+
+impl WalletVerifier {
+    pub fn new(zklogin: ZkLoginConfig, epoch: EpochCache) -> Self {
+        Self { zklogin, epoch }
+    }
+
+    /// Refresh the current epoch used for zkLogin expiry, if it is due.
+    pub async fn refresh_epoch(&self) {
+        self.epoch.refresh().await;
+    }
+
+    /// Verify a base64 Sui personal-message signature over `message` and check
+    /// that the signing key derives to `expected_address` (already normalized).
+    pub fn verify_personal_message(
+        &self,
+        message: &[u8],
+        signature_b64: &str,
+        expected_address: &str,
+    ) -> Result<(), EnclaveError> {
+        let signature = general_purpose::STANDARD
+            .decode(signature_b64.trim())
+            .map_err(|e| EnclaveError::InvalidInput(format!("Wallet signature is not base64: {}", e)))?;
+        let digest = personal_message_digest(message)?;
+
+        let address = match signature.first() {
+            Some(&FLAG_ZKLOGIN) => self.verify_zklogin(&signature[1..], &digest)?,
+            Some(_) => verify_simple_signature(&signature, &digest)?,
+            None => {
+                return Err(EnclaveError::InvalidInput(
+                    "Wallet signature is empty".to_string(),
+                ))
+            }
+        };
+
+        if address.iter().any(|candidate| candidate == expected_address) {
+            Ok(())
+        } else {
+            Err(EnclaveError::VerificationFailed(
+                "Wallet signature does not match wallet_address".to_string(),
+            ))
+        }
+    }
+
+    /// Returns the address(es) the zkLogin signature authenticates.
+    fn verify_zklogin(&self, bytes: &[u8], digest: &[u8; 32]) -> Result<Vec<String>, EnclaveError> {
+        let mut authenticator: ZkLoginAuthenticator = bcs::from_bytes(bytes)
+            .map_err(|e| EnclaveError::InvalidInput(format!("Malformed zkLogin signature: {}", e)))?;
+        let inputs = authenticator
+            .inputs
+            .init()
+            .map_err(|e| EnclaveError::InvalidInput(format!("Malformed zkLogin inputs: {}", e)))?;
+
+        // Without a trusted epoch an expired ephemeral key would be accepted
+        let current_epoch = self.epoch.current().ok_or_else(|| {
+            EnclaveError::UpstreamUnavailable(
+                "Current Sui epoch is unknown; cannot check zkLogin signature expiry".to_string(),
+            )
+        })?;
+        let floor = self.zklogin.min_epoch.map_or(current_epoch, |min| min.max(current_epoch));
+        if authenticator.max_epoch < floor {
+            return Err(EnclaveError::VerificationFailed(format!(
+                "zkLogin signature expired at epoch {}",
+                authenticator.max_epoch
+            )));
+        }
+
+        // The ephemeral key signs the same personal message; its public key is a
+        // public input of the proof.
+        let user_signature = std::mem::take(&mut authenticator.user_signature);
+        verify_simple_signature(&user_signature, digest)?;
+        let scheme = user_signature[0];
+        let public_key = &user_signature[user_signature.len() - public_key_length(scheme)?..];
+        let mut eph_pk_bytes = vec![scheme];
+        eph_pk_bytes.extend_from_slice(public_key);
+
+        verify_zk_login(
+            &inputs,
+            authenticator.max_epoch,
+            &eph_pk_bytes,
+            &self.zklogin.jwks,
+            &self.zklogin.env,
+        )
+        .map_err(|e| EnclaveError::VerificationFailed(format!("zkLogin proof rejected: {}", e)))?;
+
+        // Wallets derive from the 32-byte seed; older ones from the unpadded seed.
+        let iss = inputs.get_iss().as_bytes();
+        let seed = inputs.get_address_seed();
+        Ok([seed.padded(), seed.unpadded()]
+            .iter()
+            .map(|seed_bytes| {
+                let mut preimage = vec![FLAG_ZKLOGIN, iss.len() as u8];
+                preimage.extend_from_slice(iss);
+                preimage.extend_from_slice(seed_bytes);
+                address_from_preimage(&preimage)
+            })
+            .collect())
+    }
+}
+
+/// Blake2b-256 of the intent-wrapped, BCS-encoded personal message.
+fn personal_message_digest(message: &[u8]) -> Result<[u8; 32], EnclaveError> {
+    let mut intent_message = PERSONAL_MESSAGE_INTENT.to_vec();
+    intent_message.extend(
+        bcs::to_bytes(message)
+            .map_err(|e| EnclaveError::InternalError(format!("BCS encoding failed: {}", e)))?,
+    );
+    Ok(Blake2b256::digest(&intent_message).digest)
+}
+
+fn public_key_length(flag: u8) -> Result<usize, EnclaveError> {
+    match flag {
+        FLAG_ED25519 => Ok(ED25519_PUBLIC_KEY_LENGTH),
+        FLAG_SECP256K1 => Ok(SECP256K1_PUBLIC_KEY_LENGTH),
+        FLAG_SECP256R1 => Ok(SECP256R1_PUBLIC_KEY_LENGTH),
+        other => Err(EnclaveError::InvalidInput(format!(
+            "Unsupported signature scheme flag: {:#04x}",
+            other
+        ))),
+    }
+}
+
+/// Verify `flag || signature || public_key` over the message digest and return
+/// the derived address.
+fn verify_simple_signature(bytes: &[u8], digest: &[u8; 32]) -> Result<Vec<String>, EnclaveError> {
+    let flag = *bytes
+        .first()
+        .ok_or_else(|| EnclaveError::InvalidInput("Signature is empty".to_string()))?;
+    let (sig_len, pk_len) = match flag {
+        FLAG_ED25519 => (ED25519_SIGNATURE_LENGTH, ED25519_PUBLIC_KEY_LENGTH),
+        FLAG_SECP256K1 => (SECP256K1_SIGNATURE_LENGTH, SECP256K1_PUBLIC_KEY_LENGTH),
+        FLAG_SECP256R1 => (SECP256R1_SIGNATURE_LENGTH, SECP256R1_PUBLIC_KEY_LENGTH),
+        other => {
+            return Err(EnclaveError::InvalidInput(format!(
+                "Unsupported signature scheme flag: {:#04x}",
+                other
+            )))
+        }
+    };
+    if bytes.len() != 1 + sig_len + pk_len {
+        return Err(EnclaveError::InvalidInput(format!(
+            "Signature length {} does not match scheme {:#04x}",
+            bytes.len(),
+            flag
+        )));
+    }
+    let sig = &bytes[1..1 + sig_len];
+    let pk = &bytes[1 + sig_len..];
+    let invalid = |e: fastcrypto::error::FastCryptoError| {
+        EnclaveError::VerificationFailed(format!("Wallet signature invalid: {}", e))
+    };
+
+    // Secp256k1/r1 verification hashes the digest with SHA-256 internally, as Sui wallets sign.
+    match flag {
+        FLAG_ED25519 => Ed25519PublicKey::from_bytes(pk)
+            .and_then(|key| key.verify(digest, &Ed25519Signature::from_bytes(sig)?))
+            .map_err(invalid)?,
+        FLAG_SECP256K1 => Secp256k1PublicKey::from_bytes(pk)
+            .and_then(|key| key.verify(digest, &Secp256k1Signature::from_bytes(sig)?))
+            .map_err(invalid)?,
+        _ => Secp256r1PublicKey::from_bytes(pk)
+            .and_then(|key| key.verify(digest, &Secp256r1Signature::from_bytes(sig)?))
+            .map_err(invalid)?,
+    }
+
+    let mut preimage = vec![flag];
+    preimage.extend_from_slice(pk);
+    Ok(vec![address_from_preimage(&preimage)])
+}
+
+fn address_from_preimage(preimage: &[u8]) -> String {
+    format!("0x{}", hex::encode(Blake2b256::digest(preimage).digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sui_rpc::JsonRpcClient;
+    use fastcrypto::ed25519::Ed25519KeyPair;
+    use fastcrypto::secp256k1::Secp256k1KeyPair;
+    use fastcrypto::secp256r1::Secp256r1KeyPair;
+    use fastcrypto::traits::KeyPair;
+    use std::time::Duration;
+
+    fn verifier() -> WalletVerifier {
+        let zklogin = ZkLoginConfig { jwks: ImHashMap::new(), env: ZkLoginEnv::Test, min_epoch: None };
+        // Simple signatures never consult the epoch
+        let rpc = JsonRpcClient::new("http://127.0.0.1:1".to_string());
+        WalletVerifier::new(zklogin, EpochCache::new(Box::new(rpc), Duration::ZERO, Duration::ZERO))
+    }
+
+    /// Serialize a personal-message signature as Sui wallets do and return it
+    /// with the signer's address.
+    fn sign<K: KeyPair>(flag: u8, keypair: &K, message: &[u8]) -> (String, String) {
+        let digest = personal_message_digest(message).unwrap();
+        let mut bytes = vec![flag];
+        bytes.extend_from_slice(keypair.sign(&digest).as_ref());
+        bytes.extend_from_slice(keypair.public().as_ref());
+        let mut preimage = vec![flag];
+        preimage.extend_from_slice(keypair.public().as_ref());
+        (general_purpose::STANDARD.encode(bytes), address_from_preimage(&preimage))
+    }
+
+    #[test]
+    fn accepts_personal_messages_from_every_simple_scheme() {
+        let verifier = verifier();
+        let faces = vec!["f1".to_string()];
+        let challenge = kyc_challenge("nonce-0000000001", 1_000, 1, "doc", &faces, None);
+        let tampered = kyc_challenge("nonce-0000000001", 1_000, 2, "doc", &faces, None);
+        let other = format!("0x{}", "ab".repeat(32));
+
+        let mut rng = rand::thread_rng();
+        let signed = [
+            sign(FLAG_ED25519, &Ed25519KeyPair::generate(&mut rng), challenge.as_bytes()),
+            sign(FLAG_SECP256K1, &Secp256k1KeyPair::generate(&mut rng), challenge.as_bytes()),
+            sign(FLAG_SECP256R1, &Secp256r1KeyPair::generate(&mut rng), challenge.as_bytes()),
+        ];
+        for (signature, address) in signed {
+            verifier.verify_personal_message(challenge.as_bytes(), &signature, &address).unwrap();
+
+            let wrong_address = verifier.verify_personal_message(challenge.as_bytes(), &signature, &other);
+            assert!(wrong_address.unwrap_err().message().contains("does not match wallet_address"));
+            let wrong_message = verifier.verify_personal_message(tampered.as_bytes(), &signature, &address);
+            assert!(wrong_message.unwrap_err().message().contains("Wallet signature invalid"));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_signatures() {
+        let verifier = verifier();
+        let (signature, address) = sign(FLAG_ED25519, &Ed25519KeyPair::generate(&mut rand::thread_rng()), b"m");
+        let mut bytes = general_purpose::STANDARD.decode(&signature).unwrap();
+        bytes[0] = FLAG_SECP256K1;
+        let wrong_flag = general_purpose::STANDARD.encode(&bytes);
+        assert!(verifier.verify_personal_message(b"m", &wrong_flag, &address).is_err());
+        assert!(verifier.verify_personal_message(b"m", "not base64!", &address).is_err());
+        assert!(verifier.verify_personal_message(b"m", "", &address).is_err());
+    }
+
+    #[test]
+    fn challenge_binds_did_type_and_every_blob() {
+        let faces = vec!["f1".to_string(), "f2".to_string()];
+        let base = kyc_challenge("nonce-0000000001", 1_000, 1, "doc", &faces, None);
+        assert!(base.contains("did_type: 1\n"));
+        assert!(base.ends_with("document_data: -"));
+
+        let variants = [
+            kyc_challenge("nonce-0000000001", 1_000, 2, "doc", &faces, None),
+            kyc_challenge("nonce-0000000001", 1_001, 1, "doc", &faces, None),
+            kyc_challenge("nonce-0000000001", 1_000, 1, "doc2", &faces, None),
+            kyc_challenge("nonce-0000000001", 1_000, 1, "doc", &faces[..1], None),
+            kyc_challenge("nonce-0000000001", 1_000, 1, "doc", &faces, Some("data")),
+        ];
+        for variant in variants {
+            assert_ne!(variant, base);
+        }
+    }
+
+    #[test]
+    fn normalizes_addresses() {
+        assert_eq!(normalize_sui_address("0xAB").unwrap(), format!("0x{:0>64}", "ab"));
+        assert!(normalize_sui_address("0xzz").is_err());
+    }
+}
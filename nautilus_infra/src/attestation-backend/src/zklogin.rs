@@ -1,20 +1,31 @@
-/*
-// zklogin.rs - COMMENTED OUT - No longer using zkLogin functionality
-use crate::AppState;
+// zklogin.rs
+use crate::jwks::{self, JwksCache, JwksSource};
+use crate::redact::SecretBytes;
+use crate::secrets::SecretStore;
+use crate::{AppState, EnclaveError};
 use axum::extract::State;
-use axum::http::StatusCode;
-use axum::response::{IntoResponse, Response};
 use axum::Json;
+use base64::{engine::general_purpose, Engine as _};
+use crate::wallet::zklogin_env;
+use crate::zkprover::{prover_from_env, ProofRequest, ZkProver};
+use fastcrypto_zkp::bn254::utils::{gen_address_seed, get_nonce};
+use fastcrypto_zkp::bn254::zk_login::{JwkId, ZkLoginInputs, JWK};
+use fastcrypto_zkp::bn254::zk_login_api::{verify_zk_login, ZkLoginEnv};
+use hkdf::Hkdf;
+use im::hashmap::HashMap as ImHashMap;
+use rsa::traits::PublicKeyParts;
+use rsa::RsaPublicKey;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use sha2::Sha256;
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use sha2::{Sha256, Digest};
-use hkdf::Hkdf;
-use base64::{Engine as _, engine::general_purpose};
-use reqwest::Client;
-use tracing::{info, error, warn};
-use std::env;
-use dotenvy::dotenv;
+use tracing::{info, warn};
+
+/// Salt seeds live in the secret store as `zklogin_master_seed_v{version}`.
+const MASTER_SEED_SECRET: &str = "zklogin_master_seed";
+
+/// Sui signature scheme flag of Ed25519 ephemeral keys.
+const ED25519_FLAG: u8 = 0x00;
 
 // JWT payload structure
 #[derive(Debug, Serialize, Deserialize)]
@@ -32,11 +43,16 @@ pub struct JwtPayload {
 #[derive(Debug, Deserialize)]
 pub struct SaltRequest {
     pub jwt: String,
+    /// Salt version the user onboarded with; the current version if omitted
+    #[serde(default)]
+    pub version: Option<u32>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SaltResponse {
     pub salt: String,
+    /// Seed version the salt was derived under; clients keep it with the address
+    pub version: u32,
 }
 
 // ZK Proof request/response - Updated for Enoki API
@@ -49,39 +65,13 @@ pub struct ZkProofRequest {
     pub max_epoch: u64,
     pub randomness: String,
     pub network: String, // Added for Enoki API
-}
-
-// Enoki API request structure
-#[derive(Debug, Serialize)]
-struct EnokiZkProofRequest {
-    pub network: String,
-    #[serde(rename = "ephemeralPublicKey")]
-    pub ephemeral_public_key: String,
-    #[serde(rename = "maxEpoch")]
-    pub max_epoch: u64,
-    pub randomness: String,
-}
-
-// Enoki API response structure
-#[derive(Debug, Deserialize)]
-struct EnokiZkProofResponse {
-    pub data: EnokiZkProofData,
-}
-
-#[derive(Debug, Deserialize)]
-struct EnokiZkProofData {
-    #[serde(rename = "proofPoints")]
-    pub proof_points: Option<ProofPoints>,
-    #[serde(rename = "issBase64Details")]
-    pub iss_base64_details: Option<IssBase64Details>,
-    #[serde(rename = "headerBase64")]
-    pub header_base64: Option<String>,
-    #[serde(rename = "addressSeed")]
-    pub address_seed: String,
+    /// Salt version for provers that take the salt; the current version if omitted
+    #[serde(default, rename = "saltVersion")]
+    pub salt_version: Option<u32>,
 }
 
 // Keep existing response structure for compatibility
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZkProofResponse {
     #[serde(rename = "proofPoints")]
     pub proof_points: ProofPoints,
@@ -93,204 +83,478 @@ pub struct ZkProofResponse {
     pub address_seed: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProofPoints {
     pub a: Vec<String>,
     pub b: Vec<Vec<String>>,
     pub c: Vec<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IssBase64Details {
     pub value: String,
     #[serde(rename = "indexMod4")]
     pub index_mod4: u32,
 }
 
-// Master seed for salt derivation (in production, use secure key management)
-const MASTER_SEED: &[u8] = b"zklogin_master_seed_change_in_production_secure_random_32_bytes!";
-
-// Enoki API configuration - Add these to your environment variables
-const ENOKI_API_URL: &str = "https://api.enoki.mystenlabs.com/v1/zklogin/zkp";
-
-
-fn get_enoki_token() -> Result<String, &'static str> {
-    // Load variables from .env (only needs to be called once, usually in main)
-    dotenv().ok();
+/// An OpenID provider, the client IDs (audiences) accepted from it and where
+/// its signing keys are published.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AllowedProvider {
+    pub issuer: String,
+    pub audiences: Vec<String>,
+    #[serde(default)]
+    pub jwks_uri: Option<String>,
+    /// Local JWKS file, used instead of `jwks_uri` when set
+    #[serde(default)]
+    pub jwks_file: Option<String>,
+}
 
-    env::var("ENOKI_API_TOKEN").map_err(|_| "ENOKI_API_TOKEN not set in .env")
+impl AllowedProvider {
+    fn source(&self) -> Result<JwksSource, EnclaveError> {
+        match (&self.jwks_file, &self.jwks_uri) {
+            (Some(path), _) => Ok(JwksSource::File(path.clone())),
+            (None, Some(url)) => Ok(JwksSource::Url(url.clone())),
+            (None, None) => Err(EnclaveError::InternalError(format!(
+                "zkLogin provider {} has no jwks_uri or jwks_file",
+                self.issuer
+            ))),
+        }
+    }
 }
 
-// Helper function to create error responses with logging
-fn error_response(status: StatusCode, message: &str) -> Response {
-    error!("Request failed: {}", message);
-    let body = Json(json!({
-        "error": message,
-        "status": status.as_u16()
-    }));
-    (status, body).into_response()
+#[derive(Deserialize)]
+struct ProvidersFile {
+    providers: Vec<AllowedProvider>,
 }
 
-/// Generate user salt using HKDF derivation
-/// This implements Option 4: HKDF(ikm = seed, salt = iss || aud, info = sub)
-pub async fn get_salt(
-    State(_state): State<Arc<AppState>>,
-    Json(request): Json<SaltRequest>,
-) -> Response {
-    info!("Salt request received");
-    
-    // Decode JWT without verification (we'll verify issuer manually)
-    let jwt_parts: Vec<&str> = request.jwt.split('.').collect();
-    if jwt_parts.len() != 3 {
-        return error_response(StatusCode::BAD_REQUEST, "Invalid JWT format");
-    }
+/// Salt derivation and proof requests for walletless (zkLogin) onboarding.
+pub struct ZkLoginService {
+    /// Seeds by version; only the current one is used for new users
+    seeds: BTreeMap<u32, SecretBytes>,
+    current_version: u32,
+    providers: Vec<AllowedProvider>,
+    jwks: JwksCache,
+    /// Clock skew tolerated on `exp` and `iat`, in seconds
+    leeway_secs: u64,
+    prover: Box<dyn ZkProver>,
+    /// Groth16 verifying key used to check prover responses
+    env: ZkLoginEnv,
+}
 
-    // Decode JWT payload
-    let payload_bytes = match general_purpose::URL_SAFE_NO_PAD.decode(jwt_parts[1]) {
-        Ok(bytes) => bytes,
-        Err(e) => {
-            return error_response(StatusCode::BAD_REQUEST, &format!("JWT decode error: {}", e));
+impl ZkLoginService {
+    /// Seeds `zklogin_master_seed_v1` up to `_v{ZKLOGIN_SALT_VERSION}` (default 1)
+    /// from the secret store, the issuer/audience allowlist from
+    /// `ZKLOGIN_PROVIDERS_FILE` (default `zklogin_providers.yaml`) and the
+    /// prover from `ZK_PROVER`. The current seed is
+    /// required; older ones keep serving users onboarded under them.
+    pub fn from_env(secrets: &SecretStore) -> Result<Self, EnclaveError> {
+        let current_version = std::env::var("ZKLOGIN_SALT_VERSION")
+            .ok()
+            .and_then(|v| v.parse::<u32>().ok())
+            .filter(|v| *v > 0)
+            .unwrap_or(1);
+        let mut seeds = BTreeMap::new();
+        for version in 1..=current_version {
+            let name = format!("{}_v{}", MASTER_SEED_SECRET, version);
+            match secrets.get(&name)? {
+                Some(seed) => {
+                    seeds.insert(version, seed);
+                }
+                None if version == current_version => {
+                    return Err(EnclaveError::InternalError(format!(
+                        "zkLogin salt seed {} is not provisioned",
+                        name
+                    )))
+                }
+                None => warn!("zkLogin salt seed {} not provisioned: version {} salts unavailable", name, version),
+            }
         }
-    };
-    
-    let payload: JwtPayload = match serde_json::from_slice(&payload_bytes) {
-        Ok(payload) => payload,
-        Err(e) => {
-            return error_response(StatusCode::BAD_REQUEST, &format!("JWT parse error: {}", e));
+
+        let path = std::env::var("ZKLOGIN_PROVIDERS_FILE")
+            .unwrap_or_else(|_| "zklogin_providers.yaml".to_string());
+        let providers = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_yaml::from_str::<ProvidersFile>(&content)
+                .map(|f| f.providers)
+                .map_err(|e| EnclaveError::InternalError(format!("Failed to parse {}: {}", path, e)))?,
+            Err(e) => {
+                warn!("No zkLogin providers file at {}: {}", path, e);
+                Vec::new()
+            }
+        };
+        for provider in &providers {
+            provider.source()?;
         }
-    };
+        info!(
+            "zkLogin salts at version {} ({} seeds), {} allowed providers",
+            current_version,
+            seeds.len(),
+            providers.len()
+        );
+
+        Ok(Self {
+            seeds,
+            current_version,
+            providers,
+            jwks: JwksCache::from_env()?,
+            leeway_secs: std::env::var("ZKLOGIN_JWT_LEEWAY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(60),
+            prover: prover_from_env()?,
+            env: zklogin_env(),
+        })
+    }
 
-    info!("JWT decoded - iss: {}, sub: {}, aud: {}", payload.iss, payload.sub, payload.aud);
+    /// Whether `aud` is an allowed client of the provider `iss`.
+    pub fn is_valid_issuer(&self, iss: &str, aud: &str) -> bool {
+        self.providers
+            .iter()
+            .any(|p| p.issuer == iss && p.audiences.iter().any(|a| a == aud))
+    }
 
-    // Validate issuer
-    if !is_valid_issuer(&payload.iss) {
-        warn!("Invalid issuer attempted: {}", payload.iss);
-        return error_response(StatusCode::BAD_REQUEST, "Invalid or unsupported issuer");
+    /// HKDF-SHA256(ikm = seed, salt = iss || aud, info = sub), 16 bytes as a
+    /// decimal string so it stays below 2^128.
+    pub fn derive_salt(&self, payload: &JwtPayload, version: u32) -> Result<String, EnclaveError> {
+        let seed = self.seeds.get(&version).ok_or_else(|| {
+            EnclaveError::InvalidInput(format!("Unknown zkLogin salt version {}", version))
+        })?;
+        let salt_input = format!("{}{}", payload.iss, payload.aud);
+        let hk = Hkdf::<Sha256>::new(Some(salt_input.as_bytes()), seed);
+
+        let mut salt_bytes = [0u8; 16];
+        hk.expand(payload.sub.as_bytes(), &mut salt_bytes)
+            .map_err(|e| EnclaveError::InternalError(format!("HKDF expand error: {}", e)))?;
+        Ok(u128::from_be_bytes(salt_bytes).to_string())
     }
 
-    // Generate salt using HKDF
-    let salt_input = format!("{}{}", payload.iss, payload.aud);
-    let hk = Hkdf::<Sha256>::new(Some(salt_input.as_bytes()), MASTER_SEED);
-    
-    let mut salt_bytes = [0u8; 16]; // 16 bytes for salt
-    if let Err(e) = hk.expand(payload.sub.as_bytes(), &mut salt_bytes) {
-        return error_response(StatusCode::INTERNAL_SERVER_ERROR, &format!("HKDF expand error: {}", e));
+    /// Verify a provider JWT: an allowed issuer and audience, an RS256
+    /// signature by a current key of the issuer, `exp`/`iat` within the
+    /// leeway and, when given, the expected `nonce`.
+    pub async fn verify_jwt(&self, jwt: &str, expected_nonce: Option<&str>) -> Result<VerifiedJwt, EnclaveError> {
+        let (header, payload_bytes) = jwks::decode(jwt)?;
+        if header.alg != "RS256" {
+            return Err(EnclaveError::Unauthorized(format!("Unsupported JWT algorithm {}", header.alg)));
+        }
+        let kid = header
+            .kid
+            .ok_or_else(|| EnclaveError::Unauthorized("JWT has no key ID".to_string()))?;
+        let payload: JwtPayload = serde_json::from_slice(&payload_bytes)
+            .map_err(|e| EnclaveError::InvalidInput(format!("JWT parse error: {}", e)))?;
+
+        let provider = self
+            .providers
+            .iter()
+            .find(|p| p.issuer == payload.iss)
+            .filter(|p| p.audiences.contains(&payload.aud))
+            .ok_or_else(|| {
+                warn!("zkLogin request from unsupported issuer {}", payload.iss);
+                EnclaveError::Unauthorized("Invalid or unsupported issuer or audience".to_string())
+            })?;
+        let key = self.jwks.key(&provider.issuer, &provider.source()?, &kid).await?;
+        jwks::verify_rs256(jwt, &key)?;
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| EnclaveError::InternalError(format!("Time error: {}", e)))?
+            .as_secs();
+        if payload.exp.saturating_add(self.leeway_secs) <= now {
+            return Err(EnclaveError::Unauthorized("JWT has expired".to_string()));
+        }
+        if payload.iat > now.saturating_add(self.leeway_secs) {
+            return Err(EnclaveError::Unauthorized("JWT is issued in the future".to_string()));
+        }
+        if let Some(expected) = expected_nonce {
+            if payload.nonce.as_deref() != Some(expected) {
+                return Err(EnclaveError::Unauthorized("JWT nonce does not match".to_string()));
+            }
+        }
+        Ok(VerifiedJwt { payload, kid, key })
     }
 
-    // Convert to big integer string (less than 2^128)
-    let salt_bigint = u128::from_be_bytes(salt_bytes);
-    let salt_string = salt_bigint.to_string();
+    /// Groth16-verify a prover response against the verified JWT's signing
+    /// key, `max_epoch`, the ephemeral key and the address seed it carries.
+    /// With `salt` (provers given this enclave's salt), the seed must also be
+    /// the one derived here from it and the JWT's `sub` and `aud`, so a prover
+    /// cannot move the user to another address.
+    pub fn verify_proof(
+        &self,
+        proof: &ZkProofResponse,
+        jwt: &VerifiedJwt,
+        salt: Option<&str>,
+        max_epoch: u64,
+        extended_ephemeral_key: &[u8],
+    ) -> Result<(), EnclaveError> {
+        let rejected = |reason: String| {
+            EnclaveError::UpstreamUnavailable(format!("Prover returned an invalid proof: {}", reason))
+        };
+        if let Some(salt) = salt {
+            let address_seed = gen_address_seed(salt, "sub", &jwt.payload.sub, &jwt.payload.aud)
+                .map_err(|e| EnclaveError::InternalError(format!("Cannot derive address seed: {}", e)))?;
+            if proof.address_seed != address_seed {
+                return Err(rejected("address seed does not match the user's salt".to_string()));
+            }
+        }
+        let encoded = serde_json::json!({
+            "proofPoints": proof.proof_points,
+            "issBase64Details": proof.iss_base64_details,
+            "headerBase64": proof.header_base64,
+        })
+        .to_string();
+        let inputs = ZkLoginInputs::from_json(&encoded, &proof.address_seed).map_err(|e| rejected(e.to_string()))?;
+
+        let jwk = JWK {
+            kty: "RSA".to_string(),
+            e: general_purpose::URL_SAFE_NO_PAD.encode(jwt.key.e().to_bytes_be()),
+            n: general_purpose::URL_SAFE_NO_PAD.encode(jwt.key.n().to_bytes_be()),
+            alg: "RS256".to_string(),
+        };
+        let mut jwks = ImHashMap::new();
+        jwks.insert(JwkId::new(jwt.payload.iss.clone(), jwt.kid.clone()), jwk);
+
+        verify_zk_login(&inputs, max_epoch, extended_ephemeral_key, &jwks, &self.env)
+            .map_err(|e| rejected(e.to_string()))
+    }
+}
 
-    info!("Generated salt for user {} from issuer {}", payload.sub, payload.iss);
+/// A JWT whose signature and claims passed [`ZkLoginService::verify_jwt`].
+pub struct VerifiedJwt {
+    pub payload: JwtPayload,
+    pub kid: String,
+    /// Provider key that signed it
+    pub key: RsaPublicKey,
+}
 
-    let response = SaltResponse {
-        salt: salt_string,
-    };
+/// Flag byte and public key, as hashed into the zkLogin nonce. A bare
+/// 32-byte key is taken as Ed25519 and given its flag.
+fn extended_ephemeral_key(ephemeral_public_key: &str) -> Result<Vec<u8>, EnclaveError> {
+    let mut public_key = general_purpose::STANDARD
+        .decode(ephemeral_public_key)
+        .map_err(|e| EnclaveError::InvalidInput(format!("Invalid ephemeral public key: {}", e)))?;
+    if public_key.len() == 32 {
+        public_key.insert(0, ED25519_FLAG);
+    }
+    Ok(public_key)
+}
 
-    (StatusCode::OK, Json(response)).into_response()
+/// Generate user salt using HKDF derivation under the requested seed version.
+pub async fn get_salt(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<SaltRequest>,
+) -> Result<Json<SaltResponse>, EnclaveError> {
+    let service = &state.zklogin;
+    // The nonce binds a JWT to an ephemeral key, which the salt request does not carry
+    let payload = service.verify_jwt(&request.jwt, None).await?.payload;
+    let version = request.version.unwrap_or(service.current_version);
+    let salt = service.derive_salt(&payload, version)?;
+
+    info!("Generated version {} salt for issuer {}", version, payload.iss);
+    Ok(Json(SaltResponse { salt, version }))
 }
 
-/// Generate ZK proof by calling Enoki API
+/// Generate a ZK proof with the configured prover and check it locally
+/// before returning it.
 pub async fn get_zk_proof(
-    State(_state): State<Arc<AppState>>,
+    State(state): State<Arc<AppState>>,
     Json(request): Json<ZkProofRequest>,
-) -> Response {
-    info!("ZK proof request received for Enoki API");
-
-    // Get Enoki API token
-    let enoki_token = match get_enoki_token() {
-        Ok(token) => token,
-        Err(e) => {
-            return error_response(StatusCode::INTERNAL_SERVER_ERROR, e);
+) -> Result<Json<ZkProofResponse>, EnclaveError> {
+    let service = &state.zklogin;
+    let extended_key = extended_ephemeral_key(&request.ephemeral_public_key)?;
+    let nonce = get_nonce(&extended_key, request.max_epoch, &request.randomness)
+        .map_err(|e| EnclaveError::InvalidInput(format!("Invalid nonce inputs: {}", e)))?;
+    let verified = service.verify_jwt(&request.jwt, Some(&nonce)).await?;
+    let version = request.salt_version.unwrap_or(service.current_version);
+    let salt = service.derive_salt(&verified.payload, version)?;
+
+    let proof = service
+        .prover
+        .prove(&ProofRequest {
+            jwt: &request.jwt,
+            ephemeral_public_key: &request.ephemeral_public_key,
+            extended_ephemeral_public_key: &extended_key,
+            max_epoch: request.max_epoch,
+            randomness: &request.randomness,
+            network: &request.network,
+            salt: &salt,
+            key_claim_value: &verified.payload.sub,
+            audience: &verified.payload.aud,
+        })
+        .await?;
+    let expected_salt = service.prover.uses_enclave_salt().then_some(salt.as_str());
+    service.verify_proof(&proof, &verified, expected_salt, request.max_epoch, &extended_key)?;
+
+    info!("ZK proof from {} prover verified", service.prover.name());
+    Ok(Json(proof))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use std::time::Duration;
+
+    const ISSUER: &str = "https://accounts.test.suiverify.local";
+    const AUDIENCE: &str = "suiverify-test-client";
+    /// zkLogin inputs every fixture token's nonce was computed from
+    const EPHEMERAL_KEY: &str = "ERERERERERERERERERERERERERERERERERERERERERE=";
+    const MAX_EPOCH: u64 = 10;
+    const RANDOMNESS: &str = "100681567828351849884072155819400689117";
+
+    struct NoProver;
+
+    impl ZkProver for NoProver {
+        fn name(&self) -> &str {
+            "none"
         }
-    };
-
-    // Create the request payload for Enoki API
-    let enoki_payload = EnokiZkProofRequest {
-        network: request.network,
-        ephemeral_public_key: request.ephemeral_public_key,
-        max_epoch: request.max_epoch,
-        randomness: request.randomness,
-    };
-
-    info!("Calling Enoki API zkLogin service...");
-
-    // Call the Enoki API
-    let client = Client::new();
-    let response = match client
-        .post(ENOKI_API_URL)
-        .header("Content-Type", "application/json")
-        .header("Authorization", format!("Bearer {}", enoki_token))
-        .header("zklogin-jwt", &request.jwt)
-        .json(&enoki_payload)
-        .send()
-        .await
-    {
-        Ok(response) => response,
-        Err(e) => {
-            return error_response(StatusCode::BAD_GATEWAY, &format!("Enoki API service error: {}", e));
+
+        fn prove<'a>(&'a self, _: &'a ProofRequest<'a>) -> BoxFuture<'a, Result<ZkProofResponse, EnclaveError>> {
+            Box::pin(async { Err(EnclaveError::InternalError("no prover in tests".to_string())) })
         }
-    };
+    }
 
-    if !response.status().is_success() {
-        let status_code = response.status();
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        error!("Enoki API returned error ({}): {}", status_code, error_text);
-        return error_response(StatusCode::BAD_GATEWAY, &format!("Enoki API failed ({}): {}", status_code, error_text));
+    fn fixture(name: &str) -> String {
+        format!("{}/fixtures/zklogin/{}", env!("CARGO_MANIFEST_DIR"), name)
     }
 
-    let enoki_response: EnokiZkProofResponse = match response.json().await {
-        Ok(resp) => resp,
-        Err(e) => {
-            return error_response(StatusCode::BAD_GATEWAY, &format!("Enoki API response parse error: {}", e));
-        }
-    };
+    fn token(name: &str) -> String {
+        std::fs::read_to_string(fixture(name)).unwrap().trim().to_string()
+    }
 
-    // Check if the proof data is present
-    let proof_points = match enoki_response.data.proof_points {
-        Some(points) => points,
-        None => {
-            return error_response(StatusCode::BAD_GATEWAY, "Enoki API returned null proof points");
+    fn service(jwks_file: String, min_refresh: Duration) -> ZkLoginService {
+        ZkLoginService {
+            seeds: BTreeMap::from([(1, SecretBytes::new(vec![9u8; 32]))]),
+            current_version: 1,
+            providers: vec![AllowedProvider {
+                issuer: ISSUER.to_string(),
+                audiences: vec![AUDIENCE.to_string()],
+                jwks_uri: None,
+                jwks_file: Some(jwks_file),
+            }],
+            jwks: JwksCache::new(
+                Duration::from_secs(3600),
+                min_refresh,
+                jwks::http_client(Duration::from_secs(5)).unwrap(),
+            ),
+            leeway_secs: 60,
+            prover: Box::new(NoProver),
+            env: zklogin_env(),
         }
-    };
+    }
 
-    let iss_base64_details = match enoki_response.data.iss_base64_details {
-        Some(details) => details,
-        None => {
-            return error_response(StatusCode::BAD_GATEWAY, "Enoki API returned null iss_base64_details");
+    async fn rejection(service: &ZkLoginService, name: &str, nonce: Option<&str>) -> String {
+        match service.verify_jwt(&token(name), nonce).await {
+            Err(e @ EnclaveError::Unauthorized(_)) => e.message().to_string(),
+            Err(other) => panic!("{} failed with {:?}, not Unauthorized", name, other.message()),
+            Ok(_) => panic!("{} was accepted", name),
         }
-    };
+    }
 
-    let header_base64 = match enoki_response.data.header_base64 {
-        Some(header) => header,
-        None => {
-            return error_response(StatusCode::BAD_GATEWAY, "Enoki API returned null header_base64");
-        }
-    };
+    fn fixture_nonce() -> String {
+        let key = extended_ephemeral_key(EPHEMERAL_KEY).unwrap();
+        get_nonce(&key, MAX_EPOCH, RANDOMNESS).unwrap()
+    }
+
+    #[tokio::test]
+    async fn accepts_valid_token_with_its_nonce() {
+        let service = service(fixture("jwks.json"), Duration::from_secs(60));
+        let verified = service.verify_jwt(&token("valid.jwt"), Some(&fixture_nonce())).await.unwrap();
+        assert_eq!(verified.payload.iss, ISSUER);
+        assert_eq!(verified.payload.aud, AUDIENCE);
+        let salt = service.derive_salt(&verified.payload, 1).unwrap();
+        assert_eq!(salt, service.derive_salt(&verified.payload, 1).unwrap());
+        assert!(service.derive_salt(&verified.payload, 2).is_err());
+    }
 
-    // Convert Enoki response to our expected format
-    let zk_proof = ZkProofResponse {
-        proof_points,
-        iss_base64_details,
-        header_base64,
-        address_seed: enoki_response.data.address_seed,
-    };
+    #[tokio::test]
+    async fn rejects_invalid_tokens() {
+        let service = service(fixture("jwks.json"), Duration::from_secs(60));
+        assert!(rejection(&service, "expired.jwt", None).await.contains("expired"));
+        assert!(rejection(&service, "wrong_audience.jwt", None).await.contains("audience"));
+        assert!(rejection(&service, "unknown_kid.jwt", None).await.contains("Unknown signing key"));
+        assert!(rejection(&service, "forged_payload.jwt", None).await.contains("signature is invalid"));
+        assert!(rejection(&service, "hs256.jwt", None).await.contains("algorithm"));
+        assert!(rejection(&service, "wrong_nonce.jwt", Some(&fixture_nonce())).await.contains("nonce"));
+        service.verify_jwt(&token("wrong_nonce.jwt"), None).await.unwrap();
+    }
 
-    info!("ZK proof generated successfully via Enoki API");
+    #[tokio::test]
+    async fn rejects_proof_for_another_address_seed() {
+        let service = service(fixture("jwks.json"), Duration::from_secs(60));
+        let verified = service.verify_jwt(&token("valid.jwt"), None).await.unwrap();
+        let salt = service.derive_salt(&verified.payload, 1).unwrap();
+        let other_salt = format!("{}1", salt);
+        let proof = ZkProofResponse {
+            proof_points: ProofPoints { a: vec![], b: vec![], c: vec![] },
+            iss_base64_details: IssBase64Details { value: String::new(), index_mod4: 0 },
+            header_base64: String::new(),
+            address_seed: gen_address_seed(&other_salt, "sub", &verified.payload.sub, AUDIENCE).unwrap(),
+        };
+        let key = extended_ephemeral_key(EPHEMERAL_KEY).unwrap();
+        match service.verify_proof(&proof, &verified, Some(&salt), MAX_EPOCH, &key) {
+            Err(e @ EnclaveError::UpstreamUnavailable(_)) => assert!(e.message().contains("address seed")),
+            other => panic!("unexpected outcome {:?}", other.map_err(|e| e.message().to_string())),
+        }
+    }
 
-    (StatusCode::OK, Json(zk_proof)).into_response()
-}
+    /// The Google key, ephemeral key and max epoch `google_proof.json` was made for.
+    fn google_proof() -> (ZkProofResponse, VerifiedJwt, Vec<u8>) {
+        let proof = std::fs::read_to_string(fixture("google_proof.json")).unwrap();
+        let n = general_purpose::URL_SAFE_NO_PAD
+            .decode(
+                "whYOFK2Ocbbpb_zVypi9SeKiNUqKQH0zTKN1-6fpCTu6ZalGI82s7XK3tan4dJt90ptUPKD2zvxqTzFNfx4HHHsrYCf2-FMLn1VTJfQa\
+                 zA2BvJqAwcpW1bqRUEty8tS_Yv4hRvWfQPcc2Gc3-_fQOOW57zVy-rNoJc744kb30NjQxdGp03J2S3GLQu7oKtSDDPooQHD38PEMNnIT\
+                 f0pj-KgDPjymkMGoJlO3aKppsjfbt_AH6GGdRghYRLOUwQU-h-ofWHR3lbYiKtXPn5dN24kiHy61e3VAQ9_YAZlwXC_99GGtw_NpghFA\
+                 uM4P1JDn0DppJldy3PGFC0GfBCZASw",
+            )
+            .unwrap();
+        let jwt = VerifiedJwt {
+            payload: JwtPayload {
+                iss: "https://accounts.google.com".to_string(),
+                sub: "106294049240999307923".to_string(),
+                aud: "25769832374-famecqrhe2gkebt5fvqms2263046lj96.apps.googleusercontent.com".to_string(),
+                exp: 0,
+                iat: 0,
+                nonce: None,
+            },
+            kid: "c9afda3682ebf09eb3055c1c4bd39b751fbf8195".to_string(),
+            key: RsaPublicKey::new(rsa::BigUint::from_bytes_be(&n), rsa::BigUint::from(65537u32)).unwrap(),
+        };
+        let ephemeral_key = "3598866369818193253063936208363210863933653800990958031560302098730308306242903464"
+            .parse::<num_bigint::BigUint>()
+            .unwrap()
+            .to_bytes_be();
+        (serde_json::from_str(&proof).unwrap(), jwt, [vec![ED25519_FLAG], ephemeral_key].concat())
+    }
+
+    #[tokio::test]
+    async fn verifies_proofs_with_the_provers_own_seed() {
+        let mut service = service(fixture("jwks.json"), Duration::from_secs(60));
+        service.env = ZkLoginEnv::Test;
+        let (proof, jwt, key) = google_proof();
+
+        // Enoki path: its seed comes from Enoki's salt, so only Groth16 applies
+        service.verify_proof(&proof, &jwt, None, 10_000, &key).unwrap();
+        assert!(!crate::zkprover::prover_from_env().unwrap().uses_enclave_salt());
+
+        // Same proof with a changed point and the correct seed
+        let mut tampered = proof.clone();
+        tampered.proof_points.a[0] = "1".to_string();
+        tampered.proof_points.b[0][0] = "1".to_string();
+        assert!(service.verify_proof(&tampered, &jwt, None, 10_000, &key).is_err());
+        // And the untouched proof for another epoch
+        assert!(service.verify_proof(&proof, &jwt, None, 10, &key).is_err());
+    }
 
-/// Validate if the issuer is supported
-fn is_valid_issuer(iss: &str) -> bool {
-    let valid_issuers = vec![
-        "https://accounts.google.com",
-        "https://www.facebook.com", 
-        "https://id.twitch.tv/oauth2",
-        "https://appleid.apple.com",
-        // Add more supported issuers as needed
-    ];
-    
-    valid_issuers.contains(&iss)
+    #[tokio::test]
+    async fn picks_up_rotated_keys_on_refresh() {
+        let path = std::env::temp_dir().join(format!("jwks-rotation-{}.json", std::process::id()));
+        std::fs::copy(fixture("jwks.json"), &path).unwrap();
+        let service = service(path.to_string_lossy().into_owned(), Duration::ZERO);
+
+        assert!(rejection(&service, "rotated_kid.jwt", None).await.contains("Unknown signing key"));
+        std::fs::copy(fixture("jwks_rotated.json"), &path).unwrap();
+        let verified = service.verify_jwt(&token("rotated_kid.jwt"), None).await;
+        let _ = std::fs::remove_file(&path);
+        assert!(verified.is_ok());
+    }
 }
-*/
\ No newline at end of file
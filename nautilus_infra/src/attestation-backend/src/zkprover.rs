@@ -0,0 +1,270 @@
+// zkprover.rs
+use crate::jwks::{env_secs, http_client};
+use crate::zklogin::{IssBase64Details, ProofPoints, ZkProofResponse};
+use crate::EnclaveError;
+use base64::{engine::general_purpose, Engine as _};
+use fastcrypto_zkp::bn254::utils::gen_address_seed;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+const DEFAULT_ENOKI_API_URL: &str = "https://api.enoki.mystenlabs.com/v1/zklogin/zkp";
+
+/// Inputs to a zkLogin proof for a JWT that has already been verified.
+pub struct ProofRequest<'a> {
+    pub jwt: &'a str,
+    /// Ephemeral public key as the client sent it
+    pub ephemeral_public_key: &'a str,
+    /// Flag byte followed by the ephemeral public key
+    pub extended_ephemeral_public_key: &'a [u8],
+    pub max_epoch: u64,
+    pub randomness: &'a str,
+    pub network: &'a str,
+    /// User salt from the salt service, for provers that need it
+    pub salt: &'a str,
+    /// Claim the address is derived from (`sub`) and its value and audience
+    pub key_claim_value: &'a str,
+    pub audience: &'a str,
+}
+
+/// Produces zkLogin proofs. Responses are untrusted: the caller verifies them
+/// before they reach the user.
+pub trait ZkProver: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn prove<'a>(&'a self, request: &'a ProofRequest<'a>) -> BoxFuture<'a, Result<ZkProofResponse, EnclaveError>>;
+
+    /// Whether proofs use the salt from this enclave's salt service, so that
+    /// their address seed can be checked against it. Provers that manage
+    /// their own salt (Enoki) return a seed the enclave cannot derive.
+    fn uses_enclave_salt(&self) -> bool {
+        false
+    }
+}
+
+/// Build the prover selected by `ZK_PROVER` (`enoki`, `self_hosted`, or `mock`
+/// in builds with the `mock` feature). Calls time out after
+/// `ZK_PROVER_TIMEOUT_SECS` (default 30).
+pub fn prover_from_env() -> Result<Box<dyn ZkProver>, EnclaveError> {
+    let http = http_client(env_secs("ZK_PROVER_TIMEOUT_SECS", 30))?;
+    let prover: Box<dyn ZkProver> = match std::env::var("ZK_PROVER").as_deref() {
+        Ok("self_hosted") => Box::new(SelfHostedProver {
+            url: std::env::var("ZK_PROVER_URL").map_err(|_| {
+                EnclaveError::InternalError("ZK_PROVER_URL is required for the self-hosted prover".to_string())
+            })?,
+            http,
+        }),
+        #[cfg(feature = "mock")]
+        Ok("mock") => {
+            let path = std::env::var("ZK_PROVER_MOCK_FILE").map_err(|_| {
+                EnclaveError::InternalError("ZK_PROVER_MOCK_FILE is required for the mock prover".to_string())
+            })?;
+            let response = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|c| serde_json::from_str(&c).map_err(|e| e.to_string()))
+                .map_err(|e| EnclaveError::InternalError(format!("Cannot load mock proof {}: {}", path, e)))?;
+            Box::new(MockProver { response })
+        }
+        #[cfg(not(feature = "mock"))]
+        Ok("mock") => {
+            tracing::warn!("ZK_PROVER=mock needs the mock feature; using the enoki prover");
+            Box::new(EnokiProver::from_env(http))
+        }
+        _ => Box::new(EnokiProver::from_env(http)),
+    };
+    info!("Using {} zkLogin prover", prover.name());
+    Ok(prover)
+}
+
+fn upstream(prover: &str, reason: String) -> EnclaveError {
+    EnclaveError::UpstreamUnavailable(format!("{} prover {}", prover, reason))
+}
+
+/// POST `body` as JSON and decode the JSON response.
+async fn post_json<B: Serialize, R: for<'de> Deserialize<'de>>(
+    prover: &str,
+    request: reqwest::RequestBuilder,
+    body: &B,
+) -> Result<R, EnclaveError> {
+    let response = request
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| upstream(prover, format!("service error: {}", e)))?;
+    if !response.status().is_success() {
+        let status_code = response.status();
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(upstream(prover, format!("failed ({}): {}", status_code, error_text)));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| upstream(prover, format!("response parse error: {}", e)))
+}
+
+/// Mysten Labs' hosted Enoki API, which also manages the salt.
+pub struct EnokiProver {
+    url: String,
+    token: Option<String>,
+    http: reqwest::Client,
+}
+
+// Enoki API request structure
+#[derive(Debug, Serialize)]
+struct EnokiZkProofRequest<'a> {
+    network: &'a str,
+    #[serde(rename = "ephemeralPublicKey")]
+    ephemeral_public_key: &'a str,
+    #[serde(rename = "maxEpoch")]
+    max_epoch: u64,
+    randomness: &'a str,
+}
+
+// Enoki API response structure
+#[derive(Debug, Deserialize)]
+struct EnokiZkProofResponse {
+    data: EnokiZkProofData,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnokiZkProofData {
+    #[serde(rename = "proofPoints")]
+    proof_points: Option<ProofPoints>,
+    #[serde(rename = "issBase64Details")]
+    iss_base64_details: Option<IssBase64Details>,
+    #[serde(rename = "headerBase64")]
+    header_base64: Option<String>,
+    #[serde(rename = "addressSeed")]
+    address_seed: String,
+}
+
+impl EnokiProver {
+    /// Load from `ENOKI_API_URL` (default the hosted API) and `ENOKI_API_TOKEN`.
+    fn from_env(http: reqwest::Client) -> Self {
+        Self {
+            url: std::env::var("ENOKI_API_URL").unwrap_or_else(|_| DEFAULT_ENOKI_API_URL.to_string()),
+            token: std::env::var("ENOKI_API_TOKEN").ok().filter(|t| !t.is_empty()),
+            http,
+        }
+    }
+}
+
+impl ZkProver for EnokiProver {
+    fn name(&self) -> &str {
+        "enoki"
+    }
+
+    fn prove<'a>(&'a self, request: &'a ProofRequest<'a>) -> BoxFuture<'a, Result<ZkProofResponse, EnclaveError>> {
+        Box::pin(async move {
+            let token = self
+                .token
+                .as_deref()
+                .ok_or_else(|| EnclaveError::InternalError("ENOKI_API_TOKEN not set".to_string()))?;
+            let body = EnokiZkProofRequest {
+                network: request.network,
+                ephemeral_public_key: request.ephemeral_public_key,
+                max_epoch: request.max_epoch,
+                randomness: request.randomness,
+            };
+            let builder = self
+                .http
+                .post(&self.url)
+                .header("Authorization", format!("Bearer {}", token))
+                .header("zklogin-jwt", request.jwt);
+            let data = post_json::<_, EnokiZkProofResponse>(self.name(), builder, &body).await?.data;
+
+            let missing = |field: &str| upstream(self.name(), format!("returned null {}", field));
+            Ok(ZkProofResponse {
+                proof_points: data.proof_points.ok_or_else(|| missing("proof points"))?,
+                iss_base64_details: data.iss_base64_details.ok_or_else(|| missing("iss_base64_details"))?,
+                header_base64: data.header_base64.ok_or_else(|| missing("header_base64"))?,
+                address_seed: data.address_seed,
+            })
+        })
+    }
+}
+
+/// A self-hosted instance of the Mysten zkLogin prover (`/v1` endpoint),
+/// given the salt from this enclave's salt service.
+pub struct SelfHostedProver {
+    url: String,
+    http: reqwest::Client,
+}
+
+#[derive(Debug, Serialize)]
+struct SelfHostedRequest<'a> {
+    jwt: &'a str,
+    #[serde(rename = "extendedEphemeralPublicKey")]
+    extended_ephemeral_public_key: String,
+    #[serde(rename = "maxEpoch")]
+    max_epoch: String,
+    #[serde(rename = "jwtRandomness")]
+    jwt_randomness: &'a str,
+    salt: &'a str,
+    #[serde(rename = "keyClaimName")]
+    key_claim_name: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct SelfHostedResponse {
+    #[serde(rename = "proofPoints")]
+    proof_points: ProofPoints,
+    #[serde(rename = "issBase64Details")]
+    iss_base64_details: IssBase64Details,
+    #[serde(rename = "headerBase64")]
+    header_base64: String,
+}
+
+impl ZkProver for SelfHostedProver {
+    fn name(&self) -> &str {
+        "self-hosted"
+    }
+
+    fn uses_enclave_salt(&self) -> bool {
+        true
+    }
+
+    fn prove<'a>(&'a self, request: &'a ProofRequest<'a>) -> BoxFuture<'a, Result<ZkProofResponse, EnclaveError>> {
+        Box::pin(async move {
+            let body = SelfHostedRequest {
+                jwt: request.jwt,
+                extended_ephemeral_public_key: general_purpose::STANDARD
+                    .encode(request.extended_ephemeral_public_key),
+                max_epoch: request.max_epoch.to_string(),
+                jwt_randomness: request.randomness,
+                salt: request.salt,
+                key_claim_name: "sub",
+            };
+            let response: SelfHostedResponse =
+                post_json(self.name(), self.http.post(&self.url), &body).await?;
+
+            // The prover does not return the seed; derive it from the same salt
+            let address_seed = gen_address_seed(request.salt, "sub", request.key_claim_value, request.audience)
+                .map_err(|e| EnclaveError::InvalidInput(format!("Cannot derive address seed: {}", e)))?;
+            Ok(ZkProofResponse {
+                proof_points: response.proof_points,
+                iss_base64_details: response.iss_base64_details,
+                header_base64: response.header_base64,
+                address_seed,
+            })
+        })
+    }
+}
+
+/// Replays a canned response from a file. Only compiled into builds with the
+/// `mock` feature.
+#[cfg(feature = "mock")]
+pub struct MockProver {
+    response: ZkProofResponse,
+}
+
+#[cfg(feature = "mock")]
+impl ZkProver for MockProver {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn prove<'a>(&'a self, _request: &'a ProofRequest<'a>) -> BoxFuture<'a, Result<ZkProofResponse, EnclaveError>> {
+        Box::pin(async move { Ok(self.response.clone()) })
+    }
+}
@@ -9,7 +9,8 @@ import {
   EnclaveSignature,
   DIDMetadata,
   VerificationOptions,
-  SDKError
+  SDKError,
+  IntentScope
 } from './types';
 
 // Simplified client interface for demonstration
@@ -157,6 +158,61 @@ export class SuiVerifySDK {
     }
   }
 
+  /**
+   * Verify a DID NFT's Nautilus signature on-chain with did_registry::verify_nft_signature,
+   * which signs IntentMessage<DIDIssuance { owner, did_type, evidence_hash }> under
+   * IntentScope.DIDIssuance
+   */
+  async verifyDIDNFTSignatureOnChain(
+    nftObjectId: string,
+    enclaveObjectId: string
+  ): Promise<VerificationResult> {
+    try {
+      const tx = await this.client.devInspectTransactionBlock({
+        transactionBlock: {
+          target: `${this.config.packageId}::did_registry::verify_nft_signature`,
+          typeArguments: [`${this.config.packageId}::enclave::ENCLAVE`],
+          arguments: [nftObjectId, enclaveObjectId]
+        },
+        sender: '0x0000000000000000000000000000000000000000000000000000000000000000'
+      });
+
+      // devInspect succeeds whether or not the signature is valid; the answer
+      // is the function's BCS-encoded bool return value
+      if (tx.effects?.status?.status !== 'success') {
+        return {
+          isValid: false,
+          message: `On-chain verification failed: ${tx.effects?.status?.error || 'unknown error'}`
+        };
+      }
+      const isValid = this.decodeBool(tx.results?.[0]?.returnValues?.[0]);
+      return {
+        isValid,
+        message: isValid ? 'DID signature verified on-chain' : 'DID signature does not match the enclave key',
+        data: {
+          intentScope: IntentScope.DIDIssuance,
+          functionCall: `${this.config.packageId}::did_registry::verify_nft_signature`
+        }
+      };
+    } catch (error) {
+      return {
+        isValid: false,
+        message: `On-chain verification error: ${error instanceof Error ? error.message : 'Unknown error'}`
+      };
+    }
+  }
+
+  /**
+   * Decode a devInspect return value `[bytes, type]` holding a BCS `bool`
+   */
+  private decodeBool(returnValue: [number[], string] | undefined): boolean {
+    if (!returnValue) {
+      return false;
+    }
+    const [bytes, type] = returnValue;
+    return type === 'bool' && bytes.length === 1 && bytes[0] === 1;
+  }
+
   /**
    * Helper function to encode payload for Move function call
    */
@@ -2,6 +2,16 @@
  * SuiVerify SDK Type Definitions
  */
 
+/**
+ * Intent scopes of Nautilus enclave signatures
+ */
+export enum IntentScope {
+  Generic = 0,
+  KYCVerification = 1,
+  /** IntentMessage<DIDIssuance { owner, did_type, evidence_hash }> on a DID NFT */
+  DIDIssuance = 2
+}
+
 export interface VerificationResult {
   isValid: boolean;
   message: string;